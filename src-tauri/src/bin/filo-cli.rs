@@ -0,0 +1,569 @@
+//! デスクトップ環境のないサーバー（cron・スクリプト）から、GUI と同じルールファイルで
+//! ルールセットを実行するためのコマンドライン版。結果は JSON で標準出力に書き出す。
+
+use filo_lib::engine::{self, ExecutionPlan, ExecutionResult, ExecutionStatus, PlannedAction};
+use filo_lib::journal::{self, Journal};
+use filo_lib::ruleset::{default_rulesets_path, Ruleset, RulesetFile, ValidationIssue};
+use serde::Serialize;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+
+const USAGE: &str = "\
+Usage: filo-cli [OPTIONS] <COMMAND>
+
+Commands:
+  list                 List all rulesets
+  validate             Validate all rulesets
+  run <ID|NAME>        Execute a ruleset (disabled rulesets can also be run)
+  run-all              Execute all enabled rulesets in order
+  dry-run [ID|NAME]    Show planned operations without touching files
+                       (all enabled rulesets when omitted)
+
+Options:
+  --rules <PATH>       Rules file (default: the same file as the desktop app)
+  --no-history         Do not record runs in the undo history
+  -h, --help           Print help
+
+Exit codes:
+  0   Completed
+  1   Partially failed (some files could not be processed)
+  2   Failed
+  3   Invalid ruleset
+  4   Rules file could not be loaded or ruleset not found
+  64  Invalid arguments";
+
+const EXIT_OK: i32 = 0;
+const EXIT_PARTIAL_FAILURE: i32 = 1;
+const EXIT_FAILED: i32 = 2;
+const EXIT_INVALID: i32 = 3;
+const EXIT_NOT_FOUND: i32 = 4;
+const EXIT_USAGE: i32 = 64;
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    List,
+    Validate,
+    Run(String),
+    RunAll,
+    DryRun(Option<String>),
+    Help,
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    rules_path: PathBuf,
+    record_history: bool,
+    command: Command,
+}
+
+#[derive(Serialize)]
+struct ValidationReport {
+    id: String,
+    name: String,
+    valid: bool,
+    error: Option<String>,
+    /// 項目ごとの問題。警告のみの場合も含む
+    issues: Vec<ValidationIssue>,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut rules_path = None;
+    let mut record_history = true;
+    let mut positional = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--rules" => {
+                let path = iter.next().ok_or("--rules requires a path")?;
+                rules_path = Some(PathBuf::from(path));
+            }
+            "--no-history" => record_history = false,
+            "-h" | "--help" => positional.insert(0, "help".to_string()),
+            a if a.starts_with('-') => return Err(format!("unknown option: {}", a)),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let command = match positional.next().as_deref() {
+        Some("help") => Command::Help,
+        Some("list") => Command::List,
+        Some("validate") => Command::Validate,
+        Some("run") => Command::Run(
+            positional
+                .next()
+                .ok_or("run requires a ruleset id or name")?,
+        ),
+        Some("run-all") => Command::RunAll,
+        Some("dry-run") => Command::DryRun(positional.next()),
+        Some(other) => return Err(format!("unknown command: {}", other)),
+        None => return Err("no command given".to_string()),
+    };
+    if command != Command::Help {
+        if let Some(extra) = positional.next() {
+            return Err(format!("unexpected argument: {}", extra));
+        }
+    }
+
+    Ok(Options {
+        rules_path: rules_path.unwrap_or_else(default_rulesets_path),
+        record_history,
+        command,
+    })
+}
+
+/// ID の完全一致を優先し、なければ名前の完全一致で探す。名前が重複する場合はエラーにする。
+fn find_ruleset<'a>(rulesets: &'a [Ruleset], key: &str) -> Result<&'a Ruleset, String> {
+    if let Some(rs) = rulesets.iter().find(|r| r.id == key) {
+        return Ok(rs);
+    }
+    let mut by_name = rulesets.iter().filter(|r| r.name == key);
+    match (by_name.next(), by_name.next()) {
+        (Some(rs), None) => Ok(rs),
+        (Some(_), Some(_)) => Err(format!(
+            "multiple rulesets are named '{}'; specify the id instead",
+            key
+        )),
+        (None, _) => Err(format!("ruleset not found: {}", key)),
+    }
+}
+
+fn status_exit_code(status: &ExecutionStatus) -> i32 {
+    match status {
+        ExecutionStatus::Completed => EXIT_OK,
+        ExecutionStatus::PartialFailure => EXIT_PARTIAL_FAILURE,
+        ExecutionStatus::Failed => EXIT_FAILED,
+    }
+}
+
+/// 複数の実行結果のうち最も悪い状態の終了コードを返す。
+fn worst_exit_code(results: &[ExecutionResult]) -> i32 {
+    results
+        .iter()
+        .map(|r| status_exit_code(&r.status))
+        .max()
+        .unwrap_or(EXIT_OK)
+}
+
+/// プレビューの終了コードを、実行した場合と同じ基準で返す。
+/// エラーになる操作があれば、移動・コピーできる操作が残るかで一部失敗か失敗かを分ける。
+fn plan_exit_code(plan: &ExecutionPlan) -> i32 {
+    let has_error = plan
+        .operations
+        .iter()
+        .any(|op| op.action == PlannedAction::Error);
+    let has_transfer = plan
+        .operations
+        .iter()
+        .any(|op| matches!(op.action, PlannedAction::Move | PlannedAction::Copy));
+    match (has_error, has_transfer) {
+        (false, _) => EXIT_OK,
+        (true, true) => EXIT_PARTIAL_FAILURE,
+        (true, false) => EXIT_FAILED,
+    }
+}
+
+/// ルールセットをすべて検証し、不正なものがあれば `EXIT_INVALID` を返す。
+fn validate_all(rulesets: &[&Ruleset], err: &mut impl Write) -> Result<(), i32> {
+    for rs in rulesets {
+        if let Err(e) = rs.validate() {
+            let _ = writeln!(err, "error: ruleset '{}' is invalid: {}", rs.name, e);
+            return Err(EXIT_INVALID);
+        }
+    }
+    Ok(())
+}
+
+fn write_json(out: &mut impl Write, value: &impl Serialize) {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => {
+            let _ = writeln!(out, "{}", json);
+        }
+        Err(e) => {
+            let _ = writeln!(out, "{}", serde_json::json!({ "error": e.to_string() }));
+        }
+    }
+}
+
+fn execute(ruleset: &Ruleset, record_history: bool) -> ExecutionResult {
+    // CLI では中断要求を受け付けない（プロセスの終了で止める）
+    let cancel = AtomicBool::new(false);
+    let result = engine::execute_ruleset(ruleset, |_, _, _, _| {}, &cancel);
+    if record_history {
+        if let Err(e) = Journal::new(journal::default_history_dir()).record(&result) {
+            eprintln!("warning: failed to record execution history: {}", e);
+        }
+    }
+    result
+}
+
+fn run(args: &[String], out: &mut impl Write, err: &mut impl Write) -> i32 {
+    let options = match parse_args(args) {
+        Ok(o) => o,
+        Err(e) => {
+            let _ = writeln!(err, "error: {}\n\n{}", e, USAGE);
+            return EXIT_USAGE;
+        }
+    };
+    if options.command == Command::Help {
+        let _ = writeln!(out, "{}", USAGE);
+        return EXIT_OK;
+    }
+
+    let file = match RulesetFile::load(&options.rules_path) {
+        Ok(f) => f,
+        Err(e) => {
+            let _ = writeln!(
+                err,
+                "error: failed to load {}: {}",
+                options.rules_path.display(),
+                e
+            );
+            return EXIT_NOT_FOUND;
+        }
+    };
+
+    match options.command {
+        Command::Help => EXIT_OK,
+        Command::List => {
+            write_json(out, &file.rulesets);
+            EXIT_OK
+        }
+        Command::Validate => {
+            let reports: Vec<ValidationReport> = file
+                .rulesets
+                .iter()
+                .map(|rs| {
+                    let error = rs.validate().err().map(|e| e.to_string());
+                    ValidationReport {
+                        id: rs.id.clone(),
+                        name: rs.name.clone(),
+                        valid: error.is_none(),
+                        error,
+                        issues: rs.validation_issues(),
+                    }
+                })
+                .collect();
+            let all_valid = reports.iter().all(|r| r.valid);
+            write_json(out, &reports);
+            if all_valid {
+                EXIT_OK
+            } else {
+                EXIT_INVALID
+            }
+        }
+        Command::Run(key) => {
+            let ruleset = match find_ruleset(&file.rulesets, &key) {
+                Ok(rs) => rs,
+                Err(e) => {
+                    let _ = writeln!(err, "error: {}", e);
+                    return EXIT_NOT_FOUND;
+                }
+            };
+            if let Err(code) = validate_all(&[ruleset], err) {
+                return code;
+            }
+            let result = execute(ruleset, options.record_history);
+            write_json(out, &result);
+            status_exit_code(&result.status)
+        }
+        Command::RunAll => {
+            let rulesets: Vec<&Ruleset> = file.rulesets.iter().filter(|r| r.enabled).collect();
+            // 途中で不正なルールセットに当たって一部だけ実行されることを避けるため、先にすべて検証する
+            if let Err(code) = validate_all(&rulesets, err) {
+                return code;
+            }
+            let results: Vec<ExecutionResult> = rulesets
+                .iter()
+                .map(|rs| execute(rs, options.record_history))
+                .collect();
+            write_json(out, &results);
+            worst_exit_code(&results)
+        }
+        Command::DryRun(key) => {
+            let targets: Vec<&Ruleset> = match key {
+                Some(key) => match find_ruleset(&file.rulesets, &key) {
+                    Ok(rs) => vec![rs],
+                    Err(e) => {
+                        let _ = writeln!(err, "error: {}", e);
+                        return EXIT_NOT_FOUND;
+                    }
+                },
+                None => file.rulesets.iter().filter(|r| r.enabled).collect(),
+            };
+            // 実行と同じく、不正なルールセットは予定を表示しない
+            if let Err(code) = validate_all(&targets, err) {
+                return code;
+            }
+            let plans: Vec<_> = targets
+                .iter()
+                .map(|rs| engine::preview_ruleset(rs))
+                .collect();
+            write_json(out, &plans);
+            plans.iter().map(plan_exit_code).max().unwrap_or(EXIT_OK)
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let code = run(&args, &mut std::io::stdout(), &mut std::io::stderr());
+    std::process::exit(code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn write_rules(dir: &Path, src: &Path, dst: &Path) -> PathBuf {
+        let yaml = format!(
+            r#"
+version: 1
+rulesets:
+  - id: "id-txt"
+    name: "texts"
+    enabled: true
+    source_dir: "{}"
+    destination_dir: "{}"
+    action: move
+    filters:
+      extensions:
+        - ".txt"
+  - id: "id-disabled"
+    name: "disabled"
+    enabled: false
+    source_dir: "{}"
+    destination_dir: "{}"
+    action: copy
+    filters:
+      extensions:
+        - ".log"
+"#,
+            src.display(),
+            dst.display(),
+            src.display(),
+            dst.display()
+        );
+        let path = dir.join("rules.yaml");
+        fs::write(&path, yaml).unwrap();
+        path
+    }
+
+    fn run_cli(list: &[&str]) -> (i32, String, String) {
+        let mut out = Vec::new();
+        let mut err = Vec::new();
+        let code = run(&args(list), &mut out, &mut err);
+        (
+            code,
+            String::from_utf8(out).unwrap(),
+            String::from_utf8(err).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args(&args(&["--rules", "/tmp/r.yaml", "run", "texts"])).unwrap();
+        assert_eq!(options.rules_path, PathBuf::from("/tmp/r.yaml"));
+        assert!(options.record_history);
+        assert_eq!(options.command, Command::Run("texts".to_string()));
+
+        let options = parse_args(&args(&["dry-run", "--no-history"])).unwrap();
+        assert_eq!(options.command, Command::DryRun(None));
+        assert!(!options.record_history);
+
+        assert!(parse_args(&args(&["run"])).is_err());
+        assert!(parse_args(&args(&["frobnicate"])).is_err());
+        assert!(parse_args(&args(&["list", "extra"])).is_err());
+        assert!(parse_args(&args(&[])).is_err());
+    }
+
+    #[test]
+    fn test_usage_error_exit_code() {
+        let (code, _, err) = run_cli(&["--bogus"]);
+        assert_eq!(code, EXIT_USAGE);
+        assert!(err.contains("unknown option"));
+    }
+
+    #[test]
+    fn test_missing_rules_file() {
+        let (code, _, _) = run_cli(&["--rules", "/nonexistent/filo-rules.yaml", "list"]);
+        assert_eq!(code, EXIT_NOT_FOUND);
+    }
+
+    #[test]
+    fn test_list_and_validate() {
+        let dir = tempfile::tempdir().unwrap();
+        let rules = write_rules(dir.path(), dir.path(), dir.path());
+        let rules = rules.to_str().unwrap();
+
+        let (code, out, _) = run_cli(&["--rules", rules, "list"]);
+        assert_eq!(code, EXIT_OK);
+        let listed: Vec<Ruleset> = serde_json::from_str(&out).unwrap();
+        assert_eq!(listed.len(), 2);
+
+        let (code, out, _) = run_cli(&["--rules", rules, "validate"]);
+        assert_eq!(code, EXIT_OK);
+        assert!(out.contains("\"valid\": true"));
+    }
+
+    #[test]
+    fn test_validate_reports_invalid_ruleset() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rules.yaml");
+        fs::write(
+            &path,
+            r#"
+version: 1
+rulesets:
+  - id: "bad"
+    name: ""
+    enabled: true
+    source_dir: "/src"
+    destination_dir: "/dst"
+    action: move
+    filters:
+      extensions:
+        - ".txt"
+"#,
+        )
+        .unwrap();
+        let rules = path.to_str().unwrap();
+
+        let (code, out, _) = run_cli(&["--rules", rules, "validate"]);
+        assert_eq!(code, EXIT_INVALID);
+        assert!(out.contains("\"field\": \"name\""));
+        let (code, _, _) = run_cli(&["--rules", rules, "--no-history", "run", "bad"]);
+        assert_eq!(code, EXIT_INVALID);
+        let (code, out, err) = run_cli(&["--rules", rules, "dry-run", "bad"]);
+        assert_eq!(code, EXIT_INVALID);
+        assert!(out.is_empty());
+        assert!(err.contains("invalid"));
+        let (code, _, _) = run_cli(&["--rules", rules, "dry-run"]);
+        assert_eq!(code, EXIT_INVALID);
+    }
+
+    #[test]
+    fn test_dry_run_then_run_by_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        fs::write(src.path().join("a.txt"), "content").unwrap();
+        let rules = write_rules(dir.path(), src.path(), dst.path());
+        let rules = rules.to_str().unwrap();
+
+        let (code, out, _) = run_cli(&["--rules", rules, "dry-run"]);
+        assert_eq!(code, EXIT_OK);
+        assert!(out.contains("a.txt"));
+        assert!(src.path().join("a.txt").exists());
+
+        let (code, out, _) = run_cli(&["--rules", rules, "--no-history", "run", "texts"]);
+        assert_eq!(code, EXIT_OK);
+        let result: ExecutionResult = serde_json::from_str(&out).unwrap();
+        assert_eq!(result.succeeded.len(), 1);
+        assert!(dst.path().join("a.txt").exists());
+    }
+
+    #[test]
+    fn test_run_unknown_ruleset() {
+        let dir = tempfile::tempdir().unwrap();
+        let rules = write_rules(dir.path(), dir.path(), dir.path());
+        let (code, _, err) = run_cli(&[
+            "--rules",
+            rules.to_str().unwrap(),
+            "--no-history",
+            "run",
+            "nope",
+        ]);
+        assert_eq!(code, EXIT_NOT_FOUND);
+        assert!(err.contains("not found"));
+    }
+
+    #[test]
+    fn test_run_all_exit_code_reflects_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        // ソースディレクトリが存在しない → Failed
+        let missing = dir.path().join("missing");
+        let rules = write_rules(dir.path(), &missing, dst.path());
+
+        let (code, out, _) = run_cli(&[
+            "--rules",
+            rules.to_str().unwrap(),
+            "--no-history",
+            "run-all",
+        ]);
+        assert_eq!(code, EXIT_FAILED);
+        // 無効なルールセットは実行されない
+        let results: Vec<ExecutionResult> = serde_json::from_str(&out).unwrap();
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    fn test_dry_run_exit_code_reflects_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing");
+        let rules = write_rules(dir.path(), &missing, dst.path());
+
+        let (code, out, _) = run_cli(&["--rules", rules.to_str().unwrap(), "dry-run"]);
+        assert_eq!(code, EXIT_FAILED);
+        let plans: Vec<ExecutionPlan> = serde_json::from_str(&out).unwrap();
+        assert_eq!(plans.len(), 1);
+    }
+
+    #[test]
+    fn test_plan_exit_code() {
+        let operation = |action| engine::PlannedOperation {
+            filename: "a.txt".to_string(),
+            source_path: PathBuf::from("a.txt"),
+            destination_path: None,
+            action,
+            reason: None,
+            conflict: None,
+        };
+        let plan = |actions: Vec<PlannedAction>| ExecutionPlan {
+            ruleset_id: "id".to_string(),
+            ruleset_name: "name".to_string(),
+            action: filo_lib::ruleset::Action::Move,
+            operations: actions.into_iter().map(operation).collect(),
+        };
+
+        assert_eq!(plan_exit_code(&plan(vec![])), EXIT_OK);
+        assert_eq!(
+            plan_exit_code(&plan(vec![PlannedAction::Move, PlannedAction::Skip])),
+            EXIT_OK
+        );
+        assert_eq!(
+            plan_exit_code(&plan(vec![PlannedAction::Move, PlannedAction::Error])),
+            EXIT_PARTIAL_FAILURE
+        );
+        assert_eq!(
+            plan_exit_code(&plan(vec![PlannedAction::Skip, PlannedAction::Error])),
+            EXIT_FAILED
+        );
+    }
+
+    #[test]
+    fn test_find_ruleset_prefers_id_and_rejects_ambiguous_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let rules = write_rules(dir.path(), dir.path(), dir.path());
+        let mut file = RulesetFile::load(&rules).unwrap();
+
+        assert_eq!(
+            find_ruleset(&file.rulesets, "id-txt").unwrap().name,
+            "texts"
+        );
+        assert_eq!(find_ruleset(&file.rulesets, "texts").unwrap().id, "id-txt");
+
+        file.rulesets[1].name = "texts".to_string();
+        assert!(find_ruleset(&file.rulesets, "texts").is_err());
+    }
+}
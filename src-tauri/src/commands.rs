@@ -1,5 +1,8 @@
-use crate::engine::{self, ExecutionResult, UndoRequest};
-use crate::ruleset::{Ruleset, RulesetFile};
+use crate::engine::{self, ExecutionPlan, ExecutionResult, UndoRequest};
+use crate::journal::{self, Journal, RunRecord};
+use crate::ruleset::{default_rulesets_path, Ruleset, RulesetFile, ValidationIssue};
+use crate::scheduler::{self, Scheduler};
+use crate::watcher::{self, RulesetWatcher};
 use serde::Serialize;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -16,19 +19,26 @@ struct ExecutionProgressPayload {
     bytes_per_second: f64,
 }
 
+/// 監視・スケジュール・履歴の記録など、画面の操作の結果として返せないエラーを通知するイベント
+const BACKGROUND_ERROR_EVENT: &str = "background-error";
+
+/// 画面がイベントを受け取れるようになるまでに起きたエラー。起動直後の監視開始の失敗などは
+/// 画面の読み込み前に起きるため、`take_background_errors` で受け取られるまでここに溜める。
+/// 受け取られた後は `None` になり、以降のエラーはイベントで直接通知する。
+static PENDING_ERRORS: Mutex<Option<Vec<String>>> = Mutex::new(Some(Vec::new()));
+
 static RULESETS: Mutex<Option<(PathBuf, RulesetFile)>> = Mutex::new(None);
 
 /// 実行中断フラグ。execute_ruleset / execute_all の開始時に false にリセットされ、
 /// cancel_execution コマンドで true にセットされる。
 static CANCEL_FLAG: AtomicBool = AtomicBool::new(false);
 
-fn default_rulesets_path() -> PathBuf {
-    dirs::config_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("filo")
-        .join("rulesets")
-        .join("filo-rules.yaml")
-}
+/// 手動実行と監視による自動実行が同じファイルを同時に処理しないよう、実行を直列化する。
+static EXECUTION_LOCK: Mutex<()> = Mutex::new(());
+
+static WATCHER: Mutex<Option<RulesetWatcher>> = Mutex::new(None);
+
+static SCHEDULER: Mutex<Option<Scheduler>> = Mutex::new(None);
 
 fn load_rulesets() -> Result<(PathBuf, RulesetFile), String> {
     let mut guard = RULESETS.lock().map_err(|e| e.to_string())?;
@@ -50,6 +60,116 @@ fn load_rulesets() -> Result<(PathBuf, RulesetFile), String> {
     Ok((path, file))
 }
 
+fn journal() -> Journal {
+    Journal::new(journal::default_history_dir())
+}
+
+/// 画面の操作の結果として返せないエラーを画面に通知する。GUI では標準エラー出力を見る人がいないため。
+fn notify_error(app: &tauri::AppHandle, message: String) {
+    let mut pending = PENDING_ERRORS.lock().unwrap_or_else(|e| e.into_inner());
+    match pending.as_mut() {
+        Some(errors) => errors.push(message),
+        None => {
+            let _ = app.emit(BACKGROUND_ERROR_EVENT, message);
+        }
+    }
+}
+
+/// 画面がイベントを受け取れるようになる前に起きたエラーを返す。
+/// 画面は `background-error` の購読を始めてから呼ぶ。
+#[tauri::command]
+pub fn take_background_errors() -> Vec<String> {
+    PENDING_ERRORS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+        .unwrap_or_default()
+}
+
+/// 実行結果を履歴に記録する。記録に失敗しても実行結果自体は返せるよう、エラーは別に通知する。
+fn record_run(app: &tauri::AppHandle, result: &ExecutionResult) {
+    if let Err(e) = journal().record(result) {
+        notify_error(app, format!("Failed to record execution history: {}", e));
+    }
+}
+
+/// 現在のルールセットで監視をやり直す。アプリ起動時と、ルールセットの変更後に呼ぶ。
+pub fn restart_watcher(app: &tauri::AppHandle) {
+    let rulesets = match load_rulesets() {
+        Ok((_, file)) => file.rulesets,
+        Err(e) => {
+            notify_error(app, format!("Failed to load rulesets for watching: {}", e));
+            return;
+        }
+    };
+    let mut guard = WATCHER.lock().unwrap_or_else(|e| e.into_inner());
+    // 古い監視を止めてから新しい監視を始める
+    *guard = None;
+    let on_arrival = app.clone();
+    let on_error = app.clone();
+    match RulesetWatcher::start(
+        rulesets,
+        watcher::DEFAULT_DEBOUNCE,
+        move |ruleset, paths| run_watched(&on_arrival, ruleset, &paths),
+        move |message| notify_error(&on_error, message),
+    ) {
+        Ok(w) => {
+            for failure in w.failures() {
+                notify_error(app, format!("Failed to watch {}", failure));
+            }
+            *guard = Some(w);
+        }
+        Err(e) => notify_error(app, e.to_string()),
+    }
+}
+
+/// 監視で検出したファイルにルールセットを適用する。
+fn run_watched(app: &tauri::AppHandle, ruleset: &Ruleset, paths: &[PathBuf]) {
+    run_unattended(app, "watch-executed", |cancel_flag| {
+        engine::execute_ruleset_for_paths(ruleset, paths, |_, _, _, _| {}, cancel_flag)
+    });
+}
+
+/// スケジュールに従ってルールセットを実行するスケジューラを開始する。アプリ起動時に呼ぶ。
+pub fn start_scheduler(app: &tauri::AppHandle) {
+    let on_run = app.clone();
+    let on_error = app.clone();
+    let scheduler = Scheduler::start(
+        scheduler::default_state_path(),
+        scheduler::DEFAULT_TICK,
+        || {
+            load_rulesets()
+                .map(|(_, file)| file.rulesets)
+                .map_err(|e| format!("Failed to load rulesets for scheduling: {}", e))
+        },
+        move |ruleset| {
+            run_unattended(&on_run, "schedule-executed", |cancel_flag| {
+                engine::execute_ruleset(ruleset, |_, _, _, _| {}, cancel_flag)
+            })
+        },
+        move |message| notify_error(&on_error, message),
+    );
+    *SCHEDULER.lock().unwrap_or_else(|e| e.into_inner()) = Some(scheduler);
+}
+
+/// 監視・スケジュールによる自動実行。手動実行とは直列化し、処理したファイルがあれば
+/// 履歴に記録して `event` で画面に通知する。
+fn run_unattended(
+    app: &tauri::AppHandle,
+    event: &str,
+    execute: impl FnOnce(&AtomicBool) -> ExecutionResult,
+) {
+    let _lock = EXECUTION_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    // 自動実行は中断操作の対象にしない
+    let never_cancel = AtomicBool::new(false);
+    let result = execute(&never_cancel);
+    if result.succeeded.is_empty() && result.errors.is_empty() {
+        return;
+    }
+    record_run(app, &result);
+    let _ = app.emit(event, &result);
+}
+
 fn save_rulesets_to_disk(path: &Path, file: &RulesetFile) -> Result<(), String> {
     file.save(path).map_err(|e| e.to_string())
 }
@@ -79,8 +199,14 @@ pub fn get_rulesets() -> Result<Vec<Ruleset>, String> {
     Ok(file.rulesets)
 }
 
+/// 保存せずに検証し、項目ごとの問題をすべて返す。編集ダイアログで問題のある項目を示すために使う。
 #[tauri::command]
-pub fn save_ruleset(mut ruleset: Ruleset) -> Result<String, String> {
+pub fn validate_ruleset(ruleset: Ruleset) -> Vec<ValidationIssue> {
+    ruleset.validation_issues()
+}
+
+#[tauri::command]
+pub fn save_ruleset(app: tauri::AppHandle, mut ruleset: Ruleset) -> Result<String, String> {
     ruleset.validate().map_err(|e| e.to_string())?;
 
     if ruleset.id.is_empty() {
@@ -95,18 +221,21 @@ pub fn save_ruleset(mut ruleset: Ruleset) -> Result<String, String> {
             file.rulesets.push(ruleset);
         }
     })?;
+    restart_watcher(&app);
     Ok(id)
 }
 
 #[tauri::command]
-pub fn delete_ruleset(id: String) -> Result<(), String> {
+pub fn delete_ruleset(app: tauri::AppHandle, id: String) -> Result<(), String> {
     update_and_save(|file| {
         file.rulesets.retain(|r| r.id != id);
-    })
+    })?;
+    restart_watcher(&app);
+    Ok(())
 }
 
 #[tauri::command]
-pub fn reorder_rulesets(ids: Vec<String>) -> Result<(), String> {
+pub fn reorder_rulesets(app: tauri::AppHandle, ids: Vec<String>) -> Result<(), String> {
     update_and_save(|file| {
         let mut reordered = Vec::with_capacity(ids.len());
         for id in &ids {
@@ -115,7 +244,10 @@ pub fn reorder_rulesets(ids: Vec<String>) -> Result<(), String> {
             }
         }
         file.rulesets = reordered;
-    })
+    })?;
+    // 監視による適用順も一覧の順に合わせる
+    restart_watcher(&app);
+    Ok(())
 }
 
 /// 指定ルールセットを非同期で実行する。
@@ -133,9 +265,10 @@ pub async fn execute_ruleset(app: tauri::AppHandle, id: String) -> Result<Execut
     tauri::async_runtime::spawn_blocking(move || {
         // async コンテキストと spawn_blocking 開始の間の競合を避けるため、
         // フラグのリセットをブロッキングスレッド内の先頭で行う。
+        let _lock = EXECUTION_LOCK.lock().unwrap_or_else(|e| e.into_inner());
         CANCEL_FLAG.store(false, Ordering::SeqCst);
         let ruleset_name = ruleset.name.clone();
-        engine::execute_ruleset(
+        let result = engine::execute_ruleset(
             &ruleset,
             |filename, current, total, bps| {
                 let _ = app.emit(
@@ -150,7 +283,9 @@ pub async fn execute_ruleset(app: tauri::AppHandle, id: String) -> Result<Execut
                 );
             },
             &CANCEL_FLAG,
-        )
+        );
+        record_run(&app, &result);
+        result
     })
     .await
     .map_err(|e| e.to_string())
@@ -167,6 +302,7 @@ pub async fn execute_all(app: tauri::AppHandle) -> Result<Vec<ExecutionResult>,
     tauri::async_runtime::spawn_blocking(move || {
         // async コンテキストと spawn_blocking 開始の間の競合を避けるため、
         // フラグのリセットをブロッキングスレッド内の先頭で行う。
+        let _lock = EXECUTION_LOCK.lock().unwrap_or_else(|e| e.into_inner());
         CANCEL_FLAG.store(false, Ordering::SeqCst);
         let mut results = Vec::new();
         for ruleset in &rulesets {
@@ -192,6 +328,7 @@ pub async fn execute_all(app: tauri::AppHandle) -> Result<Vec<ExecutionResult>,
                 },
                 &CANCEL_FLAG,
             );
+            record_run(&app, &result);
             results.push(result);
         }
         results
@@ -200,6 +337,39 @@ pub async fn execute_all(app: tauri::AppHandle) -> Result<Vec<ExecutionResult>,
     .map_err(|e| e.to_string())
 }
 
+/// 指定ルールセットを実行した場合の操作一覧を返す。ファイルは変更しない。
+#[tauri::command]
+pub async fn preview_ruleset(id: String) -> Result<ExecutionPlan, String> {
+    let (_, file) = load_rulesets()?;
+    let ruleset = file
+        .rulesets
+        .iter()
+        .find(|r| r.id == id)
+        .ok_or_else(|| format!("Ruleset not found: {}", id))?
+        .clone();
+
+    tauri::async_runtime::spawn_blocking(move || engine::preview_ruleset(&ruleset))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 有効なルールセットすべての操作一覧を返す。
+/// 各ルールセットは独立して評価されるため、先行するルールセットによる移動は反映されない。
+#[tauri::command]
+pub async fn preview_all() -> Result<Vec<ExecutionPlan>, String> {
+    let (_, file) = load_rulesets()?;
+    let rulesets: Vec<Ruleset> = file.rulesets.into_iter().filter(|r| r.enabled).collect();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        rulesets
+            .iter()
+            .map(engine::preview_ruleset)
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
 /// 実行を中断するよう要求する。
 /// 処理中のファイルが完了した後、残りのファイルはスキップされる。
 #[tauri::command]
@@ -207,10 +377,20 @@ pub fn cancel_execution() {
     CANCEL_FLAG.store(true, Ordering::SeqCst);
 }
 
+/// 実行結果ダイアログからファイルを元に戻し、履歴上も元に戻し済みにする。
+/// 元に戻すこと自体は成功しているため、履歴の更新に失敗した場合は別に通知する。
+fn undo_and_mark(app: &tauri::AppHandle, source: &Path, dest: &Path) -> Result<(), String> {
+    engine::undo_file_move(source, dest)?;
+    if let Err(e) = journal().mark_undone(source, dest) {
+        notify_error(app, format!("Failed to update execution history: {}", e));
+    }
+    Ok(())
+}
+
 #[tauri::command]
-pub async fn undo_file(source: String, dest: String) -> Result<(), String> {
+pub async fn undo_file(app: tauri::AppHandle, source: String, dest: String) -> Result<(), String> {
     tauri::async_runtime::spawn_blocking(move || {
-        engine::undo_file_move(Path::new(&source), Path::new(&dest))
+        undo_and_mark(&app, Path::new(&source), Path::new(&dest))
     })
     .await
     .map_err(|e| e.to_string())
@@ -218,17 +398,44 @@ pub async fn undo_file(source: String, dest: String) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub async fn undo_all(files: Vec<UndoRequest>) -> Result<Vec<Result<(), String>>, String> {
+pub async fn undo_all(
+    app: tauri::AppHandle,
+    files: Vec<UndoRequest>,
+) -> Result<Vec<Result<(), String>>, String> {
     tauri::async_runtime::spawn_blocking(move || {
         files
             .iter()
-            .map(|req| engine::undo_file_move(&req.source_path, &req.destination_path))
+            .map(|req| undo_and_mark(&app, &req.source_path, &req.destination_path))
             .collect::<Vec<_>>()
     })
     .await
     .map_err(|e| e.to_string())
 }
 
+/// 過去の実行履歴を新しい順に返す。
+#[tauri::command]
+pub fn list_history() -> Result<Vec<RunRecord>, String> {
+    journal().list().map_err(|e| e.to_string())
+}
+
+/// 履歴に記録された実行全体を元に戻す。結果は記録されたファイルと同じ順序で返す。
+#[tauri::command]
+pub async fn undo_history_run(run_id: String) -> Result<Vec<Result<(), String>>, String> {
+    tauri::async_runtime::spawn_blocking(move || journal().undo_run(&run_id))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// 履歴に記録された実行の中の 1 ファイルを元に戻す。
+#[tauri::command]
+pub async fn undo_history_file(run_id: String, source: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || journal().undo_file(&run_id, Path::new(&source)))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r)
+}
+
 #[tauri::command]
 pub fn import_rulesets(path: String) -> Result<Vec<Ruleset>, String> {
     let file = RulesetFile::load(Path::new(&path)).map_err(|e| e.to_string())?;
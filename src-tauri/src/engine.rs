@@ -1,21 +1,22 @@
-use crate::filters::{extract_named_captures, matches_filters};
-use crate::ruleset::{Action, Ruleset};
+use crate::filoignore::{FiloIgnore, InvalidIgnoreLine, IGNORE_FILE_NAME};
+use crate::filters::{CompiledFilters, FilenameCaptures};
+use crate::ruleset::{
+    Action, ConflictPolicy, CounterOptions, CounterSort, DestinationLayout, Ruleset,
+};
+use crate::template::{
+    template_static_prefix, Template, TemplateVar, COUNTER_TEMPLATE_VAR, DATE_TEMPLATE_VARS,
+    DEFAULT_DATE_FORMAT,
+};
+use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::OnceLock;
+use std::sync::{mpsc, Mutex};
 use std::time::Instant;
 
-static TEMPLATE_VAR_RE: OnceLock<regex::Regex> = OnceLock::new();
-
-fn get_template_var_re() -> &'static regex::Regex {
-    TEMPLATE_VAR_RE
-        .get_or_init(|| regex::Regex::new(r"\{([^}]+)\}").expect("static pattern is valid"))
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ExecutionStatus {
     Completed,
@@ -29,6 +30,16 @@ pub struct FileResult {
     pub source_path: PathBuf,
     pub destination_path: Option<PathBuf>,
     pub reason: Option<String>,
+    /// 宛先の同名ファイルと衝突した場合に行われた処理
+    pub conflict: Option<ConflictResolution>,
+}
+
+/// 宛先の衝突に対して `ConflictPolicy` に従って実際に行われた処理
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ConflictResolution {
+    Skipped,
+    Overwritten,
+    Renamed,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,6 +53,34 @@ pub struct ExecutionResult {
     pub errors: Vec<FileResult>,
 }
 
+/// プレビューで予測された各ファイルの処理内容
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum PlannedAction {
+    Move,
+    Copy,
+    Skip,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedOperation {
+    pub filename: String,
+    pub source_path: PathBuf,
+    pub destination_path: Option<PathBuf>,
+    pub action: PlannedAction,
+    pub reason: Option<String>,
+    pub conflict: Option<ConflictResolution>,
+}
+
+/// `preview_ruleset` の結果。ファイルは一切変更されない。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionPlan {
+    pub ruleset_id: String,
+    pub ruleset_name: String,
+    pub action: Action,
+    pub operations: Vec<PlannedOperation>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UndoRequest {
     pub source_path: PathBuf,
@@ -58,6 +97,61 @@ impl ExecutionResult {
             ExecutionStatus::PartialFailure
         }
     }
+
+    /// ファイル処理に入る前に失敗した場合の結果を作る。
+    fn failed(ruleset: &Ruleset, path: PathBuf, reason: String) -> Self {
+        ExecutionResult {
+            ruleset_id: ruleset.id.clone(),
+            ruleset_name: ruleset.name.clone(),
+            action: ruleset.action.clone(),
+            status: ExecutionStatus::Failed,
+            succeeded: Vec::new(),
+            skipped: Vec::new(),
+            errors: vec![FileResult {
+                filename: String::new(),
+                source_path: path,
+                destination_path: None,
+                reason: Some(reason),
+                conflict: None,
+            }],
+        }
+    }
+}
+
+impl ExecutionPlan {
+    fn failed(ruleset: &Ruleset, path: PathBuf, reason: String) -> Self {
+        ExecutionPlan {
+            ruleset_id: ruleset.id.clone(),
+            ruleset_name: ruleset.name.clone(),
+            action: ruleset.action.clone(),
+            operations: vec![PlannedOperation {
+                filename: String::new(),
+                source_path: path,
+                destination_path: None,
+                action: PlannedAction::Error,
+                reason: Some(reason),
+                conflict: None,
+            }],
+        }
+    }
+}
+
+const CONFLICT_SKIP_REASON: &str = "File with same name exists at destination";
+const SAME_FILE_SKIP_REASON: &str = "Source and destination are the same file";
+const CANCELLED_UNLISTED_REASON: &str = "Cancelled by user; remaining files were not listed";
+
+/// 解釈できずに無視した `.filoignore` の行を、どの行が使われなかったか分かるようスキップとして示す
+fn invalid_ignore_line_result(line: InvalidIgnoreLine) -> FileResult {
+    FileResult {
+        filename: IGNORE_FILE_NAME.to_string(),
+        source_path: line.path,
+        destination_path: None,
+        reason: Some(format!(
+            "Ignored invalid line in {}: {}",
+            IGNORE_FILE_NAME, line.message
+        )),
+        conflict: None,
+    }
 }
 
 fn is_cross_device_error(err: &io::Error) -> bool {
@@ -141,25 +235,8 @@ fn classify_io_error(e: &io::Error) -> String {
     }
 }
 
-/// `destination_dir` にテンプレート変数 `{xxx}` が含まれているか判定する。
-fn has_template_vars(s: &str) -> bool {
-    let mut chars = s.chars().peekable();
-    while let Some(c) = chars.next() {
-        if c == '{' {
-            if chars.peek().is_some() {
-                // 閉じ括弧が存在するか確認
-                for inner in chars.by_ref() {
-                    if inner == '}' {
-                        return true;
-                    }
-                }
-            }
-        }
-    }
-    false
-}
-
 /// Windows のパスコンポーネントとして使えない文字を `_` に置換する。
+/// 前後の `.` と空白は取り除く（Windows は末尾の `.`・空白を無視し、`.`・`..` はフォルダをさかのぼれるため）。
 fn sanitize_path_component(s: &str) -> String {
     s.chars()
         .map(|c| {
@@ -169,553 +246,2608 @@ fn sanitize_path_component(s: &str) -> String {
                 c
             }
         })
-        .collect()
+        .collect::<String>()
+        .trim_matches(|c: char| c == '.' || c.is_whitespace())
+        .to_string()
+}
+
+/// 保存先に変数がある場合に、変数より前のフォルダがソースディレクトリの配下にあれば、その正規化したパスを返す。
+/// このフォルダの配下はどこも宛先になりうるため、走査時に降りない・監視で届いたファイルを処理しないために使う。
+/// ソースディレクトリそのものの場合は、すべてのサブディレクトリが対象外になってしまうため `None`。
+fn template_destination_root(ruleset: &Ruleset, source_dir: &Path) -> Option<PathBuf> {
+    if !Template::parse(&ruleset.destination_dir).is_ok_and(|t| t.has_vars()) {
+        return None;
+    }
+    let prefix = template_static_prefix(&ruleset.destination_dir);
+    if prefix.is_empty() {
+        return None;
+    }
+    let root = fs::canonicalize(prefix).ok()?;
+    let source = fs::canonicalize(source_dir).ok()?;
+    (root != source && root.starts_with(&source)).then_some(root)
+}
+
+/// `.`・`..` をファイルシステムに触れずにたどって正規化する
+fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// テンプレートから解決した `resolved` が、テンプレートの変数を含まない部分の配下にあるか確認する。
+/// 変数より前にフォルダがない場合は、作業ディレクトリからの相対パスになってしまうためエラーにする。
+fn ensure_within_template_root(template: &str, resolved: &Path) -> Result<(), String> {
+    let root = template_static_prefix(template);
+    if root.is_empty() {
+        return Err(format!(
+            "Destination '{}' has no folder before the first template variable",
+            template
+        ));
+    }
+    if normalize_lexically(resolved).starts_with(normalize_lexically(Path::new(root))) {
+        return Ok(());
+    }
+    Err(format!(
+        "Resolved destination '{}' is outside of '{}'",
+        resolved.display(),
+        root
+    ))
+}
+
+/// テンプレートの組み込み変数（`{mtime:%Y}`・`{stem}` など）の元になるファイルと実行の情報
+struct TemplateContext<'a> {
+    path: &'a Path,
+    ruleset_name: &'a str,
+    source_dir: &'a Path,
+    modified: Option<DateTime<Local>>,
+    created: Option<DateTime<Local>>,
+    now: DateTime<Local>,
+    counter: Option<CounterValue>,
+}
+
+/// `{counter}` に展開する値
+#[derive(Debug, Clone, Copy)]
+enum CounterValue {
+    Number(u64),
+    /// 採番の範囲（フォルダ）を求めるため、番号の代わりに `COUNTER_MARKER` を埋め込む
+    Marker,
+}
+
+/// 宛先パス中の `{counter}` の位置を探すための目印。パスに使える英数字だけで構成する。
+const COUNTER_MARKER: &str = "FILOCOUNTERMARKER";
+
+/// 日時変数 `name` を `format`（省略時は `DEFAULT_DATE_FORMAT`）で整形する。書式は解析時に検証済み。
+/// ファイルシステムが作成日時に対応していない場合など、時刻を取得できない場合は `Err`。
+fn format_template_date(
+    name: &str,
+    format: Option<&str>,
+    ctx: &TemplateContext,
+) -> Result<String, String> {
+    let time = match name {
+        "mtime" => ctx.modified,
+        "ctime" => ctx.created,
+        _ => Some(ctx.now),
+    }
+    .ok_or_else(|| {
+        format!(
+            "Template variable '{}' is not available for this file",
+            name
+        )
+    })?;
+    Ok(time
+        .format(format.unwrap_or(DEFAULT_DATE_FORMAT))
+        .to_string())
+}
+
+/// 組み込み変数 `var` の値を返す。組み込み変数でない場合は `None`。
+fn builtin_template_value(
+    var: &TemplateVar,
+    ctx: &TemplateContext,
+) -> Option<Result<String, String>> {
+    let (name, format) = (var.name.as_str(), var.format.as_deref());
+    if DATE_TEMPLATE_VARS.contains(&name) {
+        return Some(format_template_date(name, format, ctx));
+    }
+    if name == COUNTER_TEMPLATE_VAR {
+        return ctx.counter.map(|value| format_counter(value, format));
+    }
+    if format.is_some() {
+        return None;
+    }
+    let lossy = |s: Option<&std::ffi::OsStr>| {
+        s.map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+    let value = match name {
+        "stem" => lossy(ctx.path.file_stem()),
+        "ext" => lossy(ctx.path.extension()),
+        "parent" => lossy(ctx.path.parent().and_then(Path::file_name)),
+        "ruleset" => ctx.ruleset_name.to_string(),
+        "source_dir_name" => lossy(ctx.source_dir.file_name()),
+        _ => return None,
+    };
+    Some(Ok(value))
+}
+
+/// `{counter}`・`{counter:4}` の値を返す。桁数を指定した場合はゼロ埋めする。
+fn format_counter(value: CounterValue, width: Option<&str>) -> Result<String, String> {
+    let n = match value {
+        CounterValue::Number(n) => n,
+        CounterValue::Marker => return Ok(COUNTER_MARKER.to_string()),
+    };
+    match width {
+        Some(width) => width
+            .parse::<usize>()
+            .map(|width| format!("{:0width$}", n, width = width))
+            .map_err(|_| format!("Invalid counter width '{}'", width)),
+        None => Ok(n.to_string()),
+    }
 }
 
-/// テンプレート文字列内の `{varname}` を captures の値で置換する。
-/// - 変数名が captures に存在しない場合は `Err`
-/// - 値が空文字の場合は `Err`
-/// - 値は `sanitize_path_component` でサニタイズされる
+/// テンプレート内の `{varname}` を captures の値、または組み込み変数の値で置換する。
+/// `{varname|lower|pad:3}` のように `|` で続けた変換を左から順に適用する。
+/// - 同名の captures がある場合は組み込み変数より優先する
+/// - 変数名が captures にも組み込み変数にも存在しない場合は `Err`（`default` を指定した場合を除く）
+/// - 値が空文字の場合は `Err`（`default` を指定した場合を除く）
+/// - 値は `sanitize_path_component` でサニタイズされる（`date` 変換の出力書式にある `/` を除く）
 fn resolve_destination_template(
-    template: &str,
+    template: &Template,
     captures: &HashMap<String, String>,
+    ctx: &TemplateContext,
 ) -> Result<String, String> {
-    let re = get_template_var_re();
-    let mut error: Option<String> = None;
-    let result = re.replace_all(template, |caps: &regex::Captures| {
-        let var_name = &caps[1];
-        match captures.get(var_name) {
-            Some(val) if !val.is_empty() => sanitize_path_component(val),
-            Some(_) => {
-                error.get_or_insert_with(|| {
-                    format!("Template variable '{}' resolved to empty string", var_name)
-                });
-                String::new()
-            }
-            None => {
-                error.get_or_insert_with(|| {
-                    format!(
-                        "Template variable '{}' not found in regex capture groups",
-                        var_name
-                    )
-                });
-                String::new()
+    template.render(|var| {
+        let captured = var
+            .format
+            .is_none()
+            .then(|| captures.get(&var.name))
+            .flatten();
+        let value = match captured {
+            Some(val) => Ok(val.clone()),
+            None => builtin_template_value(var, ctx).unwrap_or_else(|| {
+                Err(format!(
+                    "Template variable '{}' not found in regex capture groups",
+                    var.name
+                ))
+            }),
+        };
+        match var.apply_filters(value)? {
+            val if val.is_empty() => Err(format!(
+                "Template variable '{}' resolved to empty string",
+                var.name
+            )),
+            val => {
+                // `date:IN->%Y/%m` の出力書式にある `/` はフォルダの区切りとして残す
+                let sanitized = if var.keeps_separators() {
+                    val.split('/')
+                        .map(sanitize_path_component)
+                        .collect::<Vec<_>>()
+                        .join("/")
+                } else {
+                    sanitize_path_component(&val)
+                };
+                // `.`・`..` のようにサニタイズ後に空になるコンポーネントは使わない
+                if sanitized.split('/').any(str::is_empty) {
+                    return Err(format!(
+                        "Template variable '{}' resolved to invalid path component '{}'",
+                        var.name, val
+                    ));
+                }
+                Ok(sanitized)
             }
         }
-    });
-    match error {
-        Some(e) => Err(e),
-        None => Ok(result.into_owned()),
-    }
+    })
 }
 
-/// フィルタを通過したファイルの情報（処理前に列挙済み）
-struct PendingFile {
-    path: PathBuf,
-    filename: String,
-    file_size: u64,
+/// `auto_rename` で空き名を探す上限回数
+const MAX_RENAME_ATTEMPTS: u32 = 9999;
+
+/// 宛先の衝突判定の結果
+enum ConflictDecision {
+    /// 宛先パスが確定した。衝突がなかった場合 `resolution` は `None`
+    Proceed {
+        dest: PathBuf,
+        resolution: Option<ConflictResolution>,
+    },
+    Skip {
+        dest: PathBuf,
+        reason: String,
+    },
 }
 
-/// `on_progress(filename, current, total, bytes_per_second)` を呼びながらルールセットを実行する。
-/// `cancel_flag` が `true` になると、処理中のファイルが完了した後、残りのファイルを
-/// 「ユーザーによる中断」としてスキップして早期リターンする。
-pub fn execute_ruleset(
+/// `ruleset.on_conflict` に従って宛先の衝突を解決する。
+/// `occupant(path)` は `path` を占有しているファイルを返す。実行時は実在する宛先ファイル、
+/// プレビュー時は同じ宛先を先に予約したソースファイルも返す。
+fn resolve_conflict(
     ruleset: &Ruleset,
-    on_progress: impl Fn(&str, usize, usize, f64),
-    cancel_flag: &AtomicBool,
-) -> ExecutionResult {
-    let mut succeeded = Vec::new();
-    let mut skipped = Vec::new();
-    let mut errors = Vec::new();
-
-    let source_dir = ruleset.source_path();
-    let destination_dir = ruleset.destination_path();
-
-    // Check source directory
-    if !source_dir.exists() {
-        return ExecutionResult {
-            ruleset_id: ruleset.id.clone(),
-            ruleset_name: ruleset.name.clone(),
-            action: ruleset.action.clone(),
-            status: ExecutionStatus::Failed,
-            succeeded,
-            skipped,
-            errors: vec![FileResult {
-                filename: String::new(),
-                source_path: source_dir,
-                destination_path: None,
-                reason: Some("Source directory does not exist".to_string()),
-            }],
+    source: &Path,
+    dest: PathBuf,
+    occupant: impl Fn(&Path) -> Option<PathBuf>,
+) -> ConflictDecision {
+    let Some(existing) = occupant(&dest) else {
+        return ConflictDecision::Proceed {
+            dest,
+            resolution: None,
         };
+    };
+    let overwrite = |dest| ConflictDecision::Proceed {
+        dest,
+        resolution: Some(ConflictResolution::Overwritten),
+    };
+    let skip = |dest, reason: &str| ConflictDecision::Skip {
+        dest,
+        reason: reason.to_string(),
+    };
+    let compare_failed = |dest, e: io::Error| ConflictDecision::Skip {
+        dest,
+        reason: format!("Failed to compare with existing file: {}", e),
+    };
+
+    // 宛先が元のファイル自身の場合は、どの方針でも何もしない
+    if is_same_file(source, &existing) {
+        return skip(dest, SAME_FILE_SKIP_REASON);
     }
 
-    // テンプレート変数がない場合のみ事前に destination_dir を作成する。
-    // テンプレートがある場合はファイルごとに解決して作成する。
-    let use_template = has_template_vars(&ruleset.destination_dir);
-    if !use_template {
-        if let Err(e) = fs::create_dir_all(&destination_dir) {
-            return ExecutionResult {
-                ruleset_id: ruleset.id.clone(),
-                ruleset_name: ruleset.name.clone(),
-                action: ruleset.action.clone(),
-                status: ExecutionStatus::Failed,
-                succeeded,
-                skipped,
-                errors: vec![FileResult {
-                    filename: String::new(),
-                    source_path: destination_dir,
-                    destination_path: None,
-                    reason: Some(format!("Failed to create destination directory: {}", e)),
-                }],
-            };
+    match ruleset.on_conflict {
+        ConflictPolicy::Skip => skip(dest, CONFLICT_SKIP_REASON),
+        ConflictPolicy::Overwrite => overwrite(dest),
+        ConflictPolicy::AutoRename => auto_rename(ruleset.rename_pattern(), dest, &occupant),
+        ConflictPolicy::KeepNewer => {
+            let modified = |p: &Path| fs::metadata(p).and_then(|m| m.modified());
+            match (modified(source), modified(&existing)) {
+                (Ok(src), Ok(dst)) if src > dst => overwrite(dest),
+                (Ok(_), Ok(_)) => skip(dest, "Existing file at destination is not older"),
+                (Err(e), _) | (_, Err(e)) => compare_failed(dest, e),
+            }
         }
+        ConflictPolicy::KeepLarger => {
+            let size = |p: &Path| fs::metadata(p).map(|m| m.len());
+            match (size(source), size(&existing)) {
+                (Ok(src), Ok(dst)) if src > dst => overwrite(dest),
+                (Ok(_), Ok(_)) => skip(dest, "Existing file at destination is not smaller"),
+                (Err(e), _) | (_, Err(e)) => compare_failed(dest, e),
+            }
+        }
+        ConflictPolicy::SkipIfIdentical => match files_identical(source, &existing) {
+            Ok(true) => skip(dest, "Identical file already exists at destination"),
+            Ok(false) => auto_rename(ruleset.rename_pattern(), dest, &occupant),
+            Err(e) => compare_failed(dest, e),
+        },
     }
+}
 
-    // List files in source directory (non-recursive)
-    let entries = match fs::read_dir(&source_dir) {
-        Ok(entries) => entries,
+/// `pattern` に従って `dest` と同じディレクトリ内で占有されていない名前を探す。
+fn auto_rename(
+    pattern: &str,
+    dest: PathBuf,
+    occupant: &impl Fn(&Path) -> Option<PathBuf>,
+) -> ConflictDecision {
+    let name = Path::new(dest.file_name().unwrap_or_default());
+    let stem = name.file_stem().unwrap_or_default().to_string_lossy();
+    let dotext = name
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    let parent = dest.parent().map(Path::to_path_buf).unwrap_or_default();
+    let template = match Template::parse(pattern) {
+        Ok(template) => template,
         Err(e) => {
-            return ExecutionResult {
-                ruleset_id: ruleset.id.clone(),
-                ruleset_name: ruleset.name.clone(),
-                action: ruleset.action.clone(),
-                status: ExecutionStatus::Failed,
-                succeeded,
-                skipped,
-                errors: vec![FileResult {
-                    filename: String::new(),
-                    source_path: source_dir,
-                    destination_path: None,
-                    reason: Some(format!("Failed to read source directory: {}", e)),
-                }],
-            };
+            return ConflictDecision::Skip {
+                dest,
+                reason: format!("Invalid rename_pattern: {}", e),
+            }
         }
     };
 
-    // フィルタを通過するファイルを事前に列挙して総数を確定する。
-    // メタデータ取得に失敗したファイルはエラーとして記録し、列挙対象から除外する。
-    let mut matching_files: Vec<PendingFile> = Vec::new();
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if path.is_dir() {
-            continue;
-        }
-        let metadata = match fs::metadata(&path) {
-            Ok(m) => m,
-            Err(e) => {
-                errors.push(FileResult {
-                    filename: path
-                        .file_name()
-                        .unwrap_or_default()
-                        .to_string_lossy()
-                        .to_string(),
-                    source_path: path,
-                    destination_path: None,
-                    reason: Some(format!("Failed to read metadata: {}", e)),
-                });
-                continue;
-            }
+    for n in 1..=MAX_RENAME_ATTEMPTS {
+        let renamed = template.render(|var| {
+            let value = match var.name.as_str() {
+                "stem" => stem.to_string(),
+                "dotext" => dotext.clone(),
+                "n" => n.to_string(),
+                // 手で編集したルールファイルの誤りを、そのままファイル名にしない
+                _ => {
+                    return Err(format!(
+                        "Unknown variable '{{{}}}' in rename_pattern",
+                        var.expr
+                    ))
+                }
+            };
+            var.apply_filters(Ok(value))
+        });
+        let renamed = match renamed {
+            Ok(renamed) => renamed,
+            Err(e) => return ConflictDecision::Skip { dest, reason: e },
         };
-        if !matches_filters(&path, &metadata, &ruleset.filters) {
-            continue;
+        let candidate = parent.join(renamed);
+        if occupant(&candidate).is_none() {
+            return ConflictDecision::Proceed {
+                dest: candidate,
+                resolution: Some(ConflictResolution::Renamed),
+            };
         }
-        let filename = path
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-        matching_files.push(PendingFile {
-            path,
-            filename,
-            file_size: metadata.len(),
-        });
     }
+    ConflictDecision::Skip {
+        dest,
+        reason: "No available name found for auto-rename".to_string(),
+    }
+}
 
-    let total = matching_files.len();
-    let mut bytes_transferred: u64 = 0;
-    let start_time = Instant::now();
-    let mut last_progress_emit: Option<Instant> = None;
-    const PROGRESS_THROTTLE_MS: u128 = 100;
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    a == b
+        || matches!(
+            (fs::canonicalize(a), fs::canonicalize(b)),
+            (Ok(a), Ok(b)) if a == b
+        )
+}
 
-    // テンプレート変数がある場合、ファイル名フィルタのパターンをループ外で一度だけコンパイルする
-    let filename_regex: Option<regex::Regex> = if use_template {
-        ruleset
-            .filters
-            .filename
-            .as_ref()
-            .and_then(|f| regex::Regex::new(&f.pattern).ok())
-    } else {
-        None
-    };
+/// 2 つのファイルの内容が同一か判定する。サイズが異なる場合は内容を読まない。
+fn files_identical(a: &Path, b: &Path) -> io::Result<bool> {
+    use std::io::Read;
 
-    // テンプレートモードで create_dir_all の重複呼び出しを避けるキャッシュ
-    let mut created_dirs: std::collections::HashSet<PathBuf> = std::collections::HashSet::new();
+    if fs::metadata(a)?.len() != fs::metadata(b)?.len() {
+        return Ok(false);
+    }
+    let mut reader_a = io::BufReader::new(fs::File::open(a)?);
+    let mut reader_b = io::BufReader::new(fs::File::open(b)?);
+    let mut buf_a = [0u8; 8192];
+    let mut buf_b = [0u8; 8192];
+    loop {
+        let read = reader_a.read(&mut buf_a)?;
+        if read == 0 {
+            // サイズが同じなので b も終端に達している
+            return Ok(true);
+        }
+        reader_b.read_exact(&mut buf_b[..read])?;
+        if buf_a[..read] != buf_b[..read] {
+            return Ok(false);
+        }
+    }
+}
 
-    for (i, pending) in matching_files.iter().enumerate() {
-        let elapsed = start_time.elapsed().as_secs_f64();
-        let bps = if elapsed > 0.0 {
-            bytes_transferred as f64 / elapsed
-        } else {
-            0.0
-        };
-        // 初回・100ms経過・最終ファイルのいずれかで進捗通知する
-        let now = Instant::now();
-        let should_emit = last_progress_emit
-            .map_or(true, |t| now.duration_since(t).as_millis() >= PROGRESS_THROTTLE_MS)
-            || i + 1 == total;
-        if should_emit {
-            on_progress(&pending.filename, i + 1, total, bps);
-            last_progress_emit = Some(now);
+/// フィルタを通過したファイルの情報（処理前に列挙済み）
+struct PendingFile {
+    path: PathBuf,
+    filename: String,
+    file_size: u64,
+    /// `source_dir` からファイルの親ディレクトリまでの相対パス（直下のファイルは空）
+    relative_dir: PathBuf,
+    modified: Option<DateTime<Local>>,
+    created: Option<DateTime<Local>>,
+}
+
+/// 列挙で見つかったファイル。フィルタを通過したファイルか、メタデータを取得できなかったファイルのエラー
+type WalkItem = Result<PendingFile, FileResult>;
+
+/// 取得済みのメタデータでフィルタを評価し、通過した場合のみ `PendingFile` を返す。
+/// メタデータを取得できなかった場合はエラーを返す。
+fn pending_file(
+    filters: &CompiledFilters,
+    path: PathBuf,
+    relative_dir: &Path,
+    metadata: io::Result<fs::Metadata>,
+) -> Option<WalkItem> {
+    let filename = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let metadata = match metadata {
+        Ok(m) => m,
+        Err(e) => {
+            return Some(Err(FileResult {
+                filename,
+                source_path: path,
+                destination_path: None,
+                reason: Some(format!("Failed to read metadata: {}", e)),
+                conflict: None,
+            }));
         }
+    };
+    if !filters.matches(&path, &metadata) {
+        return None;
+    }
+    Some(Ok(PendingFile {
+        path,
+        filename,
+        file_size: metadata.len(),
+        relative_dir: relative_dir.to_path_buf(),
+        modified: metadata.modified().ok().map(Into::into),
+        created: metadata.created().ok().map(Into::into),
+    }))
+}
 
-        // ラベル付きブロックで早期脱出しても、末尾のキャンセルチェックに必ず到達する
-        'process: {
-            // テンプレート変数がある場合はファイル名からキャプチャを取得して解決する
-            let resolved_dir = if use_template {
-                let caps = if let Some(re) = filename_regex.as_ref() {
-                    extract_named_captures(&pending.filename, re)
-                } else {
-                    HashMap::new()
-                };
-                match resolve_destination_template(&ruleset.destination_dir, &caps) {
-                    Ok(dir) => PathBuf::from(dir),
-                    Err(reason) => {
-                        skipped.push(FileResult {
-                            filename: pending.filename.clone(),
-                            source_path: pending.path.clone(),
-                            destination_path: None,
-                            reason: Some(reason),
-                        });
-                        break 'process;
-                    }
-                }
-            } else {
-                destination_dir.clone()
-            };
+/// 読み進めているディレクトリ
+struct OpenDir {
+    entries: fs::ReadDir,
+    /// `source_dir` からの相対パス
+    relative_dir: PathBuf,
+    depth: u32,
+}
 
-            // テンプレートで解決された場合はディレクトリを作成する（キャッシュで重複呼び出しを回避）
-            if use_template && !created_dirs.contains(&resolved_dir) {
-                if let Err(e) = fs::create_dir_all(&resolved_dir) {
-                    errors.push(FileResult {
-                        filename: pending.filename.clone(),
-                        source_path: pending.path.clone(),
-                        destination_path: None,
-                        reason: Some(format!("Failed to create destination directory: {}", e)),
-                    });
-                    break 'process;
-                }
-                created_dirs.insert(resolved_dir.clone());
-            }
+/// ソースディレクトリを列挙し、フィルタを通過したファイルを 1 件ずつ返す。
+/// `ruleset.scan.recursive` の場合は `max_depth` までのサブディレクトリも深さ優先でたどる。
+/// ファイルの一覧は作らず、まだ読んでいないサブディレクトリだけを保持するため、
+/// ファイル数が多くてもすぐに最初のファイルを返し、使用するメモリも増えない。
+/// 種別は `DirEntry::file_type` で判定し、メタデータの取得はファイルごとに 1 回だけ行う。
+struct SourceWalker<'a> {
+    ruleset: &'a Ruleset,
+    filters: &'a CompiledFilters,
+    ignore: &'a mut FiloIgnore,
+    /// 宛先がソース配下にある場合、移動済みファイルを再度拾わないよう宛先ディレクトリには降りない
+    destination_dir: Option<PathBuf>,
+    /// 保存先に変数がある場合は、変数より前のフォルダの配下に降りない
+    template_root: Option<PathBuf>,
+    /// シンボリックリンクをたどる場合の循環検出用
+    visited: HashSet<PathBuf>,
+    current: Option<OpenDir>,
+    /// 読んでいるディレクトリで見つかったサブディレクトリ。読み終えてから `pending_dirs` に積む
+    subdirs: Vec<PathBuf>,
+    /// これから読むディレクトリと、その相対パス・深さ
+    pending_dirs: Vec<(PathBuf, PathBuf, u32)>,
+}
 
-            let dest_path = resolved_dir.join(&pending.filename);
+impl<'a> SourceWalker<'a> {
+    /// ソースディレクトリ自体が読めない場合のみエラーを返す。
+    /// `ignore` には読み込んだ `.filoignore` のうち解釈できなかった行が残る。
+    fn new(
+        ruleset: &'a Ruleset,
+        filters: &'a CompiledFilters,
+        source_dir: &Path,
+        ignore: &'a mut FiloIgnore,
+    ) -> io::Result<Self> {
+        let entries = fs::read_dir(source_dir)?;
+        let mut visited = HashSet::new();
+        if let Ok(canonical) = fs::canonicalize(source_dir) {
+            visited.insert(canonical);
+        }
+        Ok(Self {
+            ruleset,
+            filters,
+            ignore,
+            destination_dir: fs::canonicalize(ruleset.destination_path()).ok(),
+            template_root: template_destination_root(ruleset, source_dir),
+            visited,
+            current: Some(OpenDir {
+                entries,
+                relative_dir: PathBuf::new(),
+                depth: 0,
+            }),
+            subdirs: Vec::new(),
+            pending_dirs: Vec::new(),
+        })
+    }
 
-            // Check for existing file
-            if dest_path.exists() && !ruleset.overwrite {
-                skipped.push(FileResult {
-                    filename: pending.filename.clone(),
-                    source_path: pending.path.clone(),
-                    destination_path: Some(dest_path),
-                    reason: Some("File with same name exists at destination".to_string()),
+    /// 次に読むディレクトリを開く。読み取れないサブディレクトリは無視する。
+    fn open_next_dir(&mut self) -> Option<OpenDir> {
+        while let Some((path, relative_dir, depth)) = self.pending_dirs.pop() {
+            if let Ok(entries) = fs::read_dir(&path) {
+                return Some(OpenDir {
+                    entries,
+                    relative_dir,
+                    depth,
                 });
-                break 'process;
             }
+        }
+        None
+    }
 
-            // Execute action
-            let result = match ruleset.action {
-                Action::Move => move_file(&pending.path, &dest_path, pending.file_size),
-                Action::Copy => copy_and_verify(&pending.path, &dest_path, pending.file_size),
-            };
+    /// 読み終えたディレクトリのサブディレクトリを積む。逆順に積むことで列挙順に処理する。
+    fn finish_dir(&mut self, dir: OpenDir) {
+        for path in self.subdirs.drain(..).rev() {
+            let relative_dir = dir.relative_dir.join(path.file_name().unwrap_or_default());
+            self.pending_dirs.push((path, relative_dir, dir.depth + 1));
+        }
+    }
 
-            match result {
-                Ok(()) => {
-                    bytes_transferred += pending.file_size;
-                    succeeded.push(FileResult {
-                        filename: pending.filename.clone(),
-                        source_path: pending.path.clone(),
-                        destination_path: Some(dest_path),
-                        reason: None,
-                    });
-                }
-                Err(e) => {
-                    errors.push(FileResult {
-                        filename: pending.filename.clone(),
-                        source_path: pending.path.clone(),
-                        destination_path: Some(dest_path),
-                        reason: Some(classify_io_error(&e)),
-                    });
-                }
+    /// ディレクトリはたどる場合に `subdirs` に加え、ファイルはフィルタを評価する
+    fn visit(&mut self, entry: fs::DirEntry, dir: &OpenDir) -> Option<WalkItem> {
+        let scan = &self.ruleset.scan;
+        let path = entry.path();
+        let file_type = entry.file_type();
+        let is_symlink = file_type.as_ref().is_ok_and(|t| t.is_symlink());
+        // シンボリックリンクはリンク先の種別とメタデータを使う
+        let target = (is_symlink || file_type.is_err()).then(|| fs::metadata(&path));
+        let is_dir = match &target {
+            Some(metadata) => metadata.as_ref().is_ok_and(|m| m.is_dir()),
+            None => file_type.is_ok_and(|t| t.is_dir()),
+        };
+        // `.filoignore` で除外されたファイル・ディレクトリは、ほかのフィルタより先に取り除く
+        if self.ignore.is_ignored(&path, is_dir) {
+            return None;
+        }
+        if !is_dir {
+            let metadata = target.unwrap_or_else(|| entry.metadata());
+            return pending_file(self.filters, path, &dir.relative_dir, metadata);
+        }
+
+        if !scan.recursive || scan.max_depth.is_some_and(|max| dir.depth + 1 > max) {
+            return None;
+        }
+        if is_symlink && !scan.follow_symlinks {
+            return None;
+        }
+        let canonical = fs::canonicalize(&path).ok();
+        if canonical.is_some() && canonical == self.destination_dir {
+            return None;
+        }
+        if let (Some(canonical), Some(root)) = (&canonical, &self.template_root) {
+            if canonical.starts_with(root) {
+                return None;
             }
-        } // end 'process
-
-        // キャンセルチェック: 常にここに到達する。
-        // 処理中のファイルが完了した後、残りのファイルをスキップしてループを抜ける。
-        if cancel_flag.load(Ordering::Relaxed) {
-            for rem in &matching_files[i + 1..] {
-                skipped.push(FileResult {
-                    filename: rem.filename.clone(),
-                    source_path: rem.path.clone(),
-                    destination_path: None,
-                    reason: Some("Cancelled by user".to_string()),
-                });
+        }
+        if let Some(canonical) = canonical {
+            if !self.visited.insert(canonical) {
+                return None;
             }
-            break;
         }
+        self.subdirs.push(path);
+        None
     }
+}
 
-    let status = ExecutionResult::determine_status(&succeeded, &errors);
+impl Iterator for SourceWalker<'_> {
+    type Item = WalkItem;
 
-    ExecutionResult {
-        ruleset_id: ruleset.id.clone(),
-        ruleset_name: ruleset.name.clone(),
-        action: ruleset.action.clone(),
-        status,
-        succeeded,
-        skipped,
-        errors,
+    fn next(&mut self) -> Option<WalkItem> {
+        loop {
+            let mut dir = match self.current.take() {
+                Some(dir) => dir,
+                None => self.open_next_dir()?,
+            };
+            let Some(entry) = dir.entries.next() else {
+                self.finish_dir(dir);
+                continue;
+            };
+            let item = entry.ok().and_then(|entry| self.visit(entry, &dir));
+            self.current = Some(dir);
+            if item.is_some() {
+                return item;
+            }
+        }
     }
 }
 
-pub fn undo_file_move(source_path: &Path, destination_path: &Path) -> Result<(), String> {
-    // destination_path is where the file currently is (moved to)
-    // source_path is where it should go back to (original location)
-    if !destination_path.exists() {
-        return Err("File no longer exists at destination".to_string());
+/// ソースディレクトリを列挙し、フィルタを通過したファイルの一覧を返す。
+/// 採番やプレビューのように、すべてのファイルがそろってから処理する場合に使う。
+/// メタデータ取得に失敗したファイルはエラーとして `errors` に記録し、一覧から除外する。
+fn collect_matching_files(
+    ruleset: &Ruleset,
+    filters: &CompiledFilters,
+    source_dir: &Path,
+    ignore: &mut FiloIgnore,
+    errors: &mut Vec<FileResult>,
+) -> io::Result<Vec<PendingFile>> {
+    let mut matching_files = Vec::new();
+    for item in SourceWalker::new(ruleset, filters, source_dir, ignore)? {
+        match item {
+            Ok(pending) => matching_files.push(pending),
+            Err(e) => errors.push(e),
+        }
+    }
+    Ok(matching_files)
+}
+
+/// 指定されたパスのうち、ルールセットの列挙範囲に含まれフィルタを通過するファイルを返す。
+/// 監視モードで新しく置かれたファイルだけを処理するために使う。
+/// 処理までの間に削除・移動されたファイルや、範囲外のパスは黙って除外する。
+fn collect_given_files(
+    ruleset: &Ruleset,
+    filters: &CompiledFilters,
+    source_dir: &Path,
+    paths: &[PathBuf],
+    ignore: &mut FiloIgnore,
+    errors: &mut Vec<FileResult>,
+) -> Vec<PendingFile> {
+    let scan = &ruleset.scan;
+    let destination_dirs: Vec<PathBuf> = fs::canonicalize(ruleset.destination_path())
+        .ok()
+        .into_iter()
+        .chain(template_destination_root(ruleset, source_dir))
+        .collect();
+    let mut seen = HashSet::new();
+    let mut matching_files = Vec::new();
+
+    for path in paths {
+        if !seen.insert(path) {
+            continue;
+        }
+        let Some(relative_dir) = path
+            .parent()
+            .and_then(|parent| parent.strip_prefix(source_dir).ok())
+        else {
+            continue;
+        };
+        let depth = relative_dir.components().count() as u32;
+        if depth > 0 && (!scan.recursive || scan.max_depth.is_some_and(|max| depth > max)) {
+            continue;
+        }
+        if ignore.is_ignored_with_parents(path) {
+            continue;
+        }
+        let metadata = match fs::metadata(path) {
+            Ok(m) if !m.is_dir() => m,
+            _ => continue,
+        };
+        // 宛先がソース配下にある場合、移動済みファイルの到着を再度処理しない
+        if let Ok(canonical) = fs::canonicalize(path) {
+            if destination_dirs
+                .iter()
+                .any(|dest| canonical.starts_with(dest))
+            {
+                continue;
+            }
+        }
+        match pending_file(filters, path.clone(), relative_dir, Ok(metadata)) {
+            Some(Ok(pending)) => matching_files.push(pending),
+            Some(Err(e)) => errors.push(e),
+            None => {}
+        }
+    }
+    matching_files
+}
+
+/// ファイルごとの宛先ディレクトリとファイル名を解決する。実行とプレビューで共通に使う。
+struct DestinationResolver<'a> {
+    ruleset: &'a Ruleset,
+    /// 保存先に変数がある場合のみ、解析済みのテンプレートを保持する
+    destination: Option<Template>,
+    rename: Option<Template>,
+    /// テンプレートを解析できなかった場合のエラー。すべてのファイルをこの理由でスキップする
+    invalid: Option<String>,
+    /// 保存先か `rename` にテンプレート変数がある場合のみ、コンパイル済みのファイル名フィルタのキャプチャを参照する
+    filename_captures: Option<&'a FilenameCaptures>,
+    /// `{now:...}` の基準となる実行時刻。1 回の実行中はすべてのファイルで同じ値を使う。
+    now: DateTime<Local>,
+    /// 保存先か `rename` に `{counter}` がある場合のみ、採番の範囲（フォルダ）ごとの次の番号を保持する
+    counters: Option<HashMap<PathBuf, u64>>,
+}
+
+impl<'a> DestinationResolver<'a> {
+    fn new(ruleset: &'a Ruleset, filters: &'a CompiledFilters) -> Self {
+        let mut invalid = None;
+        let mut parse = |field: &str, template: &str| {
+            Template::parse(template)
+                .map_err(|e| {
+                    invalid.get_or_insert_with(|| format!("Invalid {}: {}", field, e));
+                })
+                .ok()
+        };
+        let destination = parse("destination_dir", &ruleset.destination_dir);
+        let rename = ruleset
+            .rename
+            .as_deref()
+            .and_then(|template| parse("rename", template));
+        let templates = || destination.iter().chain(rename.iter());
+        let filename_captures = if templates().any(Template::has_vars) {
+            filters.filename_captures()
+        } else {
+            None
+        };
+        let counter_used = templates().any(Template::uses_counter);
+        Self {
+            ruleset,
+            destination: destination.filter(Template::has_vars),
+            rename,
+            invalid,
+            filename_captures,
+            now: Local::now(),
+            counters: counter_used.then(HashMap::new),
+        }
+    }
+
+    fn uses_counter(&self) -> bool {
+        self.counters.is_some()
+    }
+
+    /// 保存先がファイルごとに決まるか。テンプレートが不正な場合も、固定の保存先は作成しない
+    fn uses_template(&self) -> bool {
+        self.destination.is_some() || self.invalid.is_some()
+    }
+
+    /// 宛先ディレクトリと宛先でのファイル名を返す。テンプレートを解決できない場合はスキップ理由を `Err` で返す。
+    /// `{counter}` を使う場合は、`is_taken(path)` が `true` を返す宛先を避けて番号を振る。
+    fn resolve(
+        &mut self,
+        pending: &PendingFile,
+        is_taken: impl Fn(&Path) -> bool,
+    ) -> Result<(PathBuf, String), String> {
+        if let Some(e) = &self.invalid {
+            return Err(e.clone());
+        }
+        let caps = match self.filename_captures {
+            Some(captures) => captures.extract(&pending.filename),
+            None => HashMap::new(),
+        };
+        if !self.uses_counter() {
+            return self.resolve_with(pending, &caps, None);
+        }
+
+        // 番号の位置に目印を埋め込んで解決し、採番の範囲と既存の番号の形式を求める
+        let (dir, name) = self.resolve_with(pending, &caps, Some(CounterValue::Marker))?;
+        let (scope, numbered) = counter_scope(&dir.join(name));
+        let options = &self.ruleset.counter;
+        let counters = self.counters.get_or_insert_with(HashMap::new);
+        let mut n = *counters.entry(scope.clone()).or_insert_with(|| {
+            let highest = match (&numbered, options.resume) {
+                (Some(re), true) => highest_existing_counter(&scope, re),
+                _ => None,
+            };
+            highest.map_or(options.start, |h| options.start.max(h.saturating_add(1)))
+        });
+
+        for _ in 0..MAX_RENAME_ATTEMPTS {
+            let (dir, name) = self.resolve_with(pending, &caps, Some(CounterValue::Number(n)))?;
+            n = n.saturating_add(1);
+            if !is_taken(&dir.join(&name)) {
+                self.counters
+                    .get_or_insert_with(HashMap::new)
+                    .insert(scope, n);
+                return Ok((dir, name));
+            }
+        }
+        Err("No available counter value found".to_string())
+    }
+
+    fn resolve_with(
+        &self,
+        pending: &PendingFile,
+        caps: &HashMap<String, String>,
+        counter: Option<CounterValue>,
+    ) -> Result<(PathBuf, String), String> {
+        let source_dir = self.ruleset.source_path();
+        let ctx = TemplateContext {
+            path: &pending.path,
+            ruleset_name: &self.ruleset.name,
+            source_dir: &source_dir,
+            modified: pending.modified,
+            created: pending.created,
+            now: self.now,
+            counter,
+        };
+        let base = match &self.destination {
+            Some(template) => {
+                let base = PathBuf::from(resolve_destination_template(template, caps, &ctx)?);
+                ensure_within_template_root(&self.ruleset.destination_dir, &base)?;
+                base
+            }
+            None => self.ruleset.destination_path(),
+        };
+        let dir = match self.ruleset.scan.layout {
+            DestinationLayout::Flatten => base,
+            DestinationLayout::Preserve => base.join(&pending.relative_dir),
+        };
+        let filename = match &self.rename {
+            Some(template) => {
+                let name = resolve_destination_template(template, caps, &ctx)?;
+                if matches!(name.trim(), "" | "." | "..") {
+                    return Err(format!(
+                        "Rename template resolved to invalid file name '{}'",
+                        name
+                    ));
+                }
+                name
+            }
+            None => pending.filename.clone(),
+        };
+        Ok((dir, filename))
+    }
+}
+
+/// `{counter}` の位置に `COUNTER_MARKER` を埋め込んだ宛先パスから、採番の範囲となるフォルダ
+/// （番号を含むパスコンポーネントの親）と、そのフォルダ直下の名前から番号を取り出す正規表現を返す。
+fn counter_scope(marked: &Path) -> (PathBuf, Option<regex::Regex>) {
+    let mut scope = PathBuf::new();
+    for component in marked.components() {
+        let text = component.as_os_str().to_string_lossy();
+        if let Some((prefix, suffix)) = text.split_once(COUNTER_MARKER) {
+            let pattern = format!("^{}(\\d+){}$", regex::escape(prefix), regex::escape(suffix));
+            return (scope, regex::Regex::new(&pattern).ok());
+        }
+        scope.push(component);
+    }
+    (scope, None)
+}
+
+/// `dir` 直下の名前のうち `numbered` に一致するものから、最大の番号を返す。
+fn highest_existing_counter(dir: &Path, numbered: &regex::Regex) -> Option<u64> {
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let caps = numbered.captures(name.to_str()?)?;
+            caps[1].parse().ok()
+        })
+        .max()
+}
+
+/// `{counter}` の番号が実行ごとに変わらないよう、`options.sort` の順にファイルを並べる。
+/// 同順のファイルはパスの順に並べる。
+fn sort_for_counter(files: &mut [PendingFile], options: &CounterOptions) {
+    files.sort_by(|a, b| {
+        let order = match options.sort {
+            CounterSort::Name => a.filename.cmp(&b.filename),
+            CounterSort::Modified => a.modified.cmp(&b.modified),
+            CounterSort::Created => a.created.cmp(&b.created),
+            CounterSort::Size => a.file_size.cmp(&b.file_size),
+        }
+        .then_with(|| a.path.cmp(&b.path));
+        if options.descending {
+            order.reverse()
+        } else {
+            order
+        }
+    });
+}
+
+/// 1 ファイルの処理結果
+enum Outcome {
+    Succeeded(FileResult),
+    Skipped(FileResult),
+    Failed(FileResult),
+}
+
+/// 宛先と衝突時の扱いが決まったファイルの移動・コピー。ワーカーのスレッドで実行する。
+struct Transfer {
+    /// 列挙順の番号。結果はこの順に並べ直す
+    seq: usize,
+    pending: PendingFile,
+    dest: PathBuf,
+    conflict: Option<ConflictResolution>,
+}
+
+/// 完了した `Transfer`
+struct Transferred {
+    seq: usize,
+    dest: PathBuf,
+    bytes: u64,
+    outcome: Outcome,
+}
+
+impl Transfer {
+    fn run(self, action: &Action) -> Transferred {
+        let Transfer {
+            seq,
+            pending,
+            dest,
+            conflict,
+        } = self;
+        let result = match action {
+            Action::Move => move_file(&pending.path, &dest, pending.file_size),
+            Action::Copy => copy_and_verify(&pending.path, &dest, pending.file_size),
+        };
+        let file_result = |reason| FileResult {
+            filename: pending.filename.clone(),
+            source_path: pending.path.clone(),
+            destination_path: Some(dest.clone()),
+            reason,
+            conflict,
+        };
+        let (bytes, outcome) = match result {
+            Ok(()) => (pending.file_size, Outcome::Succeeded(file_result(None))),
+            Err(e) => (0, Outcome::Failed(file_result(Some(classify_io_error(&e))))),
+        };
+        Transferred {
+            seq,
+            dest,
+            bytes,
+            outcome,
+        }
+    }
+}
+
+/// ワーカーで実行中の `Transfer` を追跡し、完了したものを含めて結果を記録する。
+struct Transfers {
+    done: mpsc::Receiver<Transferred>,
+    /// 実行中の操作の宛先と、移動の場合は移動元
+    in_flight: HashMap<PathBuf, Option<PathBuf>>,
+    /// この実行で移動・コピーを終えた、ソースディレクトリ配下の宛先。
+    /// 列挙しながら処理するため、宛先で再び拾わないようにする
+    arrived: HashSet<PathBuf>,
+    source_dir: PathBuf,
+    /// 列挙順の番号と結果。完了した順に届くため、最後に番号順に並べる
+    outcomes: Vec<(usize, Outcome)>,
+    bytes_transferred: u64,
+}
+
+impl Transfers {
+    fn new(done: mpsc::Receiver<Transferred>, source_dir: PathBuf) -> Self {
+        Self {
+            done,
+            in_flight: HashMap::new(),
+            arrived: HashSet::new(),
+            source_dir,
+            outcomes: Vec::new(),
+            bytes_transferred: 0,
+        }
+    }
+
+    fn record(&mut self, seq: usize, outcome: Outcome) {
+        self.outcomes.push((seq, outcome));
+    }
+
+    /// `vacated` は移動によって空くパス。コピーの場合は `None`
+    fn start(&mut self, dest: PathBuf, vacated: Option<PathBuf>) {
+        self.in_flight.insert(dest, vacated);
+    }
+
+    fn finish(&mut self, done: Transferred) {
+        self.in_flight.remove(&done.dest);
+        // ソースディレクトリの外の宛先は列挙で拾わないため、記録しない
+        if matches!(done.outcome, Outcome::Succeeded(_)) && done.dest.starts_with(&self.source_dir)
+        {
+            self.arrived.insert(done.dest);
+        }
+        self.bytes_transferred += done.bytes;
+        self.record(done.seq, done.outcome);
+    }
+
+    fn is_in_flight(&self, path: &Path) -> bool {
+        self.in_flight.contains_key(path)
+    }
+
+    /// この実行で移動・コピーした（している）宛先か
+    fn has_arrived(&self, path: &Path) -> bool {
+        self.arrived.contains(path) || self.in_flight.contains_key(path)
+    }
+
+    /// 完了済みの操作を待たずに受け取る
+    fn poll(&mut self) {
+        while let Ok(done) = self.done.try_recv() {
+            self.finish(done);
+        }
+    }
+
+    /// 操作が 1 件完了するまで待つ。ワーカーがすべて終了している場合は実行中の操作を破棄する。
+    fn wait_one(&mut self) {
+        match self.done.recv() {
+            Ok(done) => self.finish(done),
+            Err(_) => self.in_flight.clear(),
+        }
+    }
+
+    fn wait_for(&mut self, dest: &Path) {
+        while self.in_flight.contains_key(dest) {
+            self.wait_one();
+        }
+    }
+
+    /// `dir` にあるファイルを移動している操作が完了するまで待つ。
+    /// 移動で空く途中のパスと宛先を比べると、完了の前後で衝突の判定が変わるため。
+    fn wait_for_vacating(&mut self, dir: &Path) {
+        while self
+            .in_flight
+            .values()
+            .flatten()
+            .any(|source| source.parent() == Some(dir))
+        {
+            self.wait_one();
+        }
+    }
+
+    fn wait_below(&mut self, limit: usize) {
+        while self.in_flight.len() >= limit {
+            self.wait_one();
+        }
+    }
+
+    fn wait_all(&mut self) {
+        while !self.in_flight.is_empty() {
+            self.wait_one();
+        }
+    }
+
+    /// 列挙順に並べた成功・スキップ・エラーの結果
+    fn into_results(mut self) -> (Vec<FileResult>, Vec<FileResult>, Vec<FileResult>) {
+        self.outcomes.sort_by_key(|(seq, _)| *seq);
+        let (mut succeeded, mut skipped, mut errors) = (Vec::new(), Vec::new(), Vec::new());
+        for (_, outcome) in self.outcomes {
+            match outcome {
+                Outcome::Succeeded(r) => succeeded.push(r),
+                Outcome::Skipped(r) => skipped.push(r),
+                Outcome::Failed(r) => errors.push(r),
+            }
+        }
+        (succeeded, skipped, errors)
+    }
+}
+
+/// `on_progress(filename, current, total, bytes_per_second)` を呼びながらルールセットを実行する。
+/// 総数を事前に数えない場合（`scan.count_total` でない通常の走査）、`total` は 0 になる。
+/// `cancel_flag` が `true` になると、処理中のファイルが完了した後、残りのファイルを
+/// 「ユーザーによる中断」としてスキップして早期リターンする。
+pub fn execute_ruleset(
+    ruleset: &Ruleset,
+    on_progress: impl Fn(&str, usize, usize, f64),
+    cancel_flag: &AtomicBool,
+) -> ExecutionResult {
+    run_ruleset(ruleset, None, on_progress, cancel_flag)
+}
+
+/// `execute_ruleset` と同様に実行するが、ソースディレクトリを列挙せず `paths` のファイルだけを対象にする。
+/// `paths` のうちルールセットの列挙範囲外のもの・存在しないものは無視する。
+pub fn execute_ruleset_for_paths(
+    ruleset: &Ruleset,
+    paths: &[PathBuf],
+    on_progress: impl Fn(&str, usize, usize, f64),
+    cancel_flag: &AtomicBool,
+) -> ExecutionResult {
+    run_ruleset(ruleset, Some(paths), on_progress, cancel_flag)
+}
+
+fn run_ruleset(
+    ruleset: &Ruleset,
+    only_paths: Option<&[PathBuf]>,
+    on_progress: impl Fn(&str, usize, usize, f64),
+    cancel_flag: &AtomicBool,
+) -> ExecutionResult {
+    let mut errors = Vec::new();
+
+    let source_dir = ruleset.source_path();
+    let destination_dir = ruleset.destination_path();
+
+    // Check source directory
+    if !source_dir.exists() {
+        return ExecutionResult::failed(
+            ruleset,
+            source_dir,
+            "Source directory does not exist".to_string(),
+        );
+    }
+
+    // パターン・日時・サイズの条件は実行ごとに 1 回だけ解析し、相対的な日時条件は開始時刻を基準に評価する
+    let filters = CompiledFilters::new(&ruleset.filters, Local::now());
+
+    // テンプレート変数がない場合のみ事前に destination_dir を作成する。
+    // テンプレートがある場合はファイルごとに解決して作成する。
+    let mut resolver = DestinationResolver::new(ruleset, &filters);
+    let use_template = resolver.uses_template();
+    if !use_template {
+        if let Err(e) = fs::create_dir_all(&destination_dir) {
+            return ExecutionResult::failed(
+                ruleset,
+                destination_dir,
+                format!("Failed to create destination directory: {}", e),
+            );
+        }
+    }
+
+    // ファイルは列挙しながら処理する。監視で渡されたファイルと、`{counter}` の採番順に並べ替える場合のみ
+    // 先にすべて列挙する。それ以外は `scan.count_total` の場合のみ、事前の走査で総数だけを数える。
+    // 事前の走査も条件の評価まで同じように行うため、フォルダを 2 回辿ることになる。
+    let read_failed = |e: io::Error| {
+        ExecutionResult::failed(
+            ruleset,
+            source_dir.clone(),
+            format!("Failed to read source directory: {}", e),
+        )
+    };
+    let mut streamed = true;
+    let mut ignore = FiloIgnore::new(&source_dir);
+    let (mut files, total): (Box<dyn Iterator<Item = WalkItem>>, usize) = match only_paths {
+        Some(paths) => {
+            streamed = false;
+            let files = collect_given_files(
+                ruleset,
+                &filters,
+                &source_dir,
+                paths,
+                &mut ignore,
+                &mut errors,
+            );
+            let total = files.len();
+            (Box::new(files.into_iter().map(Ok)), total)
+        }
+        None if resolver.uses_counter() => {
+            streamed = false;
+            let mut files = match collect_matching_files(
+                ruleset,
+                &filters,
+                &source_dir,
+                &mut ignore,
+                &mut errors,
+            ) {
+                Ok(files) => files,
+                Err(e) => return read_failed(e),
+            };
+            sort_for_counter(&mut files, &ruleset.counter);
+            let total = files.len();
+            (Box::new(files.into_iter().map(Ok)), total)
+        }
+        None => {
+            let total = if ruleset.scan.count_total {
+                // 解釈できない `.filoignore` の行は、本処理の走査で読み込んだときに報告する
+                let mut ignore = FiloIgnore::new(&source_dir);
+                match SourceWalker::new(ruleset, &filters, &source_dir, &mut ignore) {
+                    Ok(walker) => walker.filter(Result::is_ok).count(),
+                    Err(e) => return read_failed(e),
+                }
+            } else {
+                0
+            };
+            match SourceWalker::new(ruleset, &filters, &source_dir, &mut ignore) {
+                Ok(walker) => (Box::new(walker), total),
+                Err(e) => return read_failed(e),
+            }
+        }
+    };
+
+    let start_time = Instant::now();
+    let mut last_progress_emit: Option<Instant> = None;
+    const PROGRESS_THROTTLE_MS: u128 = 100;
+
+    // テンプレートモード・構成保持モードで create_dir_all の重複呼び出しを避けるキャッシュ
+    let mut created_dirs: HashSet<PathBuf> = HashSet::new();
+    if !use_template {
+        created_dirs.insert(destination_dir.clone());
+    }
+
+    // 宛先の解決と衝突の判定は列挙順に 1 件ずつ行い、移動・コピーだけをワーカーに任せる
+    let parallelism = ruleset.parallelism();
+    let (job_tx, job_rx) = mpsc::channel::<Transfer>();
+    let job_rx = Mutex::new(job_rx);
+    let (done_tx, done_rx) = mpsc::channel();
+    let mut transfers = Transfers::new(done_rx, source_dir.clone());
+    let mut seq = 0;
+    for e in errors {
+        transfers.record(seq, Outcome::Failed(e));
+        seq += 1;
+    }
+
+    let mut cancelled = false;
+    std::thread::scope(|scope| {
+        for _ in 0..parallelism {
+            let (job_rx, done_tx, action) = (&job_rx, done_tx.clone(), &ruleset.action);
+            scope.spawn(move || loop {
+                // 次の操作を待つ間だけ受信側をロックする
+                let job = job_rx.lock().unwrap_or_else(|e| e.into_inner()).recv();
+                let Ok(job) = job else {
+                    break;
+                };
+                if done_tx.send(job.run(action)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(done_tx);
+
+        // 総数を数えていない場合（`total` が 0）は、処理済みの件数だけを通知する
+        let mut current = 0;
+        loop {
+            // キャンセルチェック: 次のファイルを取り出す前に行う。
+            // 実行中のファイルが完了するのを待ち、ループを抜けて残りのファイルをスキップする。
+            if cancel_flag.load(Ordering::Relaxed) {
+                cancelled = true;
+                break;
+            }
+            let Some(item) = files.next() else {
+                break;
+            };
+            transfers.poll();
+            seq += 1;
+            let pending = match item {
+                Ok(pending) if transfers.has_arrived(&pending.path) => continue,
+                Ok(pending) => pending,
+                Err(e) => {
+                    transfers.record(seq, Outcome::Failed(e));
+                    continue;
+                }
+            };
+            current += 1;
+            let elapsed = start_time.elapsed().as_secs_f64();
+            let bps = if elapsed > 0.0 {
+                transfers.bytes_transferred as f64 / elapsed
+            } else {
+                0.0
+            };
+            // 初回・100ms経過・最終ファイルのいずれかで進捗通知する
+            let now = Instant::now();
+            let should_emit = last_progress_emit
+                .is_none_or(|t| now.duration_since(t).as_millis() >= PROGRESS_THROTTLE_MS)
+                || current == total;
+            if should_emit {
+                on_progress(&pending.filename, current, total, bps);
+                last_progress_emit = Some(now);
+            }
+
+            'process: {
+                // 実行中の操作の宛先は、完了後と同じく既にファイルがあるものとして扱う
+                let occupied = |p: &Path| transfers.is_in_flight(p) || p.exists();
+
+                // テンプレート変数がある場合はファイル名からキャプチャを取得して解決する
+                let (resolved_dir, dest_name) = match resolver.resolve(&pending, occupied) {
+                    Ok(resolved) => resolved,
+                    Err(reason) => {
+                        let skip = FileResult {
+                            filename: pending.filename.clone(),
+                            source_path: pending.path.clone(),
+                            destination_path: None,
+                            reason: Some(reason),
+                            conflict: None,
+                        };
+                        transfers.record(seq, Outcome::Skipped(skip));
+                        break 'process;
+                    }
+                };
+
+                // 事前作成していないディレクトリを作成する（キャッシュで重複呼び出しを回避）
+                if !created_dirs.contains(&resolved_dir) {
+                    if let Err(e) = fs::create_dir_all(&resolved_dir) {
+                        let error = FileResult {
+                            filename: pending.filename.clone(),
+                            source_path: pending.path.clone(),
+                            destination_path: None,
+                            reason: Some(format!("Failed to create destination directory: {}", e)),
+                            conflict: None,
+                        };
+                        transfers.record(seq, Outcome::Failed(error));
+                        break 'process;
+                    }
+                    created_dirs.insert(resolved_dir.clone());
+                }
+
+                // 同じ宛先への操作や、宛先のフォルダから移動中の操作が実行中の場合は、
+                // 完了を待ってから既存のファイルと比較する。別名の候補も同じフォルダから選ぶため、フォルダ単位で待つ
+                let dest = resolved_dir.join(&dest_name);
+                transfers.wait_for(&dest);
+                transfers.wait_for_vacating(&resolved_dir);
+                let occupied = |p: &Path| transfers.is_in_flight(p) || p.exists();
+                let (dest_path, conflict) =
+                    match resolve_conflict(ruleset, &pending.path, dest, |p| {
+                        occupied(p).then(|| p.to_path_buf())
+                    }) {
+                        ConflictDecision::Proceed { dest, resolution } => (dest, resolution),
+                        ConflictDecision::Skip { dest, reason } => {
+                            let skip = FileResult {
+                                filename: pending.filename.clone(),
+                                source_path: pending.path.clone(),
+                                destination_path: Some(dest),
+                                reason: Some(reason),
+                                conflict: Some(ConflictResolution::Skipped),
+                            };
+                            transfers.record(seq, Outcome::Skipped(skip));
+                            break 'process;
+                        }
+                    };
+
+                // 実行中の操作が `parallelism` 件未満になってからワーカーに渡す
+                transfers.wait_below(parallelism);
+                let vacated = matches!(ruleset.action, Action::Move).then(|| pending.path.clone());
+                transfers.start(dest_path.clone(), vacated);
+                let transfer = Transfer {
+                    seq,
+                    pending,
+                    dest: dest_path,
+                    conflict,
+                };
+                if let Err(mpsc::SendError(transfer)) = job_tx.send(transfer) {
+                    // ワーカーが終了している場合はこのスレッドで実行する
+                    transfers.finish(transfer.run(&ruleset.action));
+                }
+            } // end 'process
+        }
+
+        transfers.wait_all();
+        drop(job_tx);
+    });
+
+    // 列挙しながら処理している場合は、キャンセル後に残りのファイルを列挙せず、
+    // 一覧に載っていないファイルがあることを 1 件のスキップで示す。先に列挙したファイルは 1 件ずつスキップにする
+    if cancelled && streamed {
+        let cancelled = FileResult {
+            filename: String::new(),
+            source_path: source_dir.clone(),
+            destination_path: None,
+            reason: Some(CANCELLED_UNLISTED_REASON.to_string()),
+            conflict: None,
+        };
+        transfers.record(seq + 1, Outcome::Skipped(cancelled));
+    } else {
+        for item in &mut files {
+            seq += 1;
+            match item {
+                Ok(rem) if transfers.has_arrived(&rem.path) => {}
+                Ok(rem) => {
+                    let cancelled = FileResult {
+                        filename: rem.filename,
+                        source_path: rem.path,
+                        destination_path: None,
+                        reason: Some("Cancelled by user".to_string()),
+                        conflict: None,
+                    };
+                    transfers.record(seq, Outcome::Skipped(cancelled));
+                }
+                Err(e) => transfers.record(seq, Outcome::Failed(e)),
+            }
+        }
+    }
+    // 列挙を終えてから `.filoignore` の読み込み結果を取り出す
+    drop(files);
+
+    // 無視した `.filoignore` の行は、列挙中に見つかった順に一覧の最後に示す
+    for line in ignore.take_invalid_lines() {
+        transfers.record(
+            usize::MAX,
+            Outcome::Skipped(invalid_ignore_line_result(line)),
+        );
+    }
+
+    let (succeeded, skipped, errors) = transfers.into_results();
+    let status = ExecutionResult::determine_status(&succeeded, &errors);
+
+    ExecutionResult {
+        ruleset_id: ruleset.id.clone(),
+        ruleset_name: ruleset.name.clone(),
+        action: ruleset.action.clone(),
+        status,
+        succeeded,
+        skipped,
+        errors,
+    }
+}
+
+/// ファイルシステムを変更せずに、`execute_ruleset` と同じ列挙・フィルタ・宛先解決を行い、
+/// 実行した場合の操作一覧を返す。
+/// 同じ実行内で宛先が重複するファイルは、先に処理されるファイルが宛先を占有したものとして予測する。
+pub fn preview_ruleset(ruleset: &Ruleset) -> ExecutionPlan {
+    let source_dir = ruleset.source_path();
+
+    if !source_dir.exists() {
+        return ExecutionPlan::failed(
+            ruleset,
+            source_dir,
+            "Source directory does not exist".to_string(),
+        );
+    }
+
+    let filters = CompiledFilters::new(&ruleset.filters, Local::now());
+    let mut errors = Vec::new();
+    let mut ignore = FiloIgnore::new(&source_dir);
+    let mut matching_files =
+        match collect_matching_files(ruleset, &filters, &source_dir, &mut ignore, &mut errors) {
+            Ok(files) => files,
+            Err(e) => {
+                return ExecutionPlan::failed(
+                    ruleset,
+                    source_dir,
+                    format!("Failed to read source directory: {}", e),
+                );
+            }
+        };
+
+    let mut operations: Vec<PlannedOperation> = errors
+        .into_iter()
+        .map(|e| PlannedOperation {
+            filename: e.filename,
+            source_path: e.source_path,
+            destination_path: None,
+            action: PlannedAction::Error,
+            reason: e.reason,
+            conflict: None,
+        })
+        .collect();
+
+    let mut resolver = DestinationResolver::new(ruleset, &filters);
+    if resolver.uses_counter() {
+        sort_for_counter(&mut matching_files, &ruleset.counter);
+    }
+    let planned_action = match ruleset.action {
+        Action::Move => PlannedAction::Move,
+        Action::Copy => PlannedAction::Copy,
+    };
+    // このプレビュー内で既に宛先として予約されたパスと、そこに置かれる予定のソースファイル
+    let mut claimed: HashMap<PathBuf, PathBuf> = HashMap::new();
+
+    for pending in &matching_files {
+        let is_taken = |p: &Path| claimed.contains_key(p) || p.exists();
+        let (resolved_dir, dest_name) = match resolver.resolve(pending, is_taken) {
+            Ok(resolved) => resolved,
+            Err(reason) => {
+                operations.push(PlannedOperation {
+                    filename: pending.filename.clone(),
+                    source_path: pending.path.clone(),
+                    destination_path: None,
+                    action: PlannedAction::Skip,
+                    reason: Some(reason),
+                    conflict: None,
+                });
+                continue;
+            }
+        };
+
+        let decision =
+            resolve_conflict(ruleset, &pending.path, resolved_dir.join(&dest_name), |p| {
+                claimed
+                    .get(p)
+                    .cloned()
+                    .or_else(|| p.exists().then(|| p.to_path_buf()))
+            });
+        let operation = match decision {
+            ConflictDecision::Proceed { dest, resolution } => {
+                claimed.insert(dest.clone(), pending.path.clone());
+                let reason = match resolution {
+                    Some(ConflictResolution::Overwritten) => {
+                        Some("Existing file at destination will be overwritten".to_string())
+                    }
+                    Some(ConflictResolution::Renamed) => {
+                        Some("Will be renamed to avoid a name conflict".to_string())
+                    }
+                    _ => None,
+                };
+                PlannedOperation {
+                    filename: pending.filename.clone(),
+                    source_path: pending.path.clone(),
+                    destination_path: Some(dest),
+                    action: planned_action.clone(),
+                    reason,
+                    conflict: resolution,
+                }
+            }
+            ConflictDecision::Skip { dest, reason } => PlannedOperation {
+                filename: pending.filename.clone(),
+                source_path: pending.path.clone(),
+                destination_path: Some(dest),
+                action: PlannedAction::Skip,
+                reason: Some(reason),
+                conflict: Some(ConflictResolution::Skipped),
+            },
+        };
+        operations.push(operation);
+    }
+    operations.extend(ignore.take_invalid_lines().into_iter().map(|line| {
+        let skip = invalid_ignore_line_result(line);
+        PlannedOperation {
+            filename: skip.filename,
+            source_path: skip.source_path,
+            destination_path: None,
+            action: PlannedAction::Skip,
+            reason: skip.reason,
+            conflict: None,
+        }
+    }));
+
+    ExecutionPlan {
+        ruleset_id: ruleset.id.clone(),
+        ruleset_name: ruleset.name.clone(),
+        action: ruleset.action.clone(),
+        operations,
+    }
+}
+
+pub fn undo_file_move(source_path: &Path, destination_path: &Path) -> Result<(), String> {
+    // destination_path is where the file currently is (moved to)
+    // source_path is where it should go back to (original location)
+    if !destination_path.exists() {
+        return Err("File no longer exists at destination".to_string());
     }
 
     if source_path.exists() {
         return Err("File already exists at original location".to_string());
     }
 
-    // Ensure parent directory exists
-    if let Some(parent) = source_path.parent() {
-        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    // Ensure parent directory exists
+    if let Some(parent) = source_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+
+    let file_size = fs::metadata(destination_path)
+        .map(|m| m.len())
+        .unwrap_or(0);
+    move_file(destination_path, source_path, file_size).map_err(|e| classify_io_error(&e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ruleset::{
+        ConflictPolicy, CounterOptions, FilenameFilter, Filters, MatchType, ScanOptions,
+    };
+
+    fn no_cancel() -> AtomicBool {
+        AtomicBool::new(false)
+    }
+
+    fn create_test_ruleset(source: &Path, dest: &Path) -> Ruleset {
+        Ruleset {
+            id: "test-id".to_string(),
+            name: "test".to_string(),
+            enabled: true,
+            source_dir: source.to_str().unwrap().to_string(),
+            destination_dir: dest.to_str().unwrap().to_string(),
+            action: Action::Move,
+            on_conflict: ConflictPolicy::Skip,
+            rename_pattern: None,
+            rename: None,
+            counter: CounterOptions::default(),
+            filters: Filters {
+                extensions: Some(vec![".txt".to_string()]),
+                filename: None,
+                created_at: None,
+                modified_at: None,
+                accessed_at: None,
+                size: None,
+                exclude: None,
+                all: None,
+                any: None,
+                not: None,
+                captures: None,
+            },
+            scan: ScanOptions::default(),
+            watch: false,
+            schedule: None,
+            parallelism: None,
+        }
+    }
+
+    #[test]
+    fn test_move_files_basic() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+
+        fs::write(src.path().join("hello.txt"), "content").unwrap();
+        fs::write(src.path().join("world.txt"), "content2").unwrap();
+
+        let ruleset = create_test_ruleset(src.path(), dst.path());
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
+        assert_eq!(result.status, ExecutionStatus::Completed);
+        assert_eq!(result.succeeded.len(), 2);
+        assert_eq!(result.skipped.len(), 0);
+        assert_eq!(result.errors.len(), 0);
+
+        // Source files should be gone
+        assert!(!src.path().join("hello.txt").exists());
+        assert!(!src.path().join("world.txt").exists());
+
+        // Destination files should exist
+        assert!(dst.path().join("hello.txt").exists());
+        assert!(dst.path().join("world.txt").exists());
+    }
+
+    #[test]
+    fn test_copy_files_basic() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+
+        fs::write(src.path().join("hello.txt"), "content").unwrap();
+
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.action = Action::Copy;
+
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
+        assert_eq!(result.status, ExecutionStatus::Completed);
+        assert_eq!(result.succeeded.len(), 1);
+
+        // Source file should still exist
+        assert!(src.path().join("hello.txt").exists());
+        // Destination file should also exist
+        assert!(dst.path().join("hello.txt").exists());
+    }
+
+    #[test]
+    fn test_skip_when_overwrite_disabled() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+
+        fs::write(src.path().join("exists.txt"), "new content").unwrap();
+        fs::write(dst.path().join("exists.txt"), "old content").unwrap();
+
+        let ruleset = create_test_ruleset(src.path(), dst.path());
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
+        assert_eq!(result.skipped.len(), 1);
+        // Old content should remain
+        assert_eq!(
+            fs::read_to_string(dst.path().join("exists.txt")).unwrap(),
+            "old content"
+        );
+    }
+
+    #[test]
+    fn test_copy_skip_when_overwrite_disabled() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+
+        fs::write(src.path().join("exists.txt"), "new content").unwrap();
+        fs::write(dst.path().join("exists.txt"), "old content").unwrap();
+
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.action = Action::Copy;
+
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
+        assert_eq!(result.skipped.len(), 1);
+        // コピー元ファイルは残っている
+        assert!(src.path().join("exists.txt").exists());
+        // コピー先の古い内容が保護されている
+        assert_eq!(
+            fs::read_to_string(dst.path().join("exists.txt")).unwrap(),
+            "old content"
+        );
+    }
+
+    #[test]
+    fn test_overwrite_when_enabled() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+
+        fs::write(src.path().join("exists.txt"), "new content").unwrap();
+        fs::write(dst.path().join("exists.txt"), "old content").unwrap();
+
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.on_conflict = ConflictPolicy::Overwrite;
+
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
+        assert_eq!(result.succeeded.len(), 1);
+        assert_eq!(result.skipped.len(), 0);
+        assert_eq!(
+            fs::read_to_string(dst.path().join("exists.txt")).unwrap(),
+            "new content"
+        );
+    }
+
+    #[test]
+    fn test_overwrite_records_conflict_resolution() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+
+        fs::write(src.path().join("exists.txt"), "new content").unwrap();
+        fs::write(src.path().join("fresh.txt"), "content").unwrap();
+        fs::write(dst.path().join("exists.txt"), "old content").unwrap();
+
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.on_conflict = ConflictPolicy::Overwrite;
+        let mut result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+        result.succeeded.sort_by(|a, b| a.filename.cmp(&b.filename));
+
+        assert_eq!(
+            result.succeeded[0].conflict,
+            Some(ConflictResolution::Overwritten)
+        );
+        assert_eq!(result.succeeded[1].conflict, None);
+    }
+
+    #[test]
+    fn test_auto_rename_default_pattern() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+
+        fs::write(src.path().join("report.txt"), "new content").unwrap();
+        fs::write(dst.path().join("report.txt"), "old content").unwrap();
+        fs::write(dst.path().join("report (1).txt"), "older content").unwrap();
+
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.on_conflict = ConflictPolicy::AutoRename;
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
+        assert_eq!(result.succeeded.len(), 1);
+        assert_eq!(
+            result.succeeded[0].conflict,
+            Some(ConflictResolution::Renamed)
+        );
+        assert_eq!(
+            result.succeeded[0].destination_path,
+            Some(dst.path().join("report (2).txt"))
+        );
+        assert_eq!(
+            fs::read_to_string(dst.path().join("report (2).txt")).unwrap(),
+            "new content"
+        );
+        assert_eq!(
+            fs::read_to_string(dst.path().join("report.txt")).unwrap(),
+            "old content"
+        );
+    }
+
+    #[test]
+    fn test_auto_rename_custom_pattern() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+
+        fs::write(src.path().join("report.txt"), "new content").unwrap();
+        fs::write(dst.path().join("report.txt"), "old content").unwrap();
+
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.on_conflict = ConflictPolicy::AutoRename;
+        ruleset.rename_pattern = Some("{stem}_dup{n}{dotext}".to_string());
+        execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
+        assert!(dst.path().join("report_dup1.txt").exists());
+
+        fs::write(src.path().join("report.txt"), "newer content").unwrap();
+        ruleset.rename_pattern = Some("{stem}_{n|pad:3}{dotext}".to_string());
+        execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
+        assert!(dst.path().join("report_001.txt").exists());
+    }
+
+    #[test]
+    fn test_auto_rename_unknown_variable_skips() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        fs::write(src.path().join("report.txt"), "new content").unwrap();
+        fs::write(dst.path().join("report.txt"), "old content").unwrap();
+
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.on_conflict = ConflictPolicy::AutoRename;
+        ruleset.rename_pattern = Some("{stem} ({n}){ext}".to_string());
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
+        assert!(result.succeeded.is_empty());
+        assert!(result.skipped[0]
+            .reason
+            .as_deref()
+            .unwrap()
+            .contains("{ext}"));
+        assert_eq!(fs::read_dir(dst.path()).unwrap().count(), 1);
+        assert!(src.path().join("report.txt").exists());
+    }
+
+    #[test]
+    fn test_keep_newer() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+
+        let old = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        fs::write(src.path().join("newer.txt"), "source").unwrap();
+        fs::write(dst.path().join("newer.txt"), "dest").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(dst.path().join("newer.txt"))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        fs::write(src.path().join("older.txt"), "source").unwrap();
+        fs::write(dst.path().join("older.txt"), "dest").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(src.path().join("older.txt"))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.on_conflict = ConflictPolicy::KeepNewer;
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
+        assert_eq!(result.succeeded.len(), 1);
+        assert_eq!(result.succeeded[0].filename, "newer.txt");
+        assert_eq!(result.skipped.len(), 1);
+        assert_eq!(
+            result.skipped[0].conflict,
+            Some(ConflictResolution::Skipped)
+        );
+        assert_eq!(
+            fs::read_to_string(dst.path().join("newer.txt")).unwrap(),
+            "source"
+        );
+        assert_eq!(
+            fs::read_to_string(dst.path().join("older.txt")).unwrap(),
+            "dest"
+        );
+    }
+
+    #[test]
+    fn test_keep_larger() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+
+        fs::write(src.path().join("big.txt"), "larger source").unwrap();
+        fs::write(dst.path().join("big.txt"), "small").unwrap();
+        fs::write(src.path().join("small.txt"), "small").unwrap();
+        fs::write(dst.path().join("small.txt"), "larger dest").unwrap();
+
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.on_conflict = ConflictPolicy::KeepLarger;
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
+        assert_eq!(result.succeeded.len(), 1);
+        assert_eq!(result.succeeded[0].filename, "big.txt");
+        assert_eq!(
+            fs::read_to_string(dst.path().join("big.txt")).unwrap(),
+            "larger source"
+        );
+        assert!(src.path().join("small.txt").exists());
+    }
+
+    #[test]
+    fn test_skip_if_identical() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+
+        fs::write(src.path().join("same.txt"), "same content").unwrap();
+        fs::write(dst.path().join("same.txt"), "same content").unwrap();
+        fs::write(src.path().join("diff.txt"), "source content").unwrap();
+        fs::write(dst.path().join("diff.txt"), "dest content!!").unwrap();
+
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.on_conflict = ConflictPolicy::SkipIfIdentical;
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
+        assert_eq!(result.skipped.len(), 1);
+        assert_eq!(result.skipped[0].filename, "same.txt");
+        assert!(src.path().join("same.txt").exists());
+
+        // 内容が異なる場合は別名で保存され、既存ファイルは保持される
+        assert_eq!(result.succeeded.len(), 1);
+        assert_eq!(
+            result.succeeded[0].conflict,
+            Some(ConflictResolution::Renamed)
+        );
+        assert_eq!(
+            fs::read_to_string(dst.path().join("diff (1).txt")).unwrap(),
+            "source content"
+        );
+        assert_eq!(
+            fs::read_to_string(dst.path().join("diff.txt")).unwrap(),
+            "dest content!!"
+        );
+    }
+
+    #[test]
+    fn test_files_identical() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a");
+        let b = dir.path().join("b");
+        let c = dir.path().join("c");
+        fs::write(&a, "x".repeat(20000)).unwrap();
+        fs::write(&b, "x".repeat(20000)).unwrap();
+        fs::write(&c, "x".repeat(19999) + "y").unwrap();
+
+        assert!(files_identical(&a, &b).unwrap());
+        assert!(!files_identical(&a, &c).unwrap());
+    }
+
+    #[test]
+    fn test_filter_only_matching_files() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+
+        fs::write(src.path().join("match.txt"), "content").unwrap();
+        fs::write(src.path().join("skip.pdf"), "content").unwrap();
+
+        let ruleset = create_test_ruleset(src.path(), dst.path());
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
+        assert_eq!(result.succeeded.len(), 1);
+        assert_eq!(result.succeeded[0].filename, "match.txt");
+
+        // pdf should remain in source
+        assert!(src.path().join("skip.pdf").exists());
     }
 
-    let file_size = fs::metadata(destination_path)
-        .map(|m| m.len())
-        .unwrap_or(0);
-    move_file(destination_path, source_path, file_size).map_err(|e| classify_io_error(&e))
-}
+    #[test]
+    fn test_source_dir_not_exists() {
+        let dst = tempfile::tempdir().unwrap();
+        let non_existent = PathBuf::from("/tmp/filo_test_nonexistent_dir");
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::ruleset::{FilenameFilter, Filters, MatchType};
+        let ruleset = create_test_ruleset(&non_existent, dst.path());
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
 
-    fn no_cancel() -> AtomicBool {
-        AtomicBool::new(false)
+        assert_eq!(result.status, ExecutionStatus::Failed);
+        assert_eq!(result.errors.len(), 1);
     }
 
-    fn create_test_ruleset(source: &Path, dest: &Path) -> Ruleset {
-        Ruleset {
-            id: "test-id".to_string(),
-            name: "test".to_string(),
-            enabled: true,
-            source_dir: source.to_str().unwrap().to_string(),
-            destination_dir: dest.to_str().unwrap().to_string(),
-            action: Action::Move,
-            overwrite: false,
-            filters: Filters {
-                extensions: Some(vec![".txt".to_string()]),
-                filename: None,
-                created_at: None,
-                modified_at: None,
-            },
-        }
+    #[test]
+    fn test_creates_destination_dir() {
+        let src = tempfile::tempdir().unwrap();
+        let dst_base = tempfile::tempdir().unwrap();
+        let dst = dst_base.path().join("new_subdir");
+
+        fs::write(src.path().join("file.txt"), "content").unwrap();
+
+        let ruleset = create_test_ruleset(src.path(), &dst);
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
+        assert_eq!(result.status, ExecutionStatus::Completed);
+        assert!(dst.join("file.txt").exists());
     }
 
     #[test]
-    fn test_move_files_basic() {
+    fn test_skip_directories_in_source() {
         let src = tempfile::tempdir().unwrap();
         let dst = tempfile::tempdir().unwrap();
 
-        fs::write(src.path().join("hello.txt"), "content").unwrap();
-        fs::write(src.path().join("world.txt"), "content2").unwrap();
+        fs::create_dir(src.path().join("subdir")).unwrap();
+        fs::write(src.path().join("file.txt"), "content").unwrap();
 
         let ruleset = create_test_ruleset(src.path(), dst.path());
         let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
 
+        assert_eq!(result.succeeded.len(), 1);
+        // Subdirectory should remain
+        assert!(src.path().join("subdir").exists());
+    }
+
+    #[test]
+    fn test_glob_filename_filter() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+
+        fs::write(src.path().join("screenshot_001.txt"), "content").unwrap();
+        fs::write(src.path().join("photo.txt"), "content").unwrap();
+
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.filters.filename = Some(FilenameFilter {
+            pattern: "screenshot_*".to_string(),
+            match_type: MatchType::Glob,
+        });
+
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
+        assert_eq!(result.succeeded.len(), 1);
+        assert_eq!(result.succeeded[0].filename, "screenshot_001.txt");
+    }
+
+    // --- 再帰走査のテスト ---
+
+    fn create_nested_source(src: &Path) {
+        fs::write(src.join("top.txt"), "content").unwrap();
+        fs::create_dir_all(src.join("a/b")).unwrap();
+        fs::write(src.join("a/mid.txt"), "content").unwrap();
+        fs::write(src.join("a/b/deep.txt"), "content").unwrap();
+    }
+
+    #[test]
+    fn test_recursive_flatten() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        create_nested_source(src.path());
+
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.scan.recursive = true;
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
         assert_eq!(result.status, ExecutionStatus::Completed);
+        assert_eq!(result.succeeded.len(), 3);
+        assert!(dst.path().join("top.txt").exists());
+        assert!(dst.path().join("mid.txt").exists());
+        assert!(dst.path().join("deep.txt").exists());
+        // 空になったサブディレクトリは残る
+        assert!(src.path().join("a/b").is_dir());
+    }
+
+    #[test]
+    fn test_recursive_preserve_structure() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        create_nested_source(src.path());
+
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.scan.recursive = true;
+        ruleset.scan.layout = DestinationLayout::Preserve;
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
+        assert_eq!(result.succeeded.len(), 3);
+        assert!(dst.path().join("top.txt").exists());
+        assert!(dst.path().join("a/mid.txt").exists());
+        assert!(dst.path().join("a/b/deep.txt").exists());
+    }
+
+    #[test]
+    fn test_recursive_max_depth() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        create_nested_source(src.path());
+
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.scan.recursive = true;
+        ruleset.scan.max_depth = Some(1);
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
         assert_eq!(result.succeeded.len(), 2);
-        assert_eq!(result.skipped.len(), 0);
-        assert_eq!(result.errors.len(), 0);
+        assert!(src.path().join("a/b/deep.txt").exists());
+    }
 
-        // Source files should be gone
-        assert!(!src.path().join("hello.txt").exists());
-        assert!(!src.path().join("world.txt").exists());
+    #[test]
+    fn test_template_destination_inside_source_is_not_rescanned() {
+        let src = tempfile::tempdir().unwrap();
+        fs::write(src.path().join("a.txt"), "content").unwrap();
 
-        // Destination files should exist
-        assert!(dst.path().join("hello.txt").exists());
-        assert!(dst.path().join("world.txt").exists());
+        let mut ruleset = create_test_ruleset(src.path(), Path::new(""));
+        ruleset.destination_dir = format!("{}/sorted/{{ext}}", src.path().display());
+        ruleset.scan.recursive = true;
+        ruleset.on_conflict = ConflictPolicy::AutoRename;
+        let first = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+        assert_eq!(first.succeeded.len(), 1);
+
+        // 2 回目以降も整理済みのファイルを拾わない
+        for _ in 0..2 {
+            let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+            assert!(result.succeeded.is_empty());
+            assert!(result.skipped.is_empty());
+        }
+        let sorted: Vec<_> = fs::read_dir(src.path().join("sorted/txt"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(sorted, vec!["a.txt"]);
     }
 
     #[test]
-    fn test_copy_files_basic() {
+    fn test_destination_same_as_source_file_is_skipped() {
+        let src = tempfile::tempdir().unwrap();
+        fs::write(src.path().join("a.txt"), "content").unwrap();
+
+        let mut ruleset = create_test_ruleset(src.path(), src.path());
+        ruleset.action = Action::Copy;
+        ruleset.on_conflict = ConflictPolicy::Overwrite;
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
+        assert!(result.succeeded.is_empty());
+        assert!(result.errors.is_empty());
+        assert_eq!(
+            result.skipped[0].reason.as_deref(),
+            Some(SAME_FILE_SKIP_REASON)
+        );
+        assert_eq!(
+            fs::read_to_string(src.path().join("a.txt")).unwrap(),
+            "content"
+        );
+    }
+
+    #[test]
+    fn test_progress_total_counted_only_when_requested() {
         let src = tempfile::tempdir().unwrap();
         let dst = tempfile::tempdir().unwrap();
+        create_nested_source(src.path());
+        fs::write(src.path().join("a/skip.log"), "content").unwrap();
 
-        fs::write(src.path().join("hello.txt"), "content").unwrap();
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.action = Action::Copy;
+        ruleset.scan.recursive = true;
+        let progress = std::cell::RefCell::new(Vec::new());
+        let record = |_: &str, current, total, _| progress.borrow_mut().push((current, total));
+
+        // 既定では列挙しながら処理し、処理済みの件数だけを通知する
+        let result = execute_ruleset(&ruleset, record, &no_cancel());
+        assert_eq!(result.succeeded.len(), 3);
+        assert!(progress.borrow().iter().all(|&(_, total)| total == 0));
+        assert_eq!(progress.borrow()[0], (1, 0));
+
+        ruleset.scan.count_total = true;
+        ruleset.on_conflict = ConflictPolicy::Overwrite;
+        progress.borrow_mut().clear();
+        execute_ruleset(&ruleset, record, &no_cancel());
+        assert_eq!(progress.borrow().last(), Some(&(3, 3)));
+    }
+
+    #[test]
+    fn test_streaming_does_not_pick_up_arrived_files() {
+        let src = tempfile::tempdir().unwrap();
+        fs::create_dir_all(src.path().join("sorted")).unwrap();
+        fs::write(src.path().join("one.txt"), "content").unwrap();
+
+        // 宛先がソース配下のテンプレートの場合、移動先のフォルダも走査の対象になる
+        let mut ruleset = create_test_ruleset(src.path(), Path::new(""));
+        ruleset.destination_dir = format!("{}/sorted/{{ext}}", src.path().display());
+        ruleset.scan.recursive = true;
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
+        assert_eq!(result.succeeded.len(), 1);
+        assert!(result.skipped.is_empty());
+        assert!(src.path().join("sorted/txt/one.txt").exists());
+    }
+
+    #[test]
+    fn test_parallel_results_keep_enumeration_order() {
+        let src = tempfile::tempdir().unwrap();
+        for i in 0..40 {
+            fs::write(src.path().join(format!("file{:02}.txt", i)), "x".repeat(i)).unwrap();
+        }
+        let run = |parallelism| {
+            let dst = tempfile::tempdir().unwrap();
+            let mut ruleset = create_test_ruleset(src.path(), dst.path());
+            ruleset.action = Action::Copy;
+            ruleset.parallelism = Some(parallelism);
+            let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+            assert_eq!(fs::read_dir(dst.path()).unwrap().count(), 40);
+            result
+                .succeeded
+                .into_iter()
+                .map(|r| r.source_path)
+                .collect::<Vec<_>>()
+        };
+
+        let sequential = run(1);
+        assert_eq!(sequential.len(), 40);
+        assert_eq!(run(8), sequential);
+    }
 
+    #[test]
+    fn test_parallel_conflicts_match_sequential_processing() {
+        let src = tempfile::tempdir().unwrap();
+        for i in 0..20 {
+            fs::write(src.path().join(format!("file{:02}.txt", i)), i.to_string()).unwrap();
+        }
+        let dst = tempfile::tempdir().unwrap();
         let mut ruleset = create_test_ruleset(src.path(), dst.path());
         ruleset.action = Action::Copy;
+        ruleset.rename = Some("same.txt".to_string());
+        ruleset.parallelism = Some(8);
 
+        // 同じ宛先への上書きは列挙順に行われ、最後のファイルの内容が残る
+        ruleset.on_conflict = ConflictPolicy::Overwrite;
         let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+        assert_eq!(result.succeeded.len(), 20);
+        let last = &result.succeeded.last().unwrap().source_path;
+        assert_eq!(
+            fs::read_to_string(dst.path().join("same.txt")).unwrap(),
+            fs::read_to_string(last).unwrap()
+        );
 
-        assert_eq!(result.status, ExecutionStatus::Completed);
+        // 別名保存では実行中の宛先も使用済みとして扱い、名前が重ならない
+        ruleset.on_conflict = ConflictPolicy::AutoRename;
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+        assert_eq!(result.succeeded.len(), 20);
+        assert!(result.errors.is_empty());
+        assert_eq!(fs::read_dir(dst.path()).unwrap().count(), 21);
+    }
+
+    #[test]
+    fn test_conflict_check_waits_for_moves_out_of_destination_dir() {
+        let (done_tx, done_rx) = mpsc::channel();
+        let dir = PathBuf::from("/src");
+        let mut transfers = Transfers::new(done_rx, dir.clone());
+        transfers.start(PathBuf::from("/dst/b.txt"), Some(dir.join("b.txt")));
+        transfers.start(PathBuf::from("/dst/c.txt"), None);
+
+        let sender = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            done_tx
+                .send(Transferred {
+                    seq: 1,
+                    dest: PathBuf::from("/dst/b.txt"),
+                    bytes: 0,
+                    outcome: Outcome::Succeeded(FileResult {
+                        filename: "b.txt".to_string(),
+                        source_path: PathBuf::from("/src/b.txt"),
+                        destination_path: Some(PathBuf::from("/dst/b.txt")),
+                        reason: None,
+                        conflict: None,
+                    }),
+                })
+                .unwrap();
+        });
+
+        // 移動元のフォルダを宛先にする前に、移動の完了を待つ。コピーは待たない
+        transfers.wait_for_vacating(&dir);
+        assert!(!transfers.is_in_flight(Path::new("/dst/b.txt")));
+        assert!(transfers.is_in_flight(Path::new("/dst/c.txt")));
+        sender.join().unwrap();
+    }
+
+    #[test]
+    fn test_cancel_skips_remaining_streamed_files() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        create_nested_source(src.path());
+
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.scan.recursive = true;
+        let cancel = AtomicBool::new(false);
+        let result = execute_ruleset(
+            &ruleset,
+            |_, _, _, _| cancel.store(true, Ordering::Relaxed),
+            &cancel,
+        );
+
+        // 残りのファイルは列挙せず、1 件のスキップだけを返す
         assert_eq!(result.succeeded.len(), 1);
+        assert_eq!(result.skipped.len(), 1);
+        assert_eq!(
+            result.skipped[0].reason.as_deref(),
+            Some(CANCELLED_UNLISTED_REASON)
+        );
+        assert_eq!(result.skipped[0].source_path, src.path());
+    }
 
-        // Source file should still exist
-        assert!(src.path().join("hello.txt").exists());
-        // Destination file should also exist
-        assert!(dst.path().join("hello.txt").exists());
+    #[test]
+    fn test_cancel_skips_each_remaining_given_file() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        let paths: Vec<PathBuf> = ["a.txt", "b.txt", "c.txt"]
+            .iter()
+            .map(|name| src.path().join(name))
+            .collect();
+        for path in &paths {
+            fs::write(path, "content").unwrap();
+        }
+
+        let ruleset = create_test_ruleset(src.path(), dst.path());
+        let cancel = AtomicBool::new(false);
+        let result = execute_ruleset_for_paths(
+            &ruleset,
+            &paths,
+            |_, _, _, _| cancel.store(true, Ordering::Relaxed),
+            &cancel,
+        );
+
+        assert_eq!(result.succeeded.len(), 1);
+        assert_eq!(result.skipped.len(), 2);
+        assert!(result
+            .skipped
+            .iter()
+            .all(|r| r.reason.as_deref() == Some("Cancelled by user")));
     }
 
     #[test]
-    fn test_skip_when_overwrite_disabled() {
+    fn test_cancel_before_start_skips_everything() {
         let src = tempfile::tempdir().unwrap();
         let dst = tempfile::tempdir().unwrap();
+        fs::write(src.path().join("a.txt"), "content").unwrap();
 
-        fs::write(src.path().join("exists.txt"), "new content").unwrap();
-        fs::write(dst.path().join("exists.txt"), "old content").unwrap();
+        let ruleset = create_test_ruleset(src.path(), dst.path());
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &AtomicBool::new(true));
+
+        assert!(result.succeeded.is_empty());
+        assert_eq!(
+            result.skipped[0].reason.as_deref(),
+            Some(CANCELLED_UNLISTED_REASON)
+        );
+        assert!(src.path().join("a.txt").exists());
+    }
+
+    #[test]
+    fn test_recursive_skips_destination_inside_source() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = src.path().join("sorted");
+        fs::create_dir(&dst).unwrap();
+        fs::write(dst.join("already.txt"), "content").unwrap();
+        fs::write(src.path().join("new.txt"), "content").unwrap();
+
+        let mut ruleset = create_test_ruleset(src.path(), &dst);
+        ruleset.scan.recursive = true;
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
+        assert_eq!(result.succeeded.len(), 1);
+        assert_eq!(result.skipped.len(), 0);
+        assert!(dst.join("new.txt").exists());
+        assert!(dst.join("already.txt").exists());
+    }
+
+    #[test]
+    fn test_execute_for_paths_processes_only_given_files() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        fs::write(src.path().join("old.txt"), "content").unwrap();
+        fs::write(src.path().join("new.txt"), "content").unwrap();
+        fs::write(src.path().join("new.jpg"), "content").unwrap();
 
         let ruleset = create_test_ruleset(src.path(), dst.path());
+        let paths = vec![
+            src.path().join("new.txt"),
+            src.path().join("new.jpg"),
+            // 処理前に消えたファイルはエラーにしない
+            src.path().join("gone.txt"),
+        ];
+        let result = execute_ruleset_for_paths(&ruleset, &paths, |_, _, _, _| {}, &no_cancel());
+
+        assert_eq!(result.status, ExecutionStatus::Completed);
+        assert_eq!(result.succeeded.len(), 1);
+        assert!(result.errors.is_empty());
+        assert!(dst.path().join("new.txt").exists());
+        assert!(src.path().join("old.txt").exists());
+        assert!(src.path().join("new.jpg").exists());
+    }
+
+    #[test]
+    fn test_execute_for_paths_respects_scan_scope() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = src.path().join("sorted");
+        fs::create_dir_all(src.path().join("sub")).unwrap();
+        fs::create_dir(&dst).unwrap();
+        fs::write(src.path().join("sub/nested.txt"), "content").unwrap();
+        fs::write(dst.join("moved.txt"), "content").unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        fs::write(outside.path().join("other.txt"), "content").unwrap();
+
+        let mut ruleset = create_test_ruleset(src.path(), &dst);
+        let paths = vec![
+            src.path().join("sub/nested.txt"),
+            dst.join("moved.txt"),
+            outside.path().join("other.txt"),
+        ];
+        let result = execute_ruleset_for_paths(&ruleset, &paths, |_, _, _, _| {}, &no_cancel());
+        assert!(result.succeeded.is_empty());
+
+        ruleset.scan.recursive = true;
+        let result = execute_ruleset_for_paths(&ruleset, &paths, |_, _, _, _| {}, &no_cancel());
+        assert_eq!(result.succeeded.len(), 1);
+        assert!(dst.join("nested.txt").exists());
+        assert!(outside.path().join("other.txt").exists());
+    }
+
+    #[test]
+    fn test_filoignore_excludes_files_and_directories() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        fs::create_dir(src.path().join("pinned")).unwrap();
+        fs::write(src.path().join(".filoignore"), "README*\n/pinned/\n").unwrap();
+        fs::write(src.path().join("README.txt"), "content").unwrap();
+        fs::write(src.path().join("report.txt"), "content").unwrap();
+        fs::write(src.path().join("pinned/todo.txt"), "content").unwrap();
+
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.scan.recursive = true;
+        // `.filoignore` 自体はどのフィルタにも一致させない
+        ruleset.filters.extensions = None;
+        ruleset.filters.filename = Some(FilenameFilter {
+            pattern: "*".to_string(),
+            match_type: MatchType::Glob,
+        });
         let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
 
+        assert_eq!(result.succeeded.len(), 1);
+        assert!(dst.path().join("report.txt").exists());
+        assert!(src.path().join("README.txt").exists());
+        assert!(src.path().join("pinned/todo.txt").exists());
+        assert!(src.path().join(".filoignore").exists());
+
+        // 監視モードで個別に渡されたパスにも適用する
+        let paths = vec![
+            src.path().join("README.txt"),
+            src.path().join("pinned/todo.txt"),
+        ];
+        let result = execute_ruleset_for_paths(&ruleset, &paths, |_, _, _, _| {}, &no_cancel());
+        assert!(result.succeeded.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_filoignore_lines_are_reported_as_skips() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        fs::write(src.path().join(".filoignore"), "README*\n{broken,line\n").unwrap();
+        fs::write(src.path().join("README.txt"), "content").unwrap();
+        fs::write(src.path().join("report.txt"), "content").unwrap();
+
+        let ruleset = create_test_ruleset(src.path(), dst.path());
+        let plan = preview_ruleset(&ruleset);
+        let skip = plan.operations.last().unwrap();
+        assert_eq!(skip.action, PlannedAction::Skip);
+        assert_eq!(skip.source_path, src.path().join(".filoignore"));
+
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+        // 解釈できる行は使われ、解釈できない行だけがスキップとして示される
+        assert_eq!(result.succeeded.len(), 1);
+        assert_eq!(result.status, ExecutionStatus::Completed);
         assert_eq!(result.skipped.len(), 1);
-        // Old content should remain
-        assert_eq!(
-            fs::read_to_string(dst.path().join("exists.txt")).unwrap(),
-            "old content"
+        assert_eq!(
+            result.skipped[0].source_path,
+            src.path().join(".filoignore")
         );
+        assert!(result.skipped[0]
+            .reason
+            .as_ref()
+            .unwrap()
+            .contains("line 2"));
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_copy_skip_when_overwrite_disabled() {
+    fn test_recursive_symlinked_dirs_followed_only_when_enabled() {
         let src = tempfile::tempdir().unwrap();
         let dst = tempfile::tempdir().unwrap();
-
-        fs::write(src.path().join("exists.txt"), "new content").unwrap();
-        fs::write(dst.path().join("exists.txt"), "old content").unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        fs::write(outside.path().join("linked.txt"), "content").unwrap();
+        std::os::unix::fs::symlink(outside.path(), src.path().join("link")).unwrap();
+        // 自身への循環リンクがあっても無限ループしない
+        std::os::unix::fs::symlink(src.path(), src.path().join("loop")).unwrap();
 
         let mut ruleset = create_test_ruleset(src.path(), dst.path());
         ruleset.action = Action::Copy;
-
+        ruleset.scan.recursive = true;
         let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+        assert_eq!(result.succeeded.len(), 0);
 
-        assert_eq!(result.skipped.len(), 1);
-        // コピー元ファイルは残っている
-        assert!(src.path().join("exists.txt").exists());
-        // コピー先の古い内容が保護されている
-        assert_eq!(
-            fs::read_to_string(dst.path().join("exists.txt")).unwrap(),
-            "old content"
-        );
+        ruleset.scan.follow_symlinks = true;
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+        assert_eq!(result.succeeded.len(), 1);
+        assert!(dst.path().join("linked.txt").exists());
     }
 
+    // Undo tests
+
     #[test]
-    fn test_overwrite_when_enabled() {
+    fn test_rename_template_and_undo_restores_original_name() {
+        use chrono::TimeZone;
         let src = tempfile::tempdir().unwrap();
         let dst = tempfile::tempdir().unwrap();
-
-        fs::write(src.path().join("exists.txt"), "new content").unwrap();
-        fs::write(dst.path().join("exists.txt"), "old content").unwrap();
+        let path = src.path().join("IMG_1234.jpg");
+        fs::write(&path, "content").unwrap();
+        let mtime = Local.with_ymd_and_hms(2025, 1, 3, 9, 0, 0).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(mtime.into())
+            .unwrap();
 
         let mut ruleset = create_test_ruleset(src.path(), dst.path());
-        ruleset.overwrite = true;
-
+        ruleset.filters.extensions = None;
+        ruleset.filters.filename = Some(FilenameFilter {
+            pattern: r"^IMG_(?P<num>\d+)".to_string(),
+            match_type: MatchType::Regex,
+        });
+        ruleset.rename = Some("{mtime:%Y-%m-%d}_holiday_{num|pad:6}.{ext|lower}".to_string());
+        assert!(ruleset.validate().is_ok());
         let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
 
         assert_eq!(result.succeeded.len(), 1);
-        assert_eq!(result.skipped.len(), 0);
+        let moved = dst.path().join("2025-01-03_holiday_001234.jpg");
+        assert!(moved.exists());
+        assert_eq!(result.succeeded[0].filename, "IMG_1234.jpg");
         assert_eq!(
-            fs::read_to_string(dst.path().join("exists.txt")).unwrap(),
-            "new content"
+            result.succeeded[0].destination_path.as_deref(),
+            Some(moved.as_path())
         );
+
+        undo_file_move(&result.succeeded[0].source_path, &moved).unwrap();
+        assert!(path.exists());
+        assert!(!moved.exists());
     }
 
     #[test]
-    fn test_filter_only_matching_files() {
+    fn test_rename_template_unresolvable_skips_file() {
         let src = tempfile::tempdir().unwrap();
         let dst = tempfile::tempdir().unwrap();
+        fs::write(src.path().join("notes.txt"), "content").unwrap();
+        fs::write(src.path().join("a.txt"), "content").unwrap();
 
-        fs::write(src.path().join("match.txt"), "content").unwrap();
-        fs::write(src.path().join("skip.pdf"), "content").unwrap();
-
-        let ruleset = create_test_ruleset(src.path(), dst.path());
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.rename = Some("{stem|slice:5}.{ext}".to_string());
         let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
 
-        assert_eq!(result.succeeded.len(), 1);
-        assert_eq!(result.succeeded[0].filename, "match.txt");
-
-        // pdf should remain in source
-        assert!(src.path().join("skip.pdf").exists());
+        // "a" は 5 文字目以降が空になるためスキップする
+        assert_eq!(result.succeeded.len(), 0);
+        assert_eq!(result.skipped.len(), 2);
+
+        ruleset.rename = Some("{stem|upper}.{ext}".to_string());
+        let plan = preview_ruleset(&ruleset);
+        assert!(plan
+            .operations
+            .iter()
+            .any(|op| op.destination_path == Some(dst.path().join("NOTES.txt"))));
     }
 
     #[test]
-    fn test_source_dir_not_exists() {
+    fn test_counter_numbers_files_in_sort_order_and_skips_taken_numbers() {
+        let src = tempfile::tempdir().unwrap();
         let dst = tempfile::tempdir().unwrap();
-        let non_existent = PathBuf::from("/tmp/filo_test_nonexistent_dir");
+        fs::write(src.path().join("b.txt"), "bb").unwrap();
+        fs::write(src.path().join("a.txt"), "a").unwrap();
+        fs::write(src.path().join("c.txt"), "ccc").unwrap();
+        fs::write(dst.path().join("scan_0002.txt"), "existing").unwrap();
 
-        let ruleset = create_test_ruleset(&non_existent, dst.path());
-        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.rename = Some("scan_{counter:4}.{ext}".to_string());
+        assert!(ruleset.validate().is_ok());
+
+        // プレビューも実行と同じ番号を予測する
+        let plan = preview_ruleset(&ruleset);
+        let planned: Vec<_> = plan
+            .operations
+            .iter()
+            .map(|op| op.destination_path.clone().unwrap())
+            .collect();
+        assert_eq!(
+            planned,
+            vec![
+                dst.path().join("scan_0001.txt"),
+                dst.path().join("scan_0003.txt"),
+                dst.path().join("scan_0004.txt"),
+            ]
+        );
 
-        assert_eq!(result.status, ExecutionStatus::Failed);
-        assert_eq!(result.errors.len(), 1);
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+        assert_eq!(result.succeeded.len(), 3);
+        assert_eq!(
+            fs::read_to_string(dst.path().join("scan_0001.txt")).unwrap(),
+            "a"
+        );
+        assert_eq!(
+            fs::read_to_string(dst.path().join("scan_0002.txt")).unwrap(),
+            "existing"
+        );
+        assert_eq!(
+            fs::read_to_string(dst.path().join("scan_0003.txt")).unwrap(),
+            "bb"
+        );
+        assert_eq!(
+            fs::read_to_string(dst.path().join("scan_0004.txt")).unwrap(),
+            "ccc"
+        );
     }
 
     #[test]
-    fn test_creates_destination_dir() {
+    fn test_counter_sort_descending_and_resume() {
         let src = tempfile::tempdir().unwrap();
-        let dst_base = tempfile::tempdir().unwrap();
-        let dst = dst_base.path().join("new_subdir");
-
-        fs::write(src.path().join("file.txt"), "content").unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        fs::write(src.path().join("small.txt"), "a").unwrap();
+        fs::write(src.path().join("large.txt"), "aaa").unwrap();
+        let sub = dst.path().join("2025");
+        fs::create_dir(&sub).unwrap();
+        fs::write(sub.join("photo-7.txt"), "existing").unwrap();
 
-        let ruleset = create_test_ruleset(src.path(), &dst);
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.destination_dir = format!("{}/{{ext}}", dst.path().display());
+        ruleset.rename = Some("photo-{counter}.{ext}".to_string());
+        ruleset.counter = CounterOptions {
+            sort: CounterSort::Size,
+            descending: true,
+            resume: true,
+            ..CounterOptions::default()
+        };
         let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+        assert_eq!(result.succeeded.len(), 2);
+        let txt = dst.path().join("txt");
+        assert_eq!(fs::read_to_string(txt.join("photo-1.txt")).unwrap(), "aaa");
+        assert_eq!(fs::read_to_string(txt.join("photo-2.txt")).unwrap(), "a");
 
-        assert_eq!(result.status, ExecutionStatus::Completed);
-        assert!(dst.join("file.txt").exists());
+        // 既存の番号の続きから振る（フォルダごとに独立して採番する）
+        fs::write(src.path().join("next.txt"), "n").unwrap();
+        ruleset.destination_dir = sub.to_str().unwrap().to_string();
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+        assert_eq!(result.succeeded.len(), 1);
+        assert_eq!(fs::read_to_string(sub.join("photo-8.txt")).unwrap(), "n");
     }
 
     #[test]
-    fn test_skip_directories_in_source() {
+    fn test_glob_wildcards_usable_in_templates() {
         let src = tempfile::tempdir().unwrap();
         let dst = tempfile::tempdir().unwrap();
+        fs::write(src.path().join("acme_report_2025-Q1.txt"), "content").unwrap();
+        fs::write(src.path().join("acme_invoice.txt"), "content").unwrap();
 
-        fs::create_dir(src.path().join("subdir")).unwrap();
-        fs::write(src.path().join("file.txt"), "content").unwrap();
-
-        let ruleset = create_test_ruleset(src.path(), dst.path());
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.destination_dir = format!("{}/{{client}}", dst.path().display());
+        ruleset.rename = Some("{2}.{ext}".to_string());
+        ruleset.filters.filename = Some(FilenameFilter {
+            pattern: "{client:*}_report_*.txt".to_string(),
+            match_type: MatchType::Glob,
+        });
+        assert!(ruleset.validate().is_ok());
         let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
 
         assert_eq!(result.succeeded.len(), 1);
-        // Subdirectory should remain
-        assert!(src.path().join("subdir").exists());
+        assert!(dst.path().join("acme").join("2025-Q1.txt").exists());
+        assert!(src.path().join("acme_invoice.txt").exists());
     }
 
     #[test]
-    fn test_glob_filename_filter() {
+    fn test_traversal_capture_is_skipped_with_reason() {
         let src = tempfile::tempdir().unwrap();
         let dst = tempfile::tempdir().unwrap();
-
-        fs::write(src.path().join("screenshot_001.txt"), "content").unwrap();
-        fs::write(src.path().join("photo.txt"), "content").unwrap();
+        fs::write(src.path().join("..-report.txt"), "content").unwrap();
 
         let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.destination_dir = format!("{}/{{label}}", dst.path().display());
         ruleset.filters.filename = Some(FilenameFilter {
-            pattern: "screenshot_*".to_string(),
-            match_type: MatchType::Glob,
+            pattern: "^(?P<label>[^-]+)-".to_string(),
+            match_type: MatchType::Regex,
         });
-
         let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
 
-        assert_eq!(result.succeeded.len(), 1);
-        assert_eq!(result.succeeded[0].filename, "screenshot_001.txt");
+        assert_eq!(result.succeeded.len(), 0);
+        assert_eq!(result.skipped.len(), 1);
+        assert!(result.skipped[0]
+            .reason
+            .as_deref()
+            .unwrap()
+            .contains("invalid path component"));
+        assert!(src.path().join("..-report.txt").exists());
     }
 
-    // Undo tests
-
     #[test]
     fn test_undo_file_move_basic() {
         let src = tempfile::tempdir().unwrap();
@@ -898,14 +3030,14 @@ mod tests {
 
         // 成功ファイル
         fs::write(src.path().join("ok.txt"), "content").unwrap();
-        // 同名ファイルが宛先に存在しかつ overwrite=true で上書き → 成功
+        // 同名ファイルが宛先に存在しかつ on_conflict=overwrite で上書き → 成功
         // 失敗を作るには dest の中にサブディレクトリと同名を置く（NotFoundにならないよう）
         // 宛先に同名のディレクトリを作ると、ファイルを上書きしようとして失敗する
         fs::create_dir(dst.path().join("fail.txt")).unwrap();
         fs::write(src.path().join("fail.txt"), "content").unwrap();
 
         let mut ruleset = create_test_ruleset(src.path(), dst.path());
-        ruleset.overwrite = true;
+        ruleset.on_conflict = ConflictPolicy::Overwrite;
 
         let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
 
@@ -917,24 +3049,48 @@ mod tests {
     // --- テンプレート機能のテスト ---
 
     #[test]
-    fn test_has_template_vars_true() {
-        assert!(has_template_vars("D:/sorted/{label}/{author}"));
-        assert!(has_template_vars("{category}/file"));
-        assert!(has_template_vars("base/{x}"));
+    fn test_sanitize_path_component_valid() {
+        assert_eq!(sanitize_path_component("valid"), "valid");
+        assert_eq!(sanitize_path_component("john_doe"), "john_doe");
+        assert_eq!(sanitize_path_component("book123"), "book123");
     }
 
     #[test]
-    fn test_has_template_vars_false() {
-        assert!(!has_template_vars("D:/sorted/static"));
-        assert!(!has_template_vars("C:/Users/user/Downloads"));
-        assert!(!has_template_vars(""));
+    fn test_sanitize_path_component_trims_dots_and_spaces() {
+        assert_eq!(sanitize_path_component(".."), "");
+        assert_eq!(sanitize_path_component("."), "");
+        assert_eq!(sanitize_path_component(" report. "), "report");
+        assert_eq!(sanitize_path_component("../etc"), "_etc");
+        assert_eq!(sanitize_path_component("v1.0"), "v1.0");
     }
 
     #[test]
-    fn test_sanitize_path_component_valid() {
-        assert_eq!(sanitize_path_component("valid"), "valid");
-        assert_eq!(sanitize_path_component("john_doe"), "john_doe");
-        assert_eq!(sanitize_path_component("book123"), "book123");
+    fn test_resolve_destination_template_rejects_traversal() {
+        let mut captures = HashMap::new();
+        captures.insert("label".to_string(), "..".to_string());
+        captures.insert("date".to_string(), "20250107".to_string());
+        let ctx = context(Path::new("D:/inbox/a.txt"));
+
+        let result = resolve_template("D:/sorted/{label}/x", &captures, &ctx);
+        assert!(result.unwrap_err().contains("invalid path component"));
+        let result = resolve_template("D:/sorted/{date|date:%Y%m%d->%Y/..}", &captures, &ctx);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_ensure_within_template_root() {
+        assert_eq!(
+            template_static_prefix("D:/sorted/{label}/{id}"),
+            "D:/sorted/"
+        );
+        assert_eq!(template_static_prefix("D:/sorted/x_{label}"), "D:/sorted/");
+        assert_eq!(template_static_prefix("{label}/x"), "");
+
+        let template = "/data/sorted/{label}";
+        assert!(ensure_within_template_root(template, Path::new("/data/sorted/a/b")).is_ok());
+        assert!(ensure_within_template_root(template, Path::new("/data/sorted/../other")).is_err());
+        assert!(ensure_within_template_root(template, Path::new("/data/sorted-x/a")).is_err());
+        assert!(ensure_within_template_root("{label}", Path::new("a")).is_err());
     }
 
     #[test]
@@ -949,6 +3105,182 @@ mod tests {
         assert_eq!(sanitize_path_component("with|pipe"), "with_pipe");
     }
 
+    fn context(path: &Path) -> TemplateContext<'_> {
+        TemplateContext {
+            path,
+            ruleset_name: "test",
+            source_dir: Path::new("D:/inbox"),
+            modified: None,
+            created: None,
+            now: Local::now(),
+            counter: None,
+        }
+    }
+
+    fn resolve_template(
+        template: &str,
+        captures: &HashMap<String, String>,
+        ctx: &TemplateContext,
+    ) -> Result<String, String> {
+        resolve_destination_template(&Template::parse(template)?, captures, ctx)
+    }
+
+    #[test]
+    fn test_resolve_destination_template_date_vars() {
+        use chrono::TimeZone;
+        let ctx = TemplateContext {
+            modified: Some(Local.with_ymd_and_hms(2024, 5, 3, 10, 30, 0).unwrap()),
+            now: Local.with_ymd_and_hms(2025, 1, 9, 8, 0, 0).unwrap(),
+            ..context(Path::new("D:/inbox/photo.jpg"))
+        };
+        let captures = HashMap::new();
+
+        let result = resolve_template("D:/photos/{mtime:%Y}/{mtime:%m}", &captures, &ctx);
+        assert_eq!(result.unwrap(), "D:/photos/2024/05");
+        let result = resolve_template("D:/inbox/{now}", &captures, &ctx);
+        assert_eq!(result.unwrap(), "D:/inbox/2025-01-09");
+        // 書式中のパス区切りや `:` はほかの値と同様にサニタイズする
+        let result = resolve_template("D:/{mtime:%H:%M}", &captures, &ctx);
+        assert_eq!(result.unwrap(), "D:/10_30");
+        // 作成日時を取得できないファイルはスキップ理由になる
+        let result = resolve_template("D:/{ctime:%Y}", &captures, &ctx);
+        assert!(result.unwrap_err().contains("ctime"));
+    }
+
+    #[test]
+    fn test_resolve_destination_template_file_vars() {
+        let captures = HashMap::new();
+        let path = Path::new("D:/inbox/scans/report.final.PDF");
+        let ctx = TemplateContext {
+            ruleset_name: "Documents",
+            ..context(path)
+        };
+
+        let result = resolve_template(
+            "D:/sorted/{ruleset}/{source_dir_name}/{parent}/{ext}/{stem}",
+            &captures,
+            &ctx,
+        );
+        assert_eq!(
+            result.unwrap(),
+            "D:/sorted/Documents/inbox/scans/PDF/report.final"
+        );
+
+        // 拡張子のないファイルは空になるためスキップ理由になる
+        let ctx = context(Path::new("D:/inbox/README"));
+        let result = resolve_template("D:/sorted/{ext}", &captures, &ctx);
+        assert!(result.unwrap_err().contains("ext"));
+    }
+
+    #[test]
+    fn test_resolve_destination_template_filters() {
+        let mut captures = HashMap::new();
+        captures.insert("label".to_string(), "Invoices".to_string());
+        captures.insert("id".to_string(), "42".to_string());
+        captures.insert("client".to_string(), "".to_string());
+        let ctx = context(Path::new("D:/inbox/Report.PDF"));
+
+        let resolve = |template| resolve_template(template, &captures, &ctx);
+        assert_eq!(resolve("D:/{label|lower}").unwrap(), "D:/invoices");
+        assert_eq!(resolve("D:/{ext|lower}").unwrap(), "D:/pdf");
+        assert_eq!(resolve("D:/{label|upper}").unwrap(), "D:/INVOICES");
+        assert_eq!(resolve("D:/{id|pad:6}").unwrap(), "D:/000042");
+        assert_eq!(resolve("D:/{label|slice:0:1}").unwrap(), "D:/I");
+        assert_eq!(resolve("D:/{label|slice:3}").unwrap(), "D:/oices");
+        assert_eq!(
+            resolve("D:/{label|slice:0:1|lower}/{label}").unwrap(),
+            "D:/i/Invoices"
+        );
+        // 空のキャプチャや存在しない変数は default で置き換える
+        assert_eq!(
+            resolve("D:/{client|default:unknown}").unwrap(),
+            "D:/unknown"
+        );
+        assert_eq!(
+            resolve("D:/{author|default:unknown|upper}").unwrap(),
+            "D:/UNKNOWN"
+        );
+        assert!(resolve("D:/{client|lower}").is_err());
+        assert!(resolve("D:/{label|capitalize}").is_err());
+    }
+
+    #[test]
+    fn test_resolve_destination_template_typed_conversions() {
+        let mut captures = HashMap::new();
+        captures.insert("date".to_string(), "20250107".to_string());
+        captures.insert("month".to_string(), "202503".to_string());
+        captures.insert("id".to_string(), "001234".to_string());
+        captures.insert("label".to_string(), "draft".to_string());
+        let ctx = context(Path::new("D:/inbox/scan.pdf"));
+
+        let resolve = |template| resolve_template(template, &captures, &ctx);
+        // 出力書式の `/` はフォルダの区切りになる
+        assert_eq!(
+            resolve("D:/{date|date:%Y%m%d->%Y/%m}").unwrap(),
+            "D:/2025/01"
+        );
+        assert_eq!(
+            resolve("D:/{month|date:%Y%m->%Y-%m-%d}").unwrap(),
+            "D:/2025-03-01"
+        );
+        assert_eq!(resolve("D:/{id|num}").unwrap(), "D:/1234");
+        assert_eq!(resolve("D:/{id|num|range:1000}").unwrap(), "D:/1000-1999");
+        assert_eq!(resolve("D:/{id|range:100000}").unwrap(), "D:/0-99999");
+        // ほかの変換の `/` は従来どおりサニタイズする
+        assert_eq!(
+            resolve("D:/{label|default:a/b}/{date|date:%Y%m%d->%d:%m}").unwrap(),
+            "D:/draft/07_01"
+        );
+
+        // 解釈できない値はスキップ理由になる
+        assert!(resolve("D:/{label|num}").unwrap_err().contains("draft"));
+        assert!(resolve("D:/{label|range:10}").is_err());
+        assert!(resolve("D:/{label|date:%Y%m%d->%Y}").is_err());
+        assert_eq!(resolve("D:/{label|num|default:other}").unwrap(), "D:/other");
+    }
+
+    #[test]
+    fn test_resolve_destination_template_capture_overrides_builtin() {
+        let mut captures = HashMap::new();
+        captures.insert("now".to_string(), "captured".to_string());
+
+        let result = resolve_template(
+            "D:/{now}",
+            &captures,
+            &context(Path::new("D:/inbox/file.txt")),
+        );
+        assert_eq!(result.unwrap(), "D:/captured");
+    }
+
+    #[test]
+    fn test_execute_ruleset_with_date_template_and_glob_filter() {
+        use chrono::TimeZone;
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        let path = src.path().join("IMG_0001.jpg");
+        fs::write(&path, "content").unwrap();
+        let mtime = Local.with_ymd_and_hms(2023, 11, 20, 12, 0, 0).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(mtime.into())
+            .unwrap();
+
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.destination_dir = format!("{}/{{mtime:%Y}}/{{mtime:%m}}", dst.path().display());
+        ruleset.filters.extensions = None;
+        ruleset.filters.filename = Some(FilenameFilter {
+            pattern: "IMG_*".to_string(),
+            match_type: MatchType::Glob,
+        });
+        assert!(ruleset.validate().is_ok());
+        let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
+
+        assert_eq!(result.succeeded.len(), 1);
+        assert!(dst.path().join("2023/11/IMG_0001.jpg").exists());
+    }
+
     #[test]
     fn test_resolve_destination_template_success() {
         use std::collections::HashMap;
@@ -956,17 +3288,36 @@ mod tests {
         captures.insert("label".to_string(), "book".to_string());
         captures.insert("author".to_string(), "john_doe".to_string());
 
-        let result = resolve_destination_template("D:/sorted/{label}/{author}", &captures);
+        let result = resolve_template(
+            "D:/sorted/{label}/{author}",
+            &captures,
+            &context(Path::new("D:/inbox/file.txt")),
+        );
         assert_eq!(result.unwrap(), "D:/sorted/book/john_doe");
     }
 
+    #[test]
+    fn test_resolve_destination_template_escaped_braces() {
+        let mut captures = HashMap::new();
+        captures.insert("label".to_string(), "99999".to_string());
+        let ctx = context(Path::new("D:/inbox/a.zip"));
+
+        let result = resolve_template("D:/sorted/{{label}}/{label}", &captures, &ctx);
+        assert_eq!(result, Ok("D:/sorted/{label}/99999".to_string()));
+        assert!(resolve_template("D:/sorted/{label", &captures, &ctx).is_err());
+    }
+
     #[test]
     fn test_resolve_destination_template_missing_var() {
         use std::collections::HashMap;
         let mut captures = HashMap::new();
         captures.insert("label".to_string(), "book".to_string());
 
-        let result = resolve_destination_template("D:/sorted/{label}/{author}", &captures);
+        let result = resolve_template(
+            "D:/sorted/{label}/{author}",
+            &captures,
+            &context(Path::new("D:/inbox/file.txt")),
+        );
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("author"));
     }
@@ -977,7 +3328,11 @@ mod tests {
         let mut captures = HashMap::new();
         captures.insert("label".to_string(), "".to_string());
 
-        let result = resolve_destination_template("D:/sorted/{label}", &captures);
+        let result = resolve_template(
+            "D:/sorted/{label}",
+            &captures,
+            &context(Path::new("D:/inbox/file.txt")),
+        );
         assert!(result.is_err());
     }
 
@@ -987,7 +3342,11 @@ mod tests {
         let mut captures = HashMap::new();
         captures.insert("label".to_string(), "sci/fi".to_string());
 
-        let result = resolve_destination_template("D:/sorted/{label}", &captures);
+        let result = resolve_template(
+            "D:/sorted/{label}",
+            &captures,
+            &context(Path::new("D:/inbox/file.txt")),
+        );
         assert_eq!(result.unwrap(), "D:/sorted/sci_fi");
     }
 
@@ -1008,7 +3367,10 @@ mod tests {
             source_dir: src.path().to_str().unwrap().to_string(),
             destination_dir: dest_template,
             action: Action::Move,
-            overwrite: false,
+            on_conflict: ConflictPolicy::Skip,
+            rename_pattern: None,
+            rename: None,
+            counter: CounterOptions::default(),
             filters: Filters {
                 extensions: None,
                 filename: Some(FilenameFilter {
@@ -1017,7 +3379,18 @@ mod tests {
                 }),
                 created_at: None,
                 modified_at: None,
+                accessed_at: None,
+                size: None,
+                exclude: None,
+                all: None,
+                any: None,
+                not: None,
+                captures: None,
             },
+            scan: ScanOptions::default(),
+            watch: false,
+            schedule: None,
+            parallelism: None,
         };
 
         let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
@@ -1057,7 +3430,10 @@ mod tests {
             source_dir: src.path().to_str().unwrap().to_string(),
             destination_dir: dest_template,
             action: Action::Move,
-            overwrite: false,
+            on_conflict: ConflictPolicy::Skip,
+            rename_pattern: None,
+            rename: None,
+            counter: CounterOptions::default(),
             filters: Filters {
                 extensions: None,
                 filename: Some(FilenameFilter {
@@ -1066,7 +3442,18 @@ mod tests {
                 }),
                 created_at: None,
                 modified_at: None,
+                accessed_at: None,
+                size: None,
+                exclude: None,
+                all: None,
+                any: None,
+                not: None,
+                captures: None,
             },
+            scan: ScanOptions::default(),
+            watch: false,
+            schedule: None,
+            parallelism: None,
         };
 
         let result = execute_ruleset(&ruleset, |_, _, _, _| {}, &no_cancel());
@@ -1077,6 +3464,148 @@ mod tests {
         assert!(src.path().join("(book) [john_doe] ihavepen.zip").exists());
     }
 
+    // --- プレビューのテスト ---
+
+    #[test]
+    fn test_preview_does_not_touch_files() {
+        let src = tempfile::tempdir().unwrap();
+        let dst_base = tempfile::tempdir().unwrap();
+        let dst = dst_base.path().join("not_created");
+
+        fs::write(src.path().join("hello.txt"), "content").unwrap();
+        fs::write(src.path().join("skip.pdf"), "content").unwrap();
+
+        let ruleset = create_test_ruleset(src.path(), &dst);
+        let plan = preview_ruleset(&ruleset);
+
+        assert_eq!(plan.operations.len(), 1);
+        let op = &plan.operations[0];
+        assert_eq!(op.filename, "hello.txt");
+        assert_eq!(op.action, PlannedAction::Move);
+        assert_eq!(op.destination_path, Some(dst.join("hello.txt")));
+        assert!(op.reason.is_none());
+
+        // ファイルも宛先ディレクトリも変更されていない
+        assert!(src.path().join("hello.txt").exists());
+        assert!(!dst.exists());
+    }
+
+    #[test]
+    fn test_preview_predicts_conflict_skip_and_overwrite() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+
+        fs::write(src.path().join("exists.txt"), "new content").unwrap();
+        fs::write(dst.path().join("exists.txt"), "old content").unwrap();
+
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        let plan = preview_ruleset(&ruleset);
+        assert_eq!(plan.operations[0].action, PlannedAction::Skip);
+        assert_eq!(
+            plan.operations[0].reason.as_deref(),
+            Some(CONFLICT_SKIP_REASON)
+        );
+
+        ruleset.on_conflict = ConflictPolicy::Overwrite;
+        ruleset.action = Action::Copy;
+        let plan = preview_ruleset(&ruleset);
+        assert_eq!(plan.operations[0].action, PlannedAction::Copy);
+        assert_eq!(
+            plan.operations[0].conflict,
+            Some(ConflictResolution::Overwritten)
+        );
+        assert_eq!(
+            fs::read_to_string(dst.path().join("exists.txt")).unwrap(),
+            "old content"
+        );
+    }
+
+    #[test]
+    fn test_preview_predicts_auto_rename_within_run() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+
+        fs::create_dir(src.path().join("sub")).unwrap();
+        fs::write(src.path().join("a.txt"), "top").unwrap();
+        fs::write(src.path().join("sub/a.txt"), "nested").unwrap();
+
+        let mut ruleset = create_test_ruleset(src.path(), dst.path());
+        ruleset.scan.recursive = true;
+        ruleset.on_conflict = ConflictPolicy::AutoRename;
+        let plan = preview_ruleset(&ruleset);
+
+        let mut dests: Vec<_> = plan
+            .operations
+            .iter()
+            .map(|op| op.destination_path.clone().unwrap())
+            .collect();
+        dests.sort();
+        assert_eq!(
+            dests,
+            vec![dst.path().join("a (1).txt"), dst.path().join("a.txt")]
+        );
+    }
+
+    #[test]
+    fn test_preview_resolves_template_and_reports_skip_reason() {
+        let src = tempfile::tempdir().unwrap();
+        let dst_base = tempfile::tempdir().unwrap();
+
+        fs::write(src.path().join("(book) [john_doe] ihavepen.zip"), "content").unwrap();
+        fs::write(src.path().join("unmatched.zip"), "content").unwrap();
+
+        let mut ruleset = create_test_ruleset(src.path(), dst_base.path());
+        ruleset.destination_dir = format!("{}/{{label}}", dst_base.path().to_str().unwrap());
+        ruleset.filters = Filters {
+            extensions: Some(vec![".zip".to_string()]),
+            filename: Some(FilenameFilter {
+                pattern: r"^(\((?P<label>[^)]+)\) )?.+".to_string(),
+                match_type: MatchType::Regex,
+            }),
+            created_at: None,
+            modified_at: None,
+            accessed_at: None,
+            size: None,
+            exclude: None,
+            all: None,
+            any: None,
+            not: None,
+            captures: None,
+        };
+
+        let mut plan = preview_ruleset(&ruleset);
+        plan.operations.sort_by(|a, b| a.filename.cmp(&b.filename));
+
+        assert_eq!(plan.operations.len(), 2);
+        assert_eq!(plan.operations[0].action, PlannedAction::Move);
+        assert_eq!(
+            plan.operations[0].destination_path,
+            Some(PathBuf::from(format!(
+                "{}/book/(book) [john_doe] ihavepen.zip",
+                dst_base.path().to_str().unwrap()
+            )))
+        );
+        assert_eq!(plan.operations[1].action, PlannedAction::Skip);
+        assert!(plan.operations[1]
+            .reason
+            .as_deref()
+            .unwrap()
+            .contains("label"));
+        assert!(!dst_base.path().join("book").exists());
+    }
+
+    #[test]
+    fn test_preview_source_dir_not_exists() {
+        let dst = tempfile::tempdir().unwrap();
+        let non_existent = PathBuf::from("/tmp/filo_test_nonexistent_dir");
+
+        let ruleset = create_test_ruleset(&non_existent, dst.path());
+        let plan = preview_ruleset(&ruleset);
+
+        assert_eq!(plan.operations.len(), 1);
+        assert_eq!(plan.operations[0].action, PlannedAction::Error);
+    }
+
     // --- キャンセルのテスト ---
 
     #[test]
@@ -1107,9 +3636,9 @@ mod tests {
             &cancel,
         );
 
-        // succeeded + skipped + errors = total (3件)
-        let total = result.succeeded.len() + result.skipped.len() + result.errors.len();
-        assert_eq!(total, 3);
+        // 残りのファイルは列挙せず、キャンセルを示すスキップ 1 件にまとめる
+        assert_eq!(result.succeeded.len(), 1);
+        assert_eq!(result.skipped.len(), 1);
         // 少なくとも1件はスキップ（キャンセル理由）
         assert!(result.skipped.iter().any(|f| f
             .reason
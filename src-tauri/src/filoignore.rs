@@ -0,0 +1,212 @@
+//! ソースディレクトリに置く `.filoignore` の解釈。
+//! 書式は `.gitignore` と同じで、`.filoignore` があるディレクトリからの相対パスとして評価する。
+//! 除外されたファイルはどのルールセットでも処理しない。
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub const IGNORE_FILE_NAME: &str = ".filoignore";
+
+/// 解釈できずに無視した `.filoignore` の行
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidIgnoreLine {
+    /// 行を含む `.filoignore` のパス
+    pub path: PathBuf,
+    pub message: String,
+}
+
+/// ソースディレクトリ以下の `.filoignore` をまとめて評価する。
+/// 各ディレクトリの `.filoignore` は初めて必要になったときに一度だけ読み込む。
+pub struct FiloIgnore {
+    root: PathBuf,
+    matchers: HashMap<PathBuf, Option<Gitignore>>,
+    invalid_lines: Vec<InvalidIgnoreLine>,
+}
+
+impl FiloIgnore {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            matchers: HashMap::new(),
+            invalid_lines: Vec::new(),
+        }
+    }
+
+    /// これまでに読み込んだ `.filoignore` のうち、解釈できずに無視した行を取り出す
+    pub fn take_invalid_lines(&mut self) -> Vec<InvalidIgnoreLine> {
+        std::mem::take(&mut self.invalid_lines)
+    }
+
+    /// `path` が除外対象か判定する。`path` の親ディレクトリから `root` までの `.filoignore` を
+    /// 深い階層から順に評価し、最初に一致したもの（`!` による再包含を含む）を採用する。
+    /// 親ディレクトリ自体が除外されているかは判定しないため、走査時は除外されたディレクトリに降りないこと。
+    pub fn is_ignored(&mut self, path: &Path, is_dir: bool) -> bool {
+        // `.filoignore` 自体は常に対象外
+        if !is_dir
+            && path
+                .file_name()
+                .is_some_and(|name| name == IGNORE_FILE_NAME)
+        {
+            return true;
+        }
+        let Some(parent) = path.parent() else {
+            return false;
+        };
+        if !parent.starts_with(&self.root) {
+            return false;
+        }
+        for dir in parent.ancestors() {
+            if let Some(matcher) = self.matcher(dir) {
+                match matcher.matched(path, is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => {}
+                }
+            }
+            if dir == self.root {
+                break;
+            }
+        }
+        false
+    }
+
+    /// `path` 自身と、`root` から `path` までの途中のディレクトリのいずれかが除外対象か判定する。
+    /// 走査を経ずに個別のパスを処理する場合（監視モードなど）に使う。
+    pub fn is_ignored_with_parents(&mut self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        let mut dir = self.root.clone();
+        if let Some(parent) = relative.parent() {
+            for component in parent.components() {
+                dir.push(component);
+                if self.is_ignored(&dir, true) {
+                    return true;
+                }
+            }
+        }
+        self.is_ignored(path, false)
+    }
+
+    fn matcher(&mut self, dir: &Path) -> Option<&Gitignore> {
+        let invalid_lines = &mut self.invalid_lines;
+        self.matchers
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| load_ignore_file(dir, invalid_lines))
+            .as_ref()
+    }
+}
+
+/// `dir` の `.filoignore` を読み込む。ファイルがない場合は `None`。
+/// 解釈できない行は無視して `invalid_lines` に記録し、残りの行だけを使う。
+fn load_ignore_file(dir: &Path, invalid_lines: &mut Vec<InvalidIgnoreLine>) -> Option<Gitignore> {
+    let path = dir.join(IGNORE_FILE_NAME);
+    if !path.is_file() {
+        return None;
+    }
+    let mut messages = Vec::new();
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Some(e) = builder.add(&path) {
+        flatten_error(e, &mut messages);
+    }
+    let matcher = match builder.build() {
+        Ok(matcher) => Some(matcher),
+        Err(e) => {
+            flatten_error(e, &mut messages);
+            None
+        }
+    };
+    invalid_lines.extend(messages.into_iter().map(|message| InvalidIgnoreLine {
+        path: path.clone(),
+        message,
+    }));
+    matcher
+}
+
+/// 複数の行のエラーをまとめたエラーを 1 行ずつに分ける。パスは別に持つため取り除く。
+fn flatten_error(error: ignore::Error, messages: &mut Vec<String>) {
+    match error {
+        ignore::Error::Partial(errors) => {
+            for e in errors {
+                flatten_error(e, messages);
+            }
+        }
+        ignore::Error::WithPath { err, .. } => flatten_error(*err, messages),
+        e => messages.push(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_ignore_file_patterns() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join(IGNORE_FILE_NAME),
+            "# inbox に残すファイル\nREADME*\n*.part\n!keep.part\npinned/\n",
+        )
+        .unwrap();
+        let mut ignore = FiloIgnore::new(dir.path());
+
+        assert!(ignore.is_ignored(&dir.path().join("README.md"), false));
+        assert!(ignore.is_ignored(&dir.path().join("movie.mp4.part"), false));
+        assert!(!ignore.is_ignored(&dir.path().join("keep.part"), false));
+        assert!(!ignore.is_ignored(&dir.path().join("photo.jpg"), false));
+        assert!(ignore.is_ignored(&dir.path().join("pinned"), true));
+        assert!(ignore.is_ignored(&dir.path().join(IGNORE_FILE_NAME), false));
+    }
+
+    #[test]
+    fn test_nested_ignore_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let sub = dir.path().join("sub");
+        fs::create_dir(&sub).unwrap();
+        fs::write(dir.path().join(IGNORE_FILE_NAME), "*.tmp\n").unwrap();
+        fs::write(sub.join(IGNORE_FILE_NAME), "!important.tmp\n/local.txt\n").unwrap();
+        let mut ignore = FiloIgnore::new(dir.path());
+
+        assert!(ignore.is_ignored(&sub.join("scratch.tmp"), false));
+        // 深い階層の指定を優先する
+        assert!(!ignore.is_ignored(&sub.join("important.tmp"), false));
+        assert!(ignore.is_ignored(&sub.join("local.txt"), false));
+        assert!(!ignore.is_ignored(&dir.path().join("local.txt"), false));
+    }
+
+    #[test]
+    fn test_ignored_parent_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(IGNORE_FILE_NAME), "pinned/\n").unwrap();
+        let mut ignore = FiloIgnore::new(dir.path());
+
+        let inside = dir.path().join("pinned").join("a").join("doc.txt");
+        assert!(ignore.is_ignored_with_parents(&inside));
+        assert!(!ignore.is_ignored_with_parents(&dir.path().join("a").join("doc.txt")));
+    }
+
+    #[test]
+    fn test_invalid_lines_are_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(IGNORE_FILE_NAME), "*.tmp\n{broken,line\n").unwrap();
+        let mut ignore = FiloIgnore::new(dir.path());
+
+        // 解釈できる行は使われる
+        assert!(ignore.is_ignored(&dir.path().join("a.tmp"), false));
+        let invalid = ignore.take_invalid_lines();
+        assert_eq!(invalid.len(), 1);
+        assert_eq!(invalid[0].path, dir.path().join(IGNORE_FILE_NAME));
+        assert!(invalid[0].message.contains("line 2"));
+        assert!(ignore.take_invalid_lines().is_empty());
+    }
+
+    #[test]
+    fn test_no_ignore_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut ignore = FiloIgnore::new(dir.path());
+        assert!(!ignore.is_ignored(&dir.path().join("README.md"), false));
+    }
+}
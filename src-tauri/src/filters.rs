@@ -1,6 +1,10 @@
-use crate::ruleset::{Filters, MatchType};
+use crate::ruleset::{
+    CaptureCondition, DateTimeRange, FilenameFilter, Filters, MatchType, SizeRange,
+};
+use crate::units::{parse_duration, parse_size};
 use chrono::{DateTime, Local};
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
 /// ファイル名に対してコンパイル済み正規表現を適用し、名前付きキャプチャグループを HashMap で返す。
@@ -19,116 +23,554 @@ pub fn extract_named_captures(filename: &str, re: &regex::Regex) -> HashMap<Stri
         .collect()
 }
 
-pub fn matches_filters(path: &Path, metadata: &std::fs::Metadata, filters: &Filters) -> bool {
-    if let Some(extensions) = &filters.extensions {
-        if !match_extensions(path, extensions) {
-            return false;
+/// ファイル名フィルタから、テンプレート変数に使うキャプチャを取り出す。
+/// 正規表現は名前付きキャプチャグループを、glob はワイルドカードを先頭から数えた `1`・`2`… と、
+/// `{label:*}` の形で付けた名前を変数名とする。
+pub struct FilenameCaptures {
+    re: regex::Regex,
+    positional: bool,
+}
+
+impl FilenameCaptures {
+    /// パターンが不正な場合は `None`
+    pub fn new(filter: &FilenameFilter) -> Option<Self> {
+        let (re, positional) = match filter.match_type {
+            MatchType::Regex => (regex::Regex::new(&filter.pattern).ok()?, false),
+            MatchType::Glob => (glob_capture_regex(&filter.pattern).ok()?, true),
+        };
+        Some(Self { re, positional })
+    }
+
+    /// マッチしない場合は空の HashMap を返す。
+    pub fn extract(&self, filename: &str) -> HashMap<String, String> {
+        if !self.positional {
+            return extract_named_captures(filename, &self.re);
+        }
+        let Some(caps) = self.re.captures(filename) else {
+            return HashMap::new();
+        };
+        let mut values = HashMap::new();
+        for (i, name) in self.re.capture_names().enumerate().skip(1) {
+            let Some(m) = caps.get(i) else {
+                continue;
+            };
+            values.insert(i.to_string(), m.as_str().to_string());
+            if let Some(name) = name {
+                values.insert(name.to_string(), m.as_str().to_string());
+            }
         }
+        values
     }
+}
 
-    if let Some(filename_filter) = &filters.filename {
-        if !match_filename(path, &filename_filter.pattern, &filename_filter.match_type) {
-            return false;
+/// ファイル名フィルタから取り出せるキャプチャの名前を返す。
+/// 正規表現は名前付きキャプチャグループ、glob は `glob_capture_names` と同じ。
+pub fn capture_names(filter: &FilenameFilter) -> Result<Vec<String>, String> {
+    match filter.match_type {
+        MatchType::Regex => {
+            let re = regex::Regex::new(&filter.pattern).map_err(|e| e.to_string())?;
+            Ok(re.capture_names().flatten().map(str::to_string).collect())
         }
+        MatchType::Glob => glob_capture_names(&filter.pattern),
     }
+}
 
-    if let Some(created_at) = &filters.created_at {
-        match metadata.created() {
-            Ok(created) => {
-                let created: DateTime<Local> = created.into();
-                if !match_datetime_range(&created, &created_at.start, &created_at.end) {
-                    return false;
-                }
+/// glob パターンを構成する要素
+enum GlobToken<'a> {
+    Literal(char),
+    /// `*`・`?`・`[...]`。`{label:*}` の形で書いた場合は名前を持つ
+    Wildcard {
+        glob: &'a str,
+        name: Option<&'a str>,
+    },
+}
+
+/// `s` の先頭にあるワイルドカードの長さを返す。ワイルドカードでない場合は `None`。
+/// 文字クラスの先頭の `!`（否定）と、その直後の `]` は glob クレートと同様にクラスの一部として扱う。
+fn wildcard_len(s: &str) -> Result<Option<usize>, String> {
+    match s.as_bytes().first() {
+        Some(b'*') => Ok(Some(s.len() - s.trim_start_matches('*').len())),
+        Some(b'?') => Ok(Some(1)),
+        Some(b'[') => {
+            let mut start = 1;
+            if s[start..].starts_with('!') {
+                start += 1;
+            }
+            if s[start..].starts_with(']') {
+                start += 1;
             }
-            Err(_) => return false,
+            s[start..]
+                .find(']')
+                .map(|end| Some(start + end + 1))
+                .ok_or_else(|| format!("unclosed character class in '{}'", s))
         }
+        _ => Ok(None),
     }
+}
 
-    if let Some(modified_at) = &filters.modified_at {
-        match metadata.modified() {
-            Ok(modified) => {
-                let modified: DateTime<Local> = modified.into();
-                if !match_datetime_range(&modified, &modified_at.start, &modified_at.end) {
-                    return false;
-                }
-            }
-            Err(_) => return false,
+/// `s` の先頭にある名前付きワイルドカード `{label:*}` の名前・ワイルドカード・全体の長さを返す。
+fn named_wildcard(s: &str) -> Result<Option<(&str, &str, usize)>, String> {
+    let Some((name, rest)) = s.strip_prefix('{').and_then(|body| body.split_once(':')) else {
+        return Ok(None);
+    };
+    let valid_name = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid_name {
+        return Ok(None);
+    }
+    match wildcard_len(rest)? {
+        Some(len) if rest[len..].starts_with('}') => {
+            Ok(Some((name, &rest[..len], name.len() + len + 3)))
         }
+        _ => Ok(None),
     }
+}
 
-    true
+fn parse_glob(pattern: &str) -> Result<Vec<GlobToken<'_>>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = pattern;
+    while let Some(c) = rest.chars().next() {
+        let (token, len) = if let Some((name, glob, len)) = named_wildcard(rest)? {
+            (
+                GlobToken::Wildcard {
+                    glob,
+                    name: Some(name),
+                },
+                len,
+            )
+        } else if let Some(len) = wildcard_len(rest)? {
+            (
+                GlobToken::Wildcard {
+                    glob: &rest[..len],
+                    name: None,
+                },
+                len,
+            )
+        } else {
+            (GlobToken::Literal(c), c.len_utf8())
+        };
+        tokens.push(token);
+        rest = &rest[len..];
+    }
+    Ok(tokens)
 }
 
-fn match_extensions(path: &Path, extensions: &[String]) -> bool {
-    let file_ext = path
-        .extension()
-        .and_then(|e| e.to_str())
-        .map(|e| format!(".{}", e.to_lowercase()));
+/// 名前付きワイルドカード `{label:*}` を `*` に戻し、glob クレートで解釈できるパターンにする。
+pub fn glob_match_pattern(pattern: &str) -> Cow<'_, str> {
+    let Ok(tokens) = parse_glob(pattern) else {
+        return Cow::Borrowed(pattern);
+    };
+    if !tokens
+        .iter()
+        .any(|t| matches!(t, GlobToken::Wildcard { name: Some(_), .. }))
+    {
+        return Cow::Borrowed(pattern);
+    }
+    let mut plain = String::with_capacity(pattern.len());
+    for token in tokens {
+        match token {
+            GlobToken::Literal(c) => plain.push(c),
+            GlobToken::Wildcard { glob, .. } => plain.push_str(glob),
+        }
+    }
+    Cow::Owned(plain)
+}
 
-    match file_ext {
-        Some(ext) => extensions.iter().any(|e| e.to_lowercase() == ext),
-        None => false,
+/// glob パターンのワイルドカードで参照できる変数名（`1`・`2`… と名前付きワイルドカードの名前）を返す。
+pub fn glob_capture_names(pattern: &str) -> Result<Vec<String>, String> {
+    let mut names = Vec::new();
+    let wildcards = parse_glob(pattern)?.into_iter().filter_map(|t| match t {
+        GlobToken::Wildcard { name, .. } => Some(name),
+        GlobToken::Literal(_) => None,
+    });
+    for (i, name) in wildcards.enumerate() {
+        names.push((i + 1).to_string());
+        names.extend(name.map(str::to_string));
     }
+    Ok(names)
 }
 
-fn match_filename(path: &Path, pattern: &str, match_type: &MatchType) -> bool {
-    let filename = match path.file_name().and_then(|f| f.to_str()) {
-        Some(f) => f,
-        None => return false,
+/// glob パターンを、ワイルドカードごとにキャプチャグループを持つ正規表現に変換する。
+/// `*` はできるだけ短く一致させる（`*_*` は最初の `_` で区切る）。
+fn glob_capture_regex(pattern: &str) -> Result<regex::Regex, String> {
+    let mut re = String::from("^");
+    for token in parse_glob(pattern)? {
+        match token {
+            GlobToken::Literal(c) => re.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+            GlobToken::Wildcard { glob, name } => {
+                re.push('(');
+                if let Some(name) = name {
+                    re.push_str(&format!("?P<{}>", name));
+                }
+                re.push_str(&wildcard_regex(glob));
+                re.push(')');
+            }
+        }
+    }
+    re.push('$');
+    regex::Regex::new(&re).map_err(|e| e.to_string())
+}
+
+fn wildcard_regex(glob: &str) -> String {
+    if glob.starts_with('*') {
+        return ".*?".to_string();
+    }
+    if glob == "?" {
+        return ".".to_string();
+    }
+    let inner = &glob[1..glob.len() - 1];
+    let (mut class, inner) = match inner.strip_prefix('!') {
+        Some(inner) => (String::from("[^"), inner),
+        None => (String::from("["), inner),
     };
+    for c in inner.chars() {
+        if matches!(c, '\\' | '[' | ']' | '^' | '&' | '~') {
+            class.push('\\');
+        }
+        class.push(c);
+    }
+    class.push(']');
+    class
+}
+
+/// フィルタを 1 回だけ評価する。多数のファイルを評価する場合は `CompiledFilters` を使う。
+pub fn matches_filters(path: &Path, metadata: &std::fs::Metadata, filters: &Filters) -> bool {
+    matches_filters_at(path, metadata, filters, Local::now())
+}
+
+/// `now` を実行時刻として、`older_than`・`newer_than` などの相対的な日時条件を評価する。
+/// 1 回の実行で列挙するファイルには同じ `now` を使い、判定がファイルごとにずれないようにする。
+pub fn matches_filters_at(
+    path: &Path,
+    metadata: &std::fs::Metadata,
+    filters: &Filters,
+    now: DateTime<Local>,
+) -> bool {
+    CompiledFilters::new(filters, now).matches(path, metadata)
+}
+
+/// ファイルごとに繰り返し評価できるよう、1 階層分のフィルタを事前に解析したもの。
+/// パターンのコンパイル・拡張子の正規化・日時とサイズの解析は作成時に 1 回だけ行う。
+/// 相対的な日時条件は作成時の `now` を基準に絶対時刻へ変換する。
+pub struct CompiledFilters {
+    /// 解析できない条件がある場合は `true`。この階層に一致するファイルはない
+    unsatisfiable: bool,
+    /// パターンが不正な除外条件は何も除外しないため含めない
+    exclude: Vec<NameMatcher>,
+    /// 小文字に揃えた `.` 付きの拡張子
+    extensions: Option<HashSet<String>>,
+    filename: Option<NameMatcher>,
+    filename_captures: Option<FilenameCaptures>,
+    captures: Option<Vec<CompiledCaptureCondition>>,
+    created_at: Option<Bounds<DateTime<Local>>>,
+    modified_at: Option<Bounds<DateTime<Local>>>,
+    accessed_at: Option<Bounds<DateTime<Local>>>,
+    size: Option<Bounds<u64>>,
+    all: Option<Vec<CompiledFilters>>,
+    any: Option<Vec<CompiledFilters>>,
+    not: Option<Box<CompiledFilters>>,
+}
+
+impl CompiledFilters {
+    pub fn new(filters: &Filters, now: DateTime<Local>) -> Self {
+        let mut unsatisfiable = false;
+
+        let filename = filters.filename.as_ref().and_then(|f| {
+            valid(
+                NameMatcher::new(&f.pattern, &f.match_type),
+                &mut unsatisfiable,
+            )
+        });
+        let filename_captures = match (&filters.filename, &filename) {
+            (Some(_), Some(NameMatcher::Regex(re))) => Some(FilenameCaptures {
+                re: re.clone(),
+                positional: false,
+            }),
+            (Some(filter), Some(NameMatcher::Glob(_))) => FilenameCaptures::new(filter),
+            _ => None,
+        };
+        let captures = filters.captures.as_ref().map(|conditions| {
+            conditions
+                .iter()
+                .map(|condition| CompiledCaptureCondition {
+                    matches: condition.matches.as_deref().and_then(|pattern| {
+                        valid(
+                            regex::Regex::new(pattern).map_err(|e| e.to_string()),
+                            &mut unsatisfiable,
+                        )
+                    }),
+                    condition: condition.clone(),
+                })
+                .collect()
+        });
+        let mut datetime = |range: &Option<DateTimeRange>| {
+            range
+                .as_ref()
+                .and_then(|range| valid(datetime_bounds(range, now), &mut unsatisfiable))
+        };
+        let created_at = datetime(&filters.created_at);
+        let modified_at = datetime(&filters.modified_at);
+        let accessed_at = datetime(&filters.accessed_at);
+        let size = filters
+            .size
+            .as_ref()
+            .and_then(|range| valid(size_bounds(range), &mut unsatisfiable));
+
+        let exclude = filters
+            .exclude
+            .iter()
+            .flatten()
+            .filter_map(|e| NameMatcher::new(&e.pattern, &e.match_type).ok())
+            .collect();
+        let extensions = filters
+            .extensions
+            .as_ref()
+            .map(|extensions| extensions.iter().map(|e| e.to_lowercase()).collect());
+        let compile_all = |list: &Vec<Filters>| list.iter().map(|f| Self::new(f, now)).collect();
+
+        Self {
+            unsatisfiable,
+            exclude,
+            extensions,
+            filename,
+            filename_captures,
+            captures,
+            created_at,
+            modified_at,
+            accessed_at,
+            size,
+            all: filters.all.as_ref().map(compile_all),
+            any: filters.any.as_ref().map(compile_all),
+            not: filters.not.as_ref().map(|f| Box::new(Self::new(f, now))),
+        }
+    }
+
+    pub fn matches(&self, path: &Path, metadata: &std::fs::Metadata) -> bool {
+        let filename = path.file_name().and_then(|f| f.to_str());
+        self.matches_within(path, filename, metadata, None)
+    }
 
-    match match_type {
-        MatchType::Glob => {
-            let glob_pattern = glob::Pattern::new(pattern);
-            match glob_pattern {
-                Ok(p) => p.matches(filename),
-                Err(_) => false,
+    /// ファイル名フィルタのキャプチャ。ファイル名フィルタがない・パターンが不正な場合は `None`。
+    pub fn filename_captures(&self) -> Option<&FilenameCaptures> {
+        self.filename_captures.as_ref()
+    }
+
+    /// `inherited` は外側の階層のファイル名フィルタのキャプチャで、同じ階層に `filename` がない場合の
+    /// `captures` の評価に使う。
+    fn matches_within(
+        &self,
+        path: &Path,
+        filename: Option<&str>,
+        metadata: &std::fs::Metadata,
+        inherited: Option<&FilenameCaptures>,
+    ) -> bool {
+        if self.unsatisfiable {
+            return false;
+        }
+
+        if self
+            .exclude
+            .iter()
+            .any(|e| filename.is_some_and(|f| e.is_match(f)))
+        {
+            return false;
+        }
+
+        if let Some(extensions) = &self.extensions {
+            let file_ext = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| format!(".{}", e.to_lowercase()));
+            if !file_ext.is_some_and(|ext| extensions.contains(&ext)) {
+                return false;
             }
         }
-        MatchType::Regex => {
-            let re = regex::Regex::new(pattern);
-            match re {
-                Ok(r) => r.is_match(filename),
-                Err(_) => false,
+
+        if let Some(matcher) = &self.filename {
+            if !filename.is_some_and(|f| matcher.is_match(f)) {
+                return false;
+            }
+        }
+
+        let filename_captures = match self.filename {
+            Some(_) => self.filename_captures.as_ref(),
+            None => inherited,
+        };
+        if let Some(conditions) = &self.captures {
+            let captures = match (filename_captures, filename) {
+                (Some(captures), Some(filename)) => captures.extract(filename),
+                _ => HashMap::new(),
+            };
+            if !conditions.iter().all(|c| c.matches(&captures)) {
+                return false;
+            }
+        }
+
+        let timestamps = [
+            (&self.created_at, metadata.created()),
+            (&self.modified_at, metadata.modified()),
+            (&self.accessed_at, metadata.accessed()),
+        ];
+        for (bounds, time) in timestamps {
+            let Some(bounds) = bounds else {
+                continue;
+            };
+            // 日時を取得できないファイルは条件を満たさないものとする
+            match time {
+                Ok(time) if bounds.contains(time.into()) => {}
+                _ => return false,
+            }
+        }
+
+        if let Some(size) = &self.size {
+            if !size.contains(metadata.len()) {
+                return false;
+            }
+        }
+
+        let nested =
+            |f: &CompiledFilters| f.matches_within(path, filename, metadata, filename_captures);
+        if let Some(all) = &self.all {
+            if !all.iter().all(nested) {
+                return false;
             }
         }
+        if let Some(any) = &self.any {
+            if !any.iter().any(nested) {
+                return false;
+            }
+        }
+        if let Some(not) = &self.not {
+            if nested(not) {
+                return false;
+            }
+        }
+
+        true
     }
 }
 
-fn match_datetime_range(
-    value: &DateTime<Local>,
-    start: &Option<String>,
-    end: &Option<String>,
-) -> bool {
-    if let Some(start_str) = start {
-        match DateTime::parse_from_rfc3339(start_str) {
-            Ok(start_dt) => {
-                let start_dt: DateTime<Local> = start_dt.into();
-                if value < &start_dt {
-                    return false;
-                }
-            }
-            Err(_) => return false,
+/// 解析に失敗した場合は `unsatisfiable` を立てて `None` を返す
+fn valid<T>(parsed: Result<T, String>, unsatisfiable: &mut bool) -> Option<T> {
+    parsed.map_err(|_| *unsatisfiable = true).ok()
+}
+
+/// コンパイル済みのファイル名パターン
+enum NameMatcher {
+    Glob(glob::Pattern),
+    Regex(regex::Regex),
+}
+
+impl NameMatcher {
+    fn new(pattern: &str, match_type: &MatchType) -> Result<Self, String> {
+        match match_type {
+            MatchType::Glob => glob::Pattern::new(&glob_match_pattern(pattern))
+                .map(Self::Glob)
+                .map_err(|e| e.to_string()),
+            MatchType::Regex => regex::Regex::new(pattern)
+                .map(Self::Regex)
+                .map_err(|e| e.to_string()),
         }
     }
-    if let Some(end_str) = end {
-        match DateTime::parse_from_rfc3339(end_str) {
-            Ok(end_dt) => {
-                let end_dt: DateTime<Local> = end_dt.into();
-                if value > &end_dt {
-                    return false;
-                }
+
+    fn is_match(&self, filename: &str) -> bool {
+        match self {
+            Self::Glob(p) => p.matches(filename),
+            Self::Regex(r) => r.is_match(filename),
+        }
+    }
+}
+
+/// `matches` の正規表現をコンパイル済みのキャプチャ条件
+struct CompiledCaptureCondition {
+    condition: CaptureCondition,
+    matches: Option<regex::Regex>,
+}
+
+impl CompiledCaptureCondition {
+    fn matches(&self, captures: &HashMap<String, String>) -> bool {
+        let condition = &self.condition;
+        let Some(value) = captures.get(&condition.name) else {
+            return false;
+        };
+        if condition.equals.as_ref().is_some_and(|e| e != value) {
+            return false;
+        }
+        if condition
+            .one_of
+            .as_ref()
+            .is_some_and(|list| !list.contains(value))
+        {
+            return false;
+        }
+        if condition.min.is_some() || condition.max.is_some() {
+            // 数値として解釈できない値は範囲の条件を満たさない
+            let Some(number) = value.trim().parse::<f64>().ok().filter(|n| n.is_finite()) else {
+                return false;
+            };
+            if condition.min.is_some_and(|min| number < min)
+                || condition.max.is_some_and(|max| number > max)
+            {
+                return false;
             }
-            Err(_) => return false,
         }
+        self.matches.as_ref().is_none_or(|re| re.is_match(value))
     }
-    true
+}
+
+/// 下限・上限（どちらも含む）
+struct Bounds<T> {
+    min: Option<T>,
+    max: Option<T>,
+}
+
+impl<T: Ord + Copy> Bounds<T> {
+    fn contains(&self, value: T) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+}
+
+/// `start`・`newer_than` を下限、`end`・`older_than` を上限とし、両方ある場合は狭いほうを使う。
+fn datetime_bounds(
+    range: &DateTimeRange,
+    now: DateTime<Local>,
+) -> Result<Bounds<DateTime<Local>>, String> {
+    let parse = |s: &Option<String>| {
+        s.as_deref()
+            .map(|s| DateTime::parse_from_rfc3339(s).map(|dt| dt.with_timezone(&Local)))
+            .transpose()
+            .map_err(|e| e.to_string())
+    };
+    let cutoff = |s: &Option<String>| {
+        s.as_deref()
+            .map(|s| {
+                now.checked_sub_signed(parse_duration(s)?)
+                    .ok_or_else(|| format!("duration is too large: '{}'", s))
+            })
+            .transpose()
+    };
+    Ok(Bounds {
+        min: [parse(&range.start)?, cutoff(&range.newer_than)?]
+            .into_iter()
+            .flatten()
+            .max(),
+        max: [parse(&range.end)?, cutoff(&range.older_than)?]
+            .into_iter()
+            .flatten()
+            .min(),
+    })
+}
+
+fn size_bounds(range: &SizeRange) -> Result<Bounds<u64>, String> {
+    Ok(Bounds {
+        min: range.min.as_deref().map(parse_size).transpose()?,
+        max: range.max.as_deref().map(parse_size).transpose()?,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ruleset::{DateTimeRange, FilenameFilter};
     use std::fs;
 
     fn create_test_file(dir: &Path, name: &str) -> std::path::PathBuf {
@@ -148,6 +590,13 @@ mod tests {
             filename: None,
             created_at: None,
             modified_at: None,
+            accessed_at: None,
+            size: None,
+            exclude: None,
+            all: None,
+            any: None,
+            not: None,
+            captures: None,
         };
 
         assert!(matches_filters(&path, &meta, &filters));
@@ -164,6 +613,13 @@ mod tests {
             filename: None,
             created_at: None,
             modified_at: None,
+            accessed_at: None,
+            size: None,
+            exclude: None,
+            all: None,
+            any: None,
+            not: None,
+            captures: None,
         };
 
         assert!(matches_filters(&path, &meta, &filters));
@@ -180,6 +636,13 @@ mod tests {
             filename: None,
             created_at: None,
             modified_at: None,
+            accessed_at: None,
+            size: None,
+            exclude: None,
+            all: None,
+            any: None,
+            not: None,
+            captures: None,
         };
 
         assert!(!matches_filters(&path, &meta, &filters));
@@ -199,6 +662,13 @@ mod tests {
             }),
             created_at: None,
             modified_at: None,
+            accessed_at: None,
+            size: None,
+            exclude: None,
+            all: None,
+            any: None,
+            not: None,
+            captures: None,
         };
 
         assert!(matches_filters(&path, &meta, &filters));
@@ -218,6 +688,13 @@ mod tests {
             }),
             created_at: None,
             modified_at: None,
+            accessed_at: None,
+            size: None,
+            exclude: None,
+            all: None,
+            any: None,
+            not: None,
+            captures: None,
         };
 
         assert!(!matches_filters(&path, &meta, &filters));
@@ -237,6 +714,13 @@ mod tests {
             }),
             created_at: None,
             modified_at: None,
+            accessed_at: None,
+            size: None,
+            exclude: None,
+            all: None,
+            any: None,
+            not: None,
+            captures: None,
         };
 
         assert!(matches_filters(&path, &meta, &filters));
@@ -256,6 +740,13 @@ mod tests {
             }),
             created_at: None,
             modified_at: None,
+            accessed_at: None,
+            size: None,
+            exclude: None,
+            all: None,
+            any: None,
+            not: None,
+            captures: None,
         };
 
         assert!(!matches_filters(&path, &meta, &filters));
@@ -275,6 +766,13 @@ mod tests {
             }),
             created_at: None,
             modified_at: None,
+            accessed_at: None,
+            size: None,
+            exclude: None,
+            all: None,
+            any: None,
+            not: None,
+            captures: None,
         };
 
         assert!(matches_filters(&path, &meta, &filters));
@@ -287,6 +785,13 @@ mod tests {
             }),
             created_at: None,
             modified_at: None,
+            accessed_at: None,
+            size: None,
+            exclude: None,
+            all: None,
+            any: None,
+            not: None,
+            captures: None,
         };
 
         assert!(!matches_filters(&path, &meta, &filters_no_match));
@@ -305,7 +810,16 @@ mod tests {
             modified_at: Some(DateTimeRange {
                 start: Some((chrono::Local::now() - chrono::Duration::hours(1)).to_rfc3339()),
                 end: None,
+                older_than: None,
+                newer_than: None,
             }),
+            accessed_at: None,
+            size: None,
+            exclude: None,
+            all: None,
+            any: None,
+            not: None,
+            captures: None,
         };
 
         assert!(matches_filters(&path, &meta, &filters));
@@ -324,7 +838,16 @@ mod tests {
             modified_at: Some(DateTimeRange {
                 start: None,
                 end: Some((chrono::Local::now() - chrono::Duration::hours(1)).to_rfc3339()),
+                older_than: None,
+                newer_than: None,
             }),
+            accessed_at: None,
+            size: None,
+            exclude: None,
+            all: None,
+            any: None,
+            not: None,
+            captures: None,
         };
 
         assert!(!matches_filters(&path, &meta, &filters));
@@ -341,6 +864,13 @@ mod tests {
             filename: None,
             created_at: None,
             modified_at: None,
+            accessed_at: None,
+            size: None,
+            exclude: None,
+            all: None,
+            any: None,
+            not: None,
+            captures: None,
         };
 
         assert!(matches_filters(&path, &meta, &filters));
@@ -380,6 +910,131 @@ mod tests {
         assert_eq!(captures.get("day").map(|s| s.as_str()), Some("15"));
     }
 
+    // --- glob のキャプチャのテスト ---
+
+    fn glob_filter(pattern: &str) -> FilenameFilter {
+        FilenameFilter {
+            pattern: pattern.to_string(),
+            match_type: MatchType::Glob,
+        }
+    }
+
+    #[test]
+    fn test_glob_captures_positional_and_named() {
+        let captures = FilenameCaptures::new(&glob_filter("{label:*}_report_*.pdf")).unwrap();
+        let values = captures.extract("acme_report_2025-Q1.pdf");
+        assert_eq!(values.get("label").map(|s| s.as_str()), Some("acme"));
+        assert_eq!(values.get("1").map(|s| s.as_str()), Some("acme"));
+        assert_eq!(values.get("2").map(|s| s.as_str()), Some("2025-Q1"));
+        assert!(captures.extract("acme_invoice.pdf").is_empty());
+
+        // `*` は最短一致、`?`・`[...]` も 1 つのワイルドカードとして数える
+        let captures = FilenameCaptures::new(&glob_filter("*_?[!0-9]*.txt")).unwrap();
+        let values = captures.extract("a_b_xy.txt");
+        assert_eq!(values.get("1").map(|s| s.as_str()), Some("a"));
+        assert_eq!(values.get("2").map(|s| s.as_str()), Some("b"));
+        assert_eq!(values.get("3").map(|s| s.as_str()), Some("_"));
+        assert_eq!(values.get("4").map(|s| s.as_str()), Some("xy"));
+    }
+
+    #[test]
+    fn test_named_glob_matches_like_plain_glob() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = create_test_file(dir.path(), "acme_report_1.pdf");
+        let meta = fs::metadata(&path).unwrap();
+        let mut filters = Filters {
+            extensions: None,
+            filename: Some(glob_filter("{label:*}_report_*.pdf")),
+            created_at: None,
+            modified_at: None,
+            accessed_at: None,
+            size: None,
+            exclude: None,
+            all: None,
+            any: None,
+            not: None,
+            captures: None,
+        };
+        assert!(matches_filters(&path, &meta, &filters));
+        filters.filename = Some(glob_filter("{label:*}_invoice_*.pdf"));
+        assert!(!matches_filters(&path, &meta, &filters));
+
+        assert_eq!(glob_match_pattern("{id:[0-9]}_{x}*"), "[0-9]_{x}*");
+        assert_eq!(
+            glob_capture_names("{label:*}_*.pdf").unwrap(),
+            vec!["1", "label", "2"]
+        );
+        assert!(glob_capture_names("[abc_*.pdf").is_err());
+    }
+
+    #[test]
+    fn test_capture_conditions() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = create_test_file(dir.path(), "2019_acme_0042.pdf");
+        let recent = create_test_file(dir.path(), "2024_acme_0007.pdf");
+        let other = create_test_file(dir.path(), "2020_globex_0100.pdf");
+        let unmatched = create_test_file(dir.path(), "notes.pdf");
+
+        let condition = |name: &str| CaptureCondition {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let mut filters = Filters {
+            filename: Some(FilenameFilter {
+                pattern: r"^(?P<year>\d{4})_(?P<client>[a-z]+)_(?P<id>\d+)".to_string(),
+                match_type: MatchType::Regex,
+            }),
+            captures: Some(vec![CaptureCondition {
+                max: Some(2023.0),
+                ..condition("year")
+            }]),
+            ..Default::default()
+        };
+        let matches = |path: &Path, filters: &Filters| {
+            matches_filters(path, &fs::metadata(path).unwrap(), filters)
+        };
+        assert!(matches(&old, &filters));
+        assert!(!matches(&recent, &filters));
+        assert!(matches(&other, &filters));
+        assert!(!matches(&unmatched, &filters));
+
+        filters.captures = Some(vec![
+            CaptureCondition {
+                one_of: Some(vec!["acme".to_string(), "initech".to_string()]),
+                ..condition("client")
+            },
+            CaptureCondition {
+                matches: Some("^00".to_string()),
+                ..condition("id")
+            },
+        ]);
+        assert!(matches(&old, &filters));
+        assert!(matches(&recent, &filters));
+        assert!(!matches(&other, &filters));
+
+        // グループ内では外側のファイル名フィルタのキャプチャを使う
+        filters.captures = None;
+        filters.any = Some(vec![
+            Filters {
+                captures: Some(vec![CaptureCondition {
+                    equals: Some("globex".to_string()),
+                    ..condition("client")
+                }]),
+                ..Default::default()
+            },
+            Filters {
+                captures: Some(vec![CaptureCondition {
+                    min: Some(2024.0),
+                    ..condition("year")
+                }]),
+                ..Default::default()
+            },
+        ]);
+        assert!(!matches(&old, &filters));
+        assert!(matches(&recent, &filters));
+        assert!(matches(&other, &filters));
+    }
+
     // --- match_datetime_range のパースエラーテスト ---
 
     #[test]
@@ -395,7 +1050,16 @@ mod tests {
             modified_at: Some(DateTimeRange {
                 start: Some("invalid-date".to_string()),
                 end: None,
+                older_than: None,
+                newer_than: None,
             }),
+            accessed_at: None,
+            size: None,
+            exclude: None,
+            all: None,
+            any: None,
+            not: None,
+            captures: None,
         };
 
         assert!(!matches_filters(&path, &meta, &filters));
@@ -414,9 +1078,318 @@ mod tests {
             modified_at: Some(DateTimeRange {
                 start: None,
                 end: Some("not-a-date".to_string()),
+                older_than: None,
+                newer_than: None,
             }),
+            accessed_at: None,
+            size: None,
+            exclude: None,
+            all: None,
+            any: None,
+            not: None,
+            captures: None,
         };
 
         assert!(!matches_filters(&path, &meta, &filters));
     }
+
+    #[test]
+    fn test_size_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let small = dir.path().join("clip.mp4");
+        let large = dir.path().join("movie.mp4");
+        fs::write(&small, vec![0u8; 1_000]).unwrap();
+        fs::write(&large, vec![0u8; 5_000]).unwrap();
+
+        let filters = Filters {
+            extensions: Some(vec![".mp4".to_string()]),
+            size: Some(SizeRange {
+                min: Some("2KB".to_string()),
+                max: None,
+            }),
+            ..Default::default()
+        };
+        assert!(!matches_filters(
+            &small,
+            &fs::metadata(&small).unwrap(),
+            &filters
+        ));
+        assert!(matches_filters(
+            &large,
+            &fs::metadata(&large).unwrap(),
+            &filters
+        ));
+
+        // 境界値は範囲に含む
+        let filters = Filters {
+            size: Some(SizeRange {
+                min: Some("1000".to_string()),
+                max: Some("1 KB".to_string()),
+            }),
+            ..Default::default()
+        };
+        assert!(matches_filters(
+            &small,
+            &fs::metadata(&small).unwrap(),
+            &filters
+        ));
+        assert!(!matches_filters(
+            &large,
+            &fs::metadata(&large).unwrap(),
+            &filters
+        ));
+    }
+
+    #[test]
+    fn test_exclude_patterns_take_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let photo = create_test_file(dir.path(), "photo.jpg");
+        let pinned = create_test_file(dir.path(), "pinned_cover.jpg");
+        let partial = create_test_file(dir.path(), "movie.jpg.part");
+
+        let filters = Filters {
+            filename: Some(FilenameFilter {
+                pattern: "*".to_string(),
+                match_type: MatchType::Glob,
+            }),
+            exclude: Some(vec![
+                FilenameFilter {
+                    pattern: "pinned_*".to_string(),
+                    match_type: MatchType::Glob,
+                },
+                FilenameFilter {
+                    pattern: r"\.part$".to_string(),
+                    match_type: MatchType::Regex,
+                },
+            ]),
+            ..Default::default()
+        };
+
+        assert!(matches_filters(
+            &photo,
+            &fs::metadata(&photo).unwrap(),
+            &filters
+        ));
+        assert!(!matches_filters(
+            &pinned,
+            &fs::metadata(&pinned).unwrap(),
+            &filters
+        ));
+        assert!(!matches_filters(
+            &partial,
+            &fs::metadata(&partial).unwrap(),
+            &filters
+        ));
+    }
+
+    #[test]
+    fn test_nested_any_all_not_groups() {
+        let dir = tempfile::tempdir().unwrap();
+        let photo = create_test_file(dir.path(), "photo.jpg");
+        let screenshot = create_test_file(dir.path(), "screenshot_01.png");
+        let note = create_test_file(dir.path(), "note.txt");
+        let video = dir.path().join("video.mp4");
+        fs::write(&video, vec![0u8; 20_000]).unwrap();
+
+        // (.jpg か .png で、screenshot_* ではない) または 10KB より大きい
+        let filters = Filters {
+            any: Some(vec![
+                Filters {
+                    extensions: Some(vec![".jpg".to_string(), ".png".to_string()]),
+                    not: Some(Box::new(Filters {
+                        filename: Some(FilenameFilter {
+                            pattern: "screenshot_*".to_string(),
+                            match_type: MatchType::Glob,
+                        }),
+                        ..Default::default()
+                    })),
+                    ..Default::default()
+                },
+                Filters {
+                    size: Some(SizeRange {
+                        min: Some("10KB".to_string()),
+                        max: None,
+                    }),
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        };
+
+        let matches = |path: &Path| matches_filters(path, &fs::metadata(path).unwrap(), &filters);
+        assert!(matches(&photo));
+        assert!(!matches(&screenshot));
+        assert!(!matches(&note));
+        assert!(matches(&video));
+    }
+
+    #[test]
+    fn test_groups_combine_with_leaf_filters() {
+        let dir = tempfile::tempdir().unwrap();
+        let report = create_test_file(dir.path(), "report_2024.txt");
+        let draft = create_test_file(dir.path(), "draft_2024.txt");
+
+        // 同じ階層のフィルタとグループは AND で結合する
+        let filters = Filters {
+            extensions: Some(vec![".txt".to_string()]),
+            all: Some(vec![Filters {
+                filename: Some(FilenameFilter {
+                    pattern: "*_2024*".to_string(),
+                    match_type: MatchType::Glob,
+                }),
+                ..Default::default()
+            }]),
+            not: Some(Box::new(Filters {
+                filename: Some(FilenameFilter {
+                    pattern: "^draft_".to_string(),
+                    match_type: MatchType::Regex,
+                }),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+
+        assert!(matches_filters(
+            &report,
+            &fs::metadata(&report).unwrap(),
+            &filters
+        ));
+        assert!(!matches_filters(
+            &draft,
+            &fs::metadata(&draft).unwrap(),
+            &filters
+        ));
+    }
+
+    #[test]
+    fn test_relative_age_against_execution_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = create_test_file(dir.path(), "report.txt");
+        let meta = fs::metadata(&path).unwrap();
+        let now = Local::now();
+
+        let older = Filters {
+            modified_at: Some(DateTimeRange {
+                older_than: Some("30d".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let newer = Filters {
+            modified_at: Some(DateTimeRange {
+                newer_than: Some("12h".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        // 作成直後のファイルは 30 日より新しい
+        assert!(!matches_filters_at(&path, &meta, &older, now));
+        assert!(matches_filters_at(&path, &meta, &newer, now));
+
+        // 実行時刻が 31 日後なら古いファイルとして扱う
+        let later = now + chrono::Duration::days(31);
+        assert!(matches_filters_at(&path, &meta, &older, later));
+        assert!(!matches_filters_at(&path, &meta, &newer, later));
+
+        // 基準時刻から引くと日時の範囲を超える場合は、パニックせずにどのファイルにも一致しない
+        use chrono::TimeZone;
+        let near_min = Local.with_ymd_and_hms(-262_143, 1, 1, 0, 0, 0).unwrap();
+        assert!(!matches_filters_at(&path, &meta, &older, near_min));
+    }
+
+    #[test]
+    fn test_accessed_at_filter() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = create_test_file(dir.path(), "seen.txt");
+        let meta = fs::metadata(&path).unwrap();
+        let now = Local::now();
+
+        let filters = Filters {
+            accessed_at: Some(DateTimeRange {
+                older_than: Some("1d".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(!matches_filters_at(&path, &meta, &filters, now));
+        assert!(matches_filters_at(
+            &path,
+            &meta,
+            &filters,
+            now + chrono::Duration::days(2)
+        ));
+    }
+
+    #[test]
+    fn test_compiled_filters_reused_across_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let filters = Filters {
+            extensions: Some(vec![".PDF".to_string()]),
+            filename: Some(FilenameFilter {
+                pattern: r"^(?P<year>\d{4})_.+".to_string(),
+                match_type: MatchType::Regex,
+            }),
+            captures: Some(vec![CaptureCondition {
+                name: "year".to_string(),
+                max: Some(2023.0),
+                ..Default::default()
+            }]),
+            modified_at: Some(DateTimeRange {
+                newer_than: Some("1d".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let compiled = CompiledFilters::new(&filters, Local::now());
+        let matches = |name: &str| {
+            let path = create_test_file(dir.path(), name);
+            compiled.matches(&path, &fs::metadata(&path).unwrap())
+        };
+
+        assert!(matches("2023_report.pdf"));
+        assert!(matches("2020_scan.Pdf"));
+        assert!(!matches("2024_report.pdf"));
+        assert!(!matches("2023_report.txt"));
+        assert!(!matches("report.pdf"));
+        let captures = compiled
+            .filename_captures()
+            .unwrap()
+            .extract("2023_report.pdf");
+        assert_eq!(captures["year"], "2023");
+    }
+
+    #[test]
+    fn test_compiled_filters_invalid_conditions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = create_test_file(dir.path(), "report.txt");
+        let meta = fs::metadata(&path).unwrap();
+        let now = Local::now();
+
+        // 不正な除外パターンは何も除外しない
+        let invalid_exclude = Filters {
+            extensions: Some(vec![".txt".to_string()]),
+            exclude: Some(vec![FilenameFilter {
+                pattern: "(".to_string(),
+                match_type: MatchType::Regex,
+            }]),
+            ..Default::default()
+        };
+        assert!(CompiledFilters::new(&invalid_exclude, now).matches(&path, &meta));
+
+        // 解析できない条件がある階層には一致しない。`not` の中なら否定される
+        let invalid_size = Filters {
+            size: Some(SizeRange {
+                min: Some("huge".to_string()),
+                max: None,
+            }),
+            ..Default::default()
+        };
+        assert!(!CompiledFilters::new(&invalid_size, now).matches(&path, &meta));
+        let negated = Filters {
+            not: Some(Box::new(invalid_size)),
+            ..Default::default()
+        };
+        assert!(CompiledFilters::new(&negated, now).matches(&path, &meta));
+    }
 }
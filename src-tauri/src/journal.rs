@@ -0,0 +1,488 @@
+use crate::engine::{self, ExecutionResult};
+use crate::ruleset::Action;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// 保持する実行履歴の最大件数。超えた分は古い順に削除する。
+const MAX_RUNS: usize = 200;
+
+/// 履歴ファイルの読み書きを直列化するロック（実行・Undo が並行しても記録を失わないため）
+static JOURNAL_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JournalFile {
+    pub filename: String,
+    pub source_path: PathBuf,
+    pub destination_path: PathBuf,
+    #[serde(default)]
+    pub undone: bool,
+}
+
+/// 1 回のルールセット実行で成功したファイル操作の記録
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RunRecord {
+    pub run_id: String,
+    /// RFC3339 形式の実行日時
+    pub timestamp: String,
+    pub ruleset_id: String,
+    pub ruleset_name: String,
+    pub action: Action,
+    pub files: Vec<JournalFile>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum JournalError {
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Run not found: {0}")]
+    NotFound(String),
+}
+
+pub fn default_history_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("filo")
+        .join("history")
+}
+
+/// 実行履歴の保存先。1 回の実行を 1 つの JSON ファイル（`<run_id>.json`）として保存する。
+pub struct Journal {
+    dir: PathBuf,
+}
+
+impl Journal {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn run_path(&self, run_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", run_id))
+    }
+
+    fn write_run(&self, record: &RunRecord) -> Result<(), JournalError> {
+        fs::create_dir_all(&self.dir)?;
+        // 書き込み途中で終了しても既存の記録が壊れないよう、一時ファイル経由で置き換える
+        let path = self.run_path(&record.run_id);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(record)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// 保存されている記録とそのファイルのパスを新しい順に返す。
+    /// `run_id` が UUID でない記録は `load` で読めないため、一覧にも含めない。
+    fn read_runs(&self) -> Result<Vec<(PathBuf, RunRecord)>, JournalError> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut runs = Vec::new();
+        for entry in fs::read_dir(&self.dir)?.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            // 壊れた記録は一覧から除外し、他の履歴は引き続き使えるようにする
+            let Ok(content) = fs::read(&path) else {
+                continue;
+            };
+            match serde_json::from_slice::<RunRecord>(&content) {
+                Ok(record) if Uuid::parse_str(&record.run_id).is_ok() => runs.push((path, record)),
+                _ => {}
+            }
+        }
+        // 新しい順。タイムゾーンが異なる記録も正しく並ぶよう日時として比較する
+        runs.sort_by_key(|(_, r)| {
+            std::cmp::Reverse(chrono::DateTime::parse_from_rfc3339(&r.timestamp).ok())
+        });
+        Ok(runs)
+    }
+
+    /// 実行結果のうち成功したファイルを記録する。成功したファイルがない場合は記録しない。
+    pub fn record(&self, result: &ExecutionResult) -> Result<Option<RunRecord>, JournalError> {
+        let files: Vec<JournalFile> = result
+            .succeeded
+            .iter()
+            .filter_map(|f| {
+                Some(JournalFile {
+                    filename: f.filename.clone(),
+                    source_path: f.source_path.clone(),
+                    destination_path: f.destination_path.clone()?,
+                    undone: false,
+                })
+            })
+            .collect();
+        if files.is_empty() {
+            return Ok(None);
+        }
+
+        let record = RunRecord {
+            run_id: Uuid::new_v4().to_string(),
+            timestamp: chrono::Local::now().to_rfc3339(),
+            ruleset_id: result.ruleset_id.clone(),
+            ruleset_name: result.ruleset_name.clone(),
+            action: result.action.clone(),
+            files,
+        };
+
+        let _guard = JOURNAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        self.write_run(&record)?;
+        self.prune()?;
+        Ok(Some(record))
+    }
+
+    /// 保存されている実行履歴を新しい順に返す。
+    pub fn list(&self) -> Result<Vec<RunRecord>, JournalError> {
+        let _guard = JOURNAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(self.read_runs()?.into_iter().map(|(_, r)| r).collect())
+    }
+
+    /// `run_id` は画面から渡されるため、履歴フォルダの外を指さないよう UUID 以外は見つからない扱いにする。
+    pub fn load(&self, run_id: &str) -> Result<RunRecord, JournalError> {
+        let path = self.run_path(run_id);
+        if Uuid::parse_str(run_id).is_err() || !path.exists() {
+            return Err(JournalError::NotFound(run_id.to_string()));
+        }
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    fn prune(&self) -> Result<(), JournalError> {
+        // 記録の中の `run_id` ではなく、読み込んだファイルそのものを削除する
+        for (path, _) in self.read_runs()?.iter().skip(MAX_RUNS) {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// 実行全体を元に戻す。結果は `files` と同じ順序で、既に元に戻したファイルは `Ok` とする。
+    /// 後から処理したファイルから順に戻す。
+    pub fn undo_run(&self, run_id: &str) -> Result<Vec<Result<(), String>>, JournalError> {
+        let _guard = JOURNAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut record = self.load(run_id)?;
+        if record.action != Action::Move {
+            let err = Err("Undo is only supported for move operations".to_string());
+            return Ok(vec![err; record.files.len()]);
+        }
+        let mut results = vec![Ok(()); record.files.len()];
+        for (i, file) in record.files.iter_mut().enumerate().rev() {
+            if file.undone {
+                continue;
+            }
+            results[i] = engine::undo_file_move(&file.source_path, &file.destination_path);
+            file.undone = results[i].is_ok();
+        }
+        self.write_run(&record)?;
+        Ok(results)
+    }
+
+    /// 実行履歴の中の 1 ファイルを元に戻す。
+    pub fn undo_file(&self, run_id: &str, source_path: &Path) -> Result<(), String> {
+        let _guard = JOURNAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut record = self.load(run_id).map_err(|e| e.to_string())?;
+        if record.action != Action::Move {
+            return Err("Undo is only supported for move operations".to_string());
+        }
+        let file = record
+            .files
+            .iter_mut()
+            .find(|f| f.source_path == source_path)
+            .ok_or_else(|| format!("File not found in run: {}", source_path.display()))?;
+        if file.undone {
+            return Err("File has already been undone".to_string());
+        }
+        engine::undo_file_move(&file.source_path, &file.destination_path)?;
+        file.undone = true;
+        self.write_run(&record).map_err(|e| e.to_string())
+    }
+
+    /// 実行結果ダイアログから直接 Undo したファイルを、履歴上も元に戻し済みにする。
+    pub fn mark_undone(
+        &self,
+        source_path: &Path,
+        destination_path: &Path,
+    ) -> Result<(), JournalError> {
+        let _guard = JOURNAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        for (_, mut record) in self.read_runs()? {
+            let target = record.files.iter_mut().find(|f| {
+                !f.undone && f.source_path == source_path && f.destination_path == destination_path
+            });
+            if let Some(file) = target {
+                file.undone = true;
+                return self.write_run(&record);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{ExecutionStatus, FileResult};
+
+    fn moved_file(src: &Path, dst: &Path, name: &str) -> FileResult {
+        fs::write(dst.join(name), "content").unwrap();
+        FileResult {
+            filename: name.to_string(),
+            source_path: src.join(name),
+            destination_path: Some(dst.join(name)),
+            reason: None,
+            conflict: None,
+        }
+    }
+
+    fn sample_result(succeeded: Vec<FileResult>) -> ExecutionResult {
+        ExecutionResult {
+            ruleset_id: "test-id".to_string(),
+            ruleset_name: "test".to_string(),
+            action: Action::Move,
+            status: ExecutionStatus::Completed,
+            succeeded,
+            skipped: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_record_and_list() {
+        let history = tempfile::tempdir().unwrap();
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        let journal = Journal::new(history.path());
+
+        let result = sample_result(vec![moved_file(src.path(), dst.path(), "a.txt")]);
+        let record = journal.record(&result).unwrap().unwrap();
+
+        let runs = journal.list().unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0], record);
+        assert_eq!(runs[0].files[0].destination_path, dst.path().join("a.txt"));
+    }
+
+    #[test]
+    fn test_record_skips_runs_without_succeeded_files() {
+        let history = tempfile::tempdir().unwrap();
+        let journal = Journal::new(history.path());
+
+        assert!(journal
+            .record(&sample_result(Vec::new()))
+            .unwrap()
+            .is_none());
+        assert!(journal.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_undo_run_from_history() {
+        let history = tempfile::tempdir().unwrap();
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+
+        let result = sample_result(vec![
+            moved_file(src.path(), dst.path(), "a.txt"),
+            moved_file(src.path(), dst.path(), "b.txt"),
+        ]);
+        let run_id = Journal::new(history.path())
+            .record(&result)
+            .unwrap()
+            .unwrap()
+            .run_id;
+
+        // アプリ再起動を想定して別インスタンスから元に戻す
+        let journal = Journal::new(history.path());
+        let results = journal.undo_run(&run_id).unwrap();
+
+        assert!(results.iter().all(|r| r.is_ok()));
+        assert!(src.path().join("a.txt").exists());
+        assert!(src.path().join("b.txt").exists());
+        assert!(!dst.path().join("a.txt").exists());
+        assert!(journal
+            .load(&run_id)
+            .unwrap()
+            .files
+            .iter()
+            .all(|f| f.undone));
+
+        // 2 回目は既に元に戻し済みなので何もしない
+        let results = journal.undo_run(&run_id).unwrap();
+        assert!(results.iter().all(|r| r.is_ok()));
+    }
+
+    #[test]
+    fn test_undo_single_file_from_history() {
+        let history = tempfile::tempdir().unwrap();
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        let journal = Journal::new(history.path());
+
+        let result = sample_result(vec![
+            moved_file(src.path(), dst.path(), "a.txt"),
+            moved_file(src.path(), dst.path(), "b.txt"),
+        ]);
+        let run_id = journal.record(&result).unwrap().unwrap().run_id;
+
+        journal
+            .undo_file(&run_id, &src.path().join("a.txt"))
+            .unwrap();
+        assert!(src.path().join("a.txt").exists());
+        assert!(dst.path().join("b.txt").exists());
+
+        let record = journal.load(&run_id).unwrap();
+        assert!(record.files[0].undone);
+        assert!(!record.files[1].undone);
+        assert!(journal
+            .undo_file(&run_id, &src.path().join("a.txt"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_undo_copy_run_is_rejected() {
+        let history = tempfile::tempdir().unwrap();
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        let journal = Journal::new(history.path());
+
+        let mut result = sample_result(vec![moved_file(src.path(), dst.path(), "a.txt")]);
+        result.action = Action::Copy;
+        let run_id = journal.record(&result).unwrap().unwrap().run_id;
+
+        let results = journal.undo_run(&run_id).unwrap();
+        assert!(results[0].is_err());
+        assert!(dst.path().join("a.txt").exists());
+    }
+
+    #[test]
+    fn test_mark_undone() {
+        let history = tempfile::tempdir().unwrap();
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        let journal = Journal::new(history.path());
+
+        let result = sample_result(vec![moved_file(src.path(), dst.path(), "a.txt")]);
+        let run_id = journal.record(&result).unwrap().unwrap().run_id;
+
+        journal
+            .mark_undone(&src.path().join("a.txt"), &dst.path().join("a.txt"))
+            .unwrap();
+        assert!(journal.load(&run_id).unwrap().files[0].undone);
+    }
+
+    #[test]
+    fn test_undo_unknown_run() {
+        let history = tempfile::tempdir().unwrap();
+        let journal = Journal::new(history.path());
+        assert!(matches!(
+            journal.undo_run("missing"),
+            Err(JournalError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_run_id_outside_history_dir_is_not_found() {
+        let root = tempfile::tempdir().unwrap();
+        let history = root.path().join("history");
+        fs::create_dir_all(&history).unwrap();
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        let journal = Journal::new(&history);
+        let mut record = journal
+            .record(&sample_result(vec![moved_file(
+                src.path(),
+                dst.path(),
+                "a.txt",
+            )]))
+            .unwrap()
+            .unwrap();
+        record.run_id = "../outside".to_string();
+        fs::write(
+            root.path().join("outside.json"),
+            serde_json::to_vec(&record).unwrap(),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            journal.load("../outside"),
+            Err(JournalError::NotFound(_))
+        ));
+        assert!(matches!(
+            journal.undo_run("../outside"),
+            Err(JournalError::NotFound(_))
+        ));
+        assert!(journal
+            .undo_file("../outside", &src.path().join("a.txt"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_prune_keeps_latest_runs() {
+        let history = tempfile::tempdir().unwrap();
+        let journal = Journal::new(history.path());
+
+        let run_ids: Vec<String> = (0..MAX_RUNS + 3)
+            .map(|_| Uuid::new_v4().to_string())
+            .collect();
+        for (i, run_id) in run_ids.iter().enumerate() {
+            journal
+                .write_run(&sample_record(
+                    run_id,
+                    &format!("00:{:02}:{:02}", i / 60, i % 60),
+                ))
+                .unwrap();
+        }
+        journal.prune().unwrap();
+
+        let runs = journal.list().unwrap();
+        assert_eq!(runs.len(), MAX_RUNS);
+        assert!(runs.iter().all(|r| r.run_id != run_ids[0]));
+        assert!(!journal.run_path(&run_ids[0]).exists());
+    }
+
+    #[test]
+    fn test_prune_deletes_the_file_it_read() {
+        let root = tempfile::tempdir().unwrap();
+        let history = root.path().join("history");
+        let journal = Journal::new(&history);
+        let outside = root.path().join("outside.json");
+        fs::write(&outside, "keep").unwrap();
+
+        // ファイル名と中身の `run_id` が異なる古い記録
+        let stale_id = Uuid::new_v4().to_string();
+        let stale = history.join("stale-copy.json");
+        for i in 0..MAX_RUNS {
+            let record =
+                sample_record(&Uuid::new_v4().to_string(), &format!("01:{:02}:00", i / 60));
+            journal.write_run(&record).unwrap();
+        }
+        fs::write(
+            &stale,
+            serde_json::to_vec(&sample_record(&stale_id, "00:00:00")).unwrap(),
+        )
+        .unwrap();
+        // `run_id` が UUID でない記録は一覧にも削除の対象にも含めない
+        fs::write(
+            history.join("escape.json"),
+            serde_json::to_vec(&sample_record("../outside", "00:00:00")).unwrap(),
+        )
+        .unwrap();
+        journal.prune().unwrap();
+
+        assert!(!stale.exists());
+        assert!(outside.exists());
+        assert!(history.join("escape.json").exists());
+        assert_eq!(journal.list().unwrap().len(), MAX_RUNS);
+    }
+
+    fn sample_record(run_id: &str, time: &str) -> RunRecord {
+        RunRecord {
+            run_id: run_id.to_string(),
+            timestamp: format!("2025-01-01T{}+09:00", time),
+            ruleset_id: "test-id".to_string(),
+            ruleset_name: "test".to_string(),
+            action: Action::Move,
+            files: Vec::new(),
+        }
+    }
+}
@@ -1,23 +1,43 @@
+#[cfg(feature = "gui")]
 pub mod commands;
 pub mod engine;
+pub mod filoignore;
 pub mod filters;
+pub mod journal;
 pub mod ruleset;
+pub mod scheduler;
+pub mod template;
+pub mod units;
+pub mod watcher;
 
+#[cfg(feature = "gui")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .setup(|app| {
+            commands::restart_watcher(app.handle());
+            commands::start_scheduler(app.handle());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             commands::get_rulesets,
             commands::save_ruleset,
+            commands::validate_ruleset,
             commands::delete_ruleset,
             commands::reorder_rulesets,
             commands::execute_ruleset,
             commands::execute_all,
             commands::cancel_execution,
+            commands::preview_ruleset,
+            commands::preview_all,
             commands::undo_file,
             commands::undo_all,
+            commands::list_history,
+            commands::undo_history_run,
+            commands::undo_history_file,
+            commands::take_background_errors,
             commands::import_rulesets,
             commands::export_rulesets,
             commands::open_in_explorer,
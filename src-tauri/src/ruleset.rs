@@ -1,4 +1,6 @@
-use chrono::DateTime;
+use crate::filters::{capture_names, glob_match_pattern};
+use crate::template::{template_static_prefix, Template};
+use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -22,18 +24,279 @@ pub struct FilenameFilter {
     pub match_type: MatchType,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// 日時の条件。絶対日時（RFC3339）の `start`・`end` と、実行時刻を基準にした
+/// `older_than`・`newer_than`（`30d`・`12h` など）を組み合わせられ、指定したものすべてを満たす必要がある。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct DateTimeRange {
     pub start: Option<String>,
     pub end: Option<String>,
+    /// 実行時刻からこの期間以上前のもの
+    #[serde(default)]
+    pub older_than: Option<String>,
+    /// 実行時刻からこの期間以内のもの
+    #[serde(default)]
+    pub newer_than: Option<String>,
 }
 
+/// ファイルサイズの範囲（両端を含む）。`"10MB"`・`"1.5 GiB"` のように単位付きで指定でき、数値のみはバイト数。
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SizeRange {
+    #[serde(default, deserialize_with = "deserialize_size")]
+    pub min: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_size")]
+    pub max: Option<String>,
+}
+
+/// `min: 1048576` のように数値で書かれたサイズも文字列として受け付ける
+fn deserialize_size<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum SizeValue {
+        Text(String),
+        Bytes(u64),
+    }
+    Ok(
+        Option::<SizeValue>::deserialize(deserializer)?.map(|v| match v {
+            SizeValue::Text(s) => s,
+            SizeValue::Bytes(n) => n.to_string(),
+        }),
+    )
+}
+
+/// ファイルの絞り込み条件。指定した条件はすべて満たす必要があり（AND）、
+/// `all`・`any`・`not` で同じ形の条件をネストして AND・OR・否定を組み合わせられる。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct Filters {
     pub extensions: Option<Vec<String>>,
     pub filename: Option<FilenameFilter>,
     pub created_at: Option<DateTimeRange>,
     pub modified_at: Option<DateTimeRange>,
+    /// 最終アクセス日時。OS やボリュームの設定によってはアクセス日時が更新されない点に注意。
+    #[serde(default)]
+    pub accessed_at: Option<DateTimeRange>,
+    #[serde(default)]
+    pub size: Option<SizeRange>,
+    /// ファイル名がいずれかに一致するファイルは、ほかの条件にかかわらず対象外とする
+    #[serde(default)]
+    pub exclude: Option<Vec<FilenameFilter>>,
+    /// すべての条件を満たす
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub all: Option<Vec<Filters>>,
+    /// いずれかの条件を満たす
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub any: Option<Vec<Filters>>,
+    /// 条件を満たさない
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not: Option<Box<Filters>>,
+    /// ファイル名フィルタのキャプチャの値に対する条件。すべて満たす場合に一致する。
+    /// 同じ階層に `filename` がない場合は、外側の階層の `filename` のキャプチャを使う。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub captures: Option<Vec<CaptureCondition>>,
+}
+
+/// キャプチャ（正規表現の名前付きグループ・glob のワイルドカード）の値に対する条件。
+/// 指定した条件をすべて満たす場合に一致し、キャプチャを取り出せないファイルは一致しない。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CaptureCondition {
+    /// キャプチャ名。glob の場合はワイルドカードの番号（`1`・`2`…）も使える
+    pub name: String,
+    /// 値が一致する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equals: Option<String>,
+    /// 値がいずれかに一致する
+    #[serde(default, rename = "in", skip_serializing_if = "Option::is_none")]
+    pub one_of: Option<Vec<String>>,
+    /// 値を数値として解釈し、`min` 以上である
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// 値を数値として解釈し、`max` 以下である
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// 値が正規表現に一致する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matches: Option<String>,
+}
+
+/// 宛先に同名ファイルが存在する場合の処理方法
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    #[default]
+    Skip,
+    Overwrite,
+    /// `rename_pattern` に従って空いている名前で保存する
+    AutoRename,
+    /// ソースの更新日時が新しい場合のみ上書きする
+    KeepNewer,
+    /// ソースのサイズが大きい場合のみ上書きする
+    KeepLarger,
+    /// 内容が同一ならスキップし、異なる場合は `auto_rename` と同様に別名で保存する
+    SkipIfIdentical,
+}
+
+/// 旧形式の `overwrite: true/false` も受け付けるため、bool と文字列の両方から復元する。
+impl<'de> Deserialize<'de> for ConflictPolicy {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct PolicyVisitor;
+
+        impl serde::de::Visitor<'_> for PolicyVisitor {
+            type Value = ConflictPolicy;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a conflict policy name or a boolean")
+            }
+
+            fn visit_bool<E: serde::de::Error>(self, v: bool) -> Result<Self::Value, E> {
+                Ok(if v {
+                    ConflictPolicy::Overwrite
+                } else {
+                    ConflictPolicy::Skip
+                })
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                match v {
+                    "skip" => Ok(ConflictPolicy::Skip),
+                    "overwrite" => Ok(ConflictPolicy::Overwrite),
+                    "auto_rename" => Ok(ConflictPolicy::AutoRename),
+                    "keep_newer" => Ok(ConflictPolicy::KeepNewer),
+                    "keep_larger" => Ok(ConflictPolicy::KeepLarger),
+                    "skip_if_identical" => Ok(ConflictPolicy::SkipIfIdentical),
+                    _ => Err(E::unknown_variant(v, CONFLICT_POLICY_NAMES)),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(PolicyVisitor)
+    }
+}
+
+const CONFLICT_POLICY_NAMES: &[&str] = &[
+    "skip",
+    "overwrite",
+    "auto_rename",
+    "keep_newer",
+    "keep_larger",
+    "skip_if_identical",
+];
+
+/// `auto_rename` のデフォルトの名前パターン（例: `name (1).ext`）
+pub const DEFAULT_RENAME_PATTERN: &str = "{stem} ({n}){dotext}";
+
+/// `rename_pattern` で使える変数
+pub const RENAME_PATTERN_VARS: &[&str] = &["stem", "dotext", "n"];
+
+/// `parallelism` の上限
+pub const MAX_PARALLELISM: u32 = 32;
+
+/// 再帰走査時に宛先でのディレクトリ構成をどうするか
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DestinationLayout {
+    /// すべてのファイルを宛先ディレクトリ直下に配置する
+    #[default]
+    Flatten,
+    /// `source_dir` からの相対ディレクトリ構成を宛先に再現する
+    Preserve,
+}
+
+/// ソースディレクトリの走査オプション。未指定の場合は従来通り直下のファイルのみを対象とする。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ScanOptions {
+    #[serde(default)]
+    pub recursive: bool,
+    /// 走査するサブディレクトリの深さの上限。`None` は無制限、1 は直下のサブディレクトリまで。
+    #[serde(default)]
+    pub max_depth: Option<u32>,
+    /// シンボリックリンク（ジャンクション）先のディレクトリもたどるか
+    #[serde(default)]
+    pub follow_symlinks: bool,
+    #[serde(default)]
+    pub layout: DestinationLayout,
+    /// 処理を始める前にフォルダ全体を辿って対象のファイルを数え、進捗に総数を含める。
+    /// 事前の走査でもメタデータを読んで条件を評価するため、大量のファイルがあるフォルダでは開始が遅れる。
+    /// 未指定の場合は列挙しながらすぐに処理を始め、処理済みの件数だけを通知する。
+    #[serde(default)]
+    pub count_total: bool,
+}
+
+/// `{counter}` を振るときのファイルの並び順
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CounterSort {
+    /// ファイル名順
+    #[default]
+    Name,
+    Modified,
+    Created,
+    Size,
+}
+
+/// テンプレート変数 `{counter}`（`{counter:4}` で 4 桁のゼロ埋め）の採番方法。
+/// 番号は保存先フォルダ（`{counter}` を含むフォルダ名の場合はその親フォルダ）ごとに振り、
+/// 既にあるファイルと衝突する番号は飛ばす。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CounterOptions {
+    #[serde(default)]
+    pub sort: CounterSort,
+    #[serde(default)]
+    pub descending: bool,
+    #[serde(default = "default_counter_start")]
+    pub start: u64,
+    /// 保存先に既にある番号のうち最大のものの次から採番する
+    #[serde(default)]
+    pub resume: bool,
+}
+
+fn default_counter_start() -> u64 {
+    1
+}
+
+impl Default for CounterOptions {
+    fn default() -> Self {
+        Self {
+            sort: CounterSort::default(),
+            descending: false,
+            start: default_counter_start(),
+            resume: false,
+        }
+    }
+}
+
+/// 定期実行の設定。`cron` と `interval` のどちらか一方を指定する。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Schedule {
+    /// cron 式（`分 時 日 月 曜日`、先頭に秒を付けた 6 フィールドも可）。ローカル時刻で評価する。
+    #[serde(default)]
+    pub cron: Option<String>,
+    /// 前回の実行からの間隔（`30m`・`6h`・`7d`・`1w` など）
+    #[serde(default)]
+    pub interval: Option<String>,
+}
+
+impl Schedule {
+    /// `after` より後で次に実行すべき時刻を返す。設定が不正な場合はその理由を返す。
+    pub fn next_after(&self, after: DateTime<Local>) -> Result<DateTime<Local>, String> {
+        match (&self.cron, &self.interval) {
+            (Some(expr), None) => {
+                let cron: croner::Cron = expr
+                    .parse()
+                    .map_err(|e| format!("invalid cron expression '{}': {}", expr, e))?;
+                cron.find_next_occurrence(&after, false)
+                    .map_err(|e| format!("cron expression '{}' never matches: {}", expr, e))
+            }
+            (None, Some(interval)) => after
+                .checked_add_signed(crate::units::parse_duration(interval)?)
+                .ok_or_else(|| format!("interval '{}' is too long", interval)),
+            _ => Err("schedule requires exactly one of cron or interval".to_string()),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -44,8 +307,33 @@ pub struct Ruleset {
     pub source_dir: String,
     pub destination_dir: String,
     pub action: Action,
-    pub overwrite: bool,
+    /// 旧形式の `overwrite: bool` からも読み込める
+    #[serde(default, alias = "overwrite")]
+    pub on_conflict: ConflictPolicy,
+    /// `auto_rename` 時の名前パターン。`{stem}`・`{n}`・`{dotext}`（ドット付きの拡張子。拡張子がなければ空）を使用でき、
+    /// `{n}` は必須。保存先・`rename` の `{ext}` とは異なりドットを含むため、別の名前にしている。
+    /// 未指定の場合は `DEFAULT_RENAME_PATTERN` を使う。
+    #[serde(default)]
+    pub rename_pattern: Option<String>,
+    /// 移動・コピー先でのファイル名のテンプレート（例: `{mtime:%Y-%m-%d}_{stem}.{ext}`）。
+    /// 保存先と同じ変数・変換を使える。未指定の場合は元のファイル名のまま。
+    #[serde(default)]
+    pub rename: Option<String>,
+    #[serde(default)]
+    pub counter: CounterOptions,
     pub filters: Filters,
+    #[serde(default)]
+    pub scan: ScanOptions,
+    /// `true` の場合、アプリ起動中はソースディレクトリを監視し、新しく置かれたファイルに自動で適用する
+    #[serde(default)]
+    pub watch: bool,
+    /// 設定されている場合、アプリ起動中に定期実行する。アプリを閉じていた間に予定があれば起動時に 1 回実行する。
+    #[serde(default)]
+    pub schedule: Option<Schedule>,
+    /// 同時に移動・コピーするファイル数の上限（1〜`MAX_PARALLELISM`）。未指定の場合は 1 件ずつ処理する。
+    /// 宛先の決定と衝突の判定は列挙順に行うため、結果は並列数によらない。
+    #[serde(default)]
+    pub parallelism: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -64,97 +352,551 @@ pub enum RulesetError {
     Validation(String),
 }
 
-/// `destination_dir` にテンプレート変数 `{xxx}` が含まれているか判定する。
-fn has_template_vars(s: &str) -> bool {
-    let mut chars = s.chars().peekable();
-    while let Some(c) = chars.next() {
-        if c == '{' {
-            if chars.peek().is_some() {
-                for inner in chars.by_ref() {
-                    if inner == '}' {
-                        return true;
-                    }
-                }
-            }
-        }
-    }
-    false
-}
-
 impl Filters {
     pub fn has_at_least_one(&self) -> bool {
         self.extensions.as_ref().is_some_and(|e| !e.is_empty())
             || self.filename.is_some()
             || self.created_at.is_some()
             || self.modified_at.is_some()
+            || self.accessed_at.is_some()
+            || self.size.is_some()
+            || self.all.as_ref().is_some_and(|g| !g.is_empty())
+            || self.any.as_ref().is_some_and(|g| !g.is_empty())
+            || self.not.is_some()
+            || self.captures.as_ref().is_some_and(|c| !c.is_empty())
+    }
+}
+
+/// 検証で見つかった問題の重大度。`Error` がある場合は保存・実行できない。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    /// 動作はするが、意図と異なる可能性が高い設定
+    Warning,
+}
+
+/// ルールセットの検証で見つかった問題
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ValidationIssue {
+    /// 問題のある項目のパス（`filters.all[0].filename.pattern` など）
+    pub field: String,
+    pub severity: Severity,
+    /// 問題の種類を表す識別子（`invalid_pattern` など）。UI で文言を切り替えるために使う
+    pub code: String,
+    pub message: String,
+}
+
+/// 検証で見つかった問題を集める
+#[derive(Default)]
+struct Issues(Vec<ValidationIssue>);
+
+impl Issues {
+    fn push(&mut self, severity: Severity, field: &str, code: &str, message: String) {
+        self.0.push(ValidationIssue {
+            field: field.to_string(),
+            severity,
+            code: code.to_string(),
+            message,
+        });
+    }
+
+    fn error(&mut self, field: &str, code: &str, message: impl Into<String>) {
+        self.push(Severity::Error, field, code, message.into());
+    }
+
+    fn warning(&mut self, field: &str, code: &str, message: impl Into<String>) {
+        self.push(Severity::Warning, field, code, message.into());
+    }
+}
+
+fn validate_datetime_range(range: &DateTimeRange, field: &str, issues: &mut Issues) {
+    let parse = |value: &Option<String>, name: &str, issues: &mut Issues| {
+        let value = value.as_ref()?;
+        DateTime::parse_from_rfc3339(value)
+            .map_err(|_| {
+                issues.error(
+                    &format!("{}.{}", field, name),
+                    "invalid_datetime",
+                    format!("invalid datetime format: '{}'", value),
+                )
+            })
+            .ok()
+    };
+    let start = parse(&range.start, "start", issues);
+    let end = parse(&range.end, "end", issues);
+    if let (Some(start), Some(end)) = (start, end) {
+        if start > end {
+            issues.error(
+                field,
+                "range_start_after_end",
+                format!("{}.start must not be later than {}.end", field, field),
+            );
+        }
+    }
+
+    let relative = |value: &Option<String>, name: &str, issues: &mut Issues| {
+        let value = value.as_ref()?;
+        crate::units::parse_duration(value)
+            .map_err(|e| issues.error(&format!("{}.{}", field, name), "invalid_duration", e))
+            .ok()
+    };
+    let older_than = relative(&range.older_than, "older_than", issues);
+    let newer_than = relative(&range.newer_than, "newer_than", issues);
+    if let (Some(older_than), Some(newer_than)) = (older_than, newer_than) {
+        // `older_than: 30d` と `newer_than: 7d` を両方満たすファイルはない
+        if older_than >= newer_than {
+            issues.error(
+                field,
+                "range_start_after_end",
+                format!(
+                    "{}.older_than must be shorter than {}.newer_than",
+                    field, field
+                ),
+            );
+        }
+    }
+}
+
+fn validate_size_range(range: &SizeRange, field: &str, issues: &mut Issues) {
+    let parse = |value: &Option<String>, name: &str, issues: &mut Issues| {
+        let value = value.as_deref()?;
+        crate::units::parse_size(value)
+            .map_err(|e| issues.error(&format!("{}.{}", field, name), "invalid_size", e))
+            .ok()
+    };
+    let min = parse(&range.min, "min", issues);
+    let max = parse(&range.max, "max", issues);
+    if let (Some(min), Some(max)) = (min, max) {
+        if min > max {
+            issues.error(
+                field,
+                "range_min_greater_than_max",
+                "size.min must not be greater than size.max",
+            );
+        }
+    }
+}
+
+/// 拡張子は `.pdf` の形式でのみ一致するため、それ以外の形式を検出する
+fn validate_extensions(extensions: &[String], field: &str, issues: &mut Issues) {
+    for (i, extension) in extensions.iter().enumerate() {
+        let field = format!("{}[{}]", field, i);
+        let body = extension.strip_prefix('.').unwrap_or(extension);
+        if !extension.starts_with('.') || body.is_empty() {
+            issues.error(
+                &field,
+                "invalid_extension",
+                format!(
+                    "extension '{}' must start with a dot (e.g. '.pdf')",
+                    extension
+                ),
+            );
+        } else if body.contains(['.', '/', '\\', '*', '?']) || body.trim() != body {
+            issues.error(
+                &field,
+                "invalid_extension",
+                format!(
+                    "extension '{}' must be a single extension like '.pdf' without wildcards",
+                    extension
+                ),
+            );
+        } else if extensions[..i]
+            .iter()
+            .any(|other| other.eq_ignore_ascii_case(extension))
+        {
+            issues.warning(
+                &field,
+                "duplicate_extension",
+                format!("extension '{}' is listed more than once", extension),
+            );
+        }
+    }
+}
+
+fn validate_pattern(filter: &FilenameFilter, field: &str, issues: &mut Issues) {
+    let result = match filter.match_type {
+        MatchType::Glob => glob::Pattern::new(&glob_match_pattern(&filter.pattern))
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        MatchType::Regex => regex::Regex::new(&filter.pattern)
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+    };
+    if let Err(e) = result {
+        issues.error(
+            &format!("{}.pattern", field),
+            "invalid_pattern",
+            format!("invalid pattern '{}': {}", filter.pattern, e),
+        );
+    }
+}
+
+/// キャプチャの条件を検証する。`filename` は条件が参照するファイル名フィルタ。
+/// ファイル名フィルタのパターン自体の誤りは `validate_pattern` で報告するため、ここでは扱わない。
+fn validate_capture_conditions(
+    conditions: &[CaptureCondition],
+    filename: Option<&FilenameFilter>,
+    field: &str,
+    issues: &mut Issues,
+) {
+    let Some(filename) = filename else {
+        issues.error(
+            field,
+            "capture_requires_filename_filter",
+            "captures requires a filename filter",
+        );
+        return;
+    };
+    if conditions.is_empty() {
+        issues.error(field, "empty_captures", "captures must not be empty");
+        return;
+    }
+    let names = capture_names(filename).ok();
+    for (i, condition) in conditions.iter().enumerate() {
+        let field = format!("{}[{}]", field, i);
+        if names.as_ref().is_some_and(|n| !n.contains(&condition.name)) {
+            issues.error(
+                &format!("{}.name", field),
+                "unknown_capture",
+                format!(
+                    "capture '{}' is not defined in the filename filter",
+                    condition.name
+                ),
+            );
+        }
+        let has_condition = condition.equals.is_some()
+            || condition.one_of.is_some()
+            || condition.min.is_some()
+            || condition.max.is_some()
+            || condition.matches.is_some();
+        if !has_condition {
+            issues.error(
+                &field,
+                "capture_condition_required",
+                format!(
+                    "capture condition for '{}' requires at least one condition",
+                    condition.name
+                ),
+            );
+        }
+        if let (Some(min), Some(max)) = (condition.min, condition.max) {
+            if min > max {
+                issues.error(
+                    &field,
+                    "range_min_greater_than_max",
+                    format!(
+                        "min must not be greater than max in capture condition for '{}'",
+                        condition.name
+                    ),
+                );
+            }
+        }
+        if let Some(pattern) = &condition.matches {
+            if let Err(e) = regex::Regex::new(pattern) {
+                issues.error(
+                    &format!("{}.matches", field),
+                    "invalid_pattern",
+                    format!("invalid pattern '{}': {}", pattern, e),
+                );
+            }
+        }
+    }
+}
+
+/// 拡張子・ファイル名・日時・サイズ・除外パターン・キャプチャの条件の形式と、
+/// `all`・`any`・`not` の各グループを再帰的に検証する。`inherited` は外側の階層のファイル名フィルタ。
+fn validate_filters(
+    filters: &Filters,
+    inherited: Option<&FilenameFilter>,
+    field: &str,
+    issues: &mut Issues,
+) {
+    let filename = filters.filename.as_ref().or(inherited);
+    if let Some(extensions) = &filters.extensions {
+        validate_extensions(extensions, &format!("{}.extensions", field), issues);
+    }
+    if let Some(own) = &filters.filename {
+        validate_pattern(own, &format!("{}.filename", field), issues);
+    }
+    let ranges = [
+        ("created_at", &filters.created_at),
+        ("modified_at", &filters.modified_at),
+        ("accessed_at", &filters.accessed_at),
+    ];
+    for (name, range) in ranges {
+        if let Some(range) = range {
+            validate_datetime_range(range, &format!("{}.{}", field, name), issues);
+        }
+    }
+    if let Some(size) = &filters.size {
+        validate_size_range(size, &format!("{}.size", field), issues);
+    }
+    for (i, exclude) in filters.exclude.iter().flatten().enumerate() {
+        validate_pattern(exclude, &format!("{}.exclude[{}]", field, i), issues);
     }
+    if let Some(conditions) = &filters.captures {
+        let field = format!("{}.captures", field);
+        validate_capture_conditions(conditions, filename, &field, issues);
+    }
+    let groups = [("all", &filters.all), ("any", &filters.any)];
+    for (name, group) in groups {
+        let Some(group) = group else {
+            continue;
+        };
+        let group_field = format!("{}.{}", field, name);
+        if group.is_empty() {
+            issues.error(
+                &group_field,
+                "empty_filter_group",
+                format!("filter group '{}' must not be empty", name),
+            );
+        }
+        for (i, child) in group.iter().enumerate() {
+            let field = format!("{}[{}]", group_field, i);
+            validate_filter_group_entry(name, child, filename, &field, issues);
+        }
+    }
+    if let Some(not) = &filters.not {
+        let field = format!("{}.not", field);
+        validate_filter_group_entry("not", not, filename, &field, issues);
+    }
+}
+
+fn validate_filter_group_entry(
+    name: &str,
+    filters: &Filters,
+    inherited: Option<&FilenameFilter>,
+    field: &str,
+    issues: &mut Issues,
+) {
+    // 空の条件はすべてのファイルにマッチしてしまうため、グループ内では許可しない
+    if !filters.has_at_least_one() {
+        issues.error(
+            field,
+            "filter_required",
+            format!(
+                "each entry in filter group '{}' requires at least one filter",
+                name
+            ),
+        );
+    }
+    validate_filters(filters, inherited, field, issues)
 }
 
-fn validate_datetime_range(range: &DateTimeRange) -> Result<(), RulesetError> {
-    if let Some(start) = &range.start {
-        DateTime::parse_from_rfc3339(start).map_err(|_| {
-            RulesetError::Validation(format!("invalid datetime format: '{}'", start))
-        })?;
+/// `rename_pattern` の構文と変数を検証する。`{n}` は必須で、使える変数は `RENAME_PATTERN_VARS` のみ。
+fn validate_rename_pattern(pattern: &str, issues: &mut Issues) {
+    const FIELD: &str = "rename_pattern";
+    let template = match Template::parse(pattern) {
+        Ok(template) => template,
+        Err(e) => {
+            issues.error(
+                FIELD,
+                "invalid_template",
+                format!("invalid rename_pattern: {}", e),
+            );
+            return;
+        }
+    };
+    for var in template
+        .vars()
+        .filter(|var| var.format.is_some() || !RENAME_PATTERN_VARS.contains(&var.name.as_str()))
+    {
+        // 保存先と同じ感覚で `{ext}` と書いた場合は、ドット付きの `{dotext}` を案内する
+        let message = if var.name == "ext" {
+            "rename_pattern uses '{ext}'; use '{dotext}' for the extension with its dot".to_string()
+        } else {
+            format!(
+                "rename_pattern uses unknown template variable '{{{}}}'",
+                var.expr
+            )
+        };
+        issues.error(FIELD, "unknown_template_variable", message);
     }
-    if let Some(end) = &range.end {
-        DateTime::parse_from_rfc3339(end)
-            .map_err(|_| RulesetError::Validation(format!("invalid datetime format: '{}'", end)))?;
+    if !template.vars().any(|var| var.name == "n") {
+        issues.error(
+            FIELD,
+            "rename_pattern_missing_n",
+            "rename_pattern must contain {n}",
+        );
+    }
+    if pattern.contains(['/', '\\']) {
+        issues.error(
+            FIELD,
+            "path_separator",
+            "rename_pattern must not contain path separators",
+        );
     }
-    Ok(())
 }
 
 impl Ruleset {
+    /// 検証して、最初のエラーではなく見つかったすべてのエラーをまとめて返す。警告は含めない。
     pub fn validate(&self) -> Result<(), RulesetError> {
-        if self.name.trim().is_empty() {
-            return Err(RulesetError::Validation("name is required".into()));
-        }
-        if self.source_dir.trim().is_empty() {
-            return Err(RulesetError::Validation("source_dir is required".into()));
+        let errors: Vec<_> = self
+            .validation_issues()
+            .into_iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .map(|issue| issue.message)
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(RulesetError::Validation(errors.join("; ")))
         }
-        if self.destination_dir.trim().is_empty() {
-            return Err(RulesetError::Validation(
-                "destination_dir is required".into(),
-            ));
+    }
+
+    /// 設定の問題を項目ごとにすべて返す。パターンのコンパイル・日時の範囲・拡張子の形式・
+    /// テンプレート変数などを検証し、エラーと警告を区別する。
+    pub fn validation_issues(&self) -> Vec<ValidationIssue> {
+        let mut issues = Issues::default();
+        for (field, value) in [
+            ("name", &self.name),
+            ("source_dir", &self.source_dir),
+            ("destination_dir", &self.destination_dir),
+        ] {
+            if value.trim().is_empty() {
+                issues.error(field, "required", format!("{} is required", field));
+            }
         }
         if !self.filters.has_at_least_one() {
-            return Err(RulesetError::Validation(
-                "at least one filter is required".into(),
-            ));
+            issues.error(
+                "filters",
+                "filter_required",
+                "at least one filter is required",
+            );
         }
-        // テンプレート変数がある場合は正規表現フィルタが必須
-        if has_template_vars(&self.destination_dir) {
-            let is_regex = self
-                .filters
-                .filename
-                .as_ref()
-                .map(|f| f.match_type == MatchType::Regex)
-                .unwrap_or(false);
-            if !is_regex {
-                return Err(RulesetError::Validation(
-                    "destination_dir contains template variables but filename filter is not regex"
-                        .into(),
-                ));
+        self.validate_template("destination_dir", &self.destination_dir, &mut issues);
+        // 変数より前にフォルダがないと、保存先が作業ディレクトリからの相対パスになる
+        if Template::parse(&self.destination_dir).is_ok_and(|t| t.has_vars())
+            && template_static_prefix(&self.destination_dir).is_empty()
+        {
+            issues.error(
+                "destination_dir",
+                "template_root_required",
+                "destination_dir must start with a folder before the first template variable",
+            );
+        }
+        if let Some(rename) = &self.rename {
+            if rename.trim().is_empty() {
+                issues.error("rename", "required", "rename must not be empty");
+            } else if rename.contains(['/', '\\']) {
+                issues.error(
+                    "rename",
+                    "path_separator",
+                    "rename must not contain path separators",
+                );
+            } else {
+                self.validate_template("rename", rename, &mut issues);
             }
         }
-        // datetime フィルタの形式を検証
-        if let Some(created_at) = &self.filters.created_at {
-            validate_datetime_range(created_at)?;
+        if let Some(pattern) = &self.rename_pattern {
+            validate_rename_pattern(pattern, &mut issues);
         }
-        if let Some(modified_at) = &self.filters.modified_at {
-            validate_datetime_range(modified_at)?;
+        if self.scan.max_depth == Some(0) {
+            issues.error(
+                "scan.max_depth",
+                "invalid_max_depth",
+                "scan.max_depth must be at least 1",
+            );
+        }
+        if let Some(schedule) = &self.schedule {
+            if let Err(e) = schedule.next_after(Local::now()) {
+                issues.error("schedule", "invalid_schedule", e);
+            }
+        }
+        if self
+            .parallelism
+            .is_some_and(|n| !(1..=MAX_PARALLELISM).contains(&n))
+        {
+            issues.error(
+                "parallelism",
+                "invalid_parallelism",
+                format!("parallelism must be between 1 and {}", MAX_PARALLELISM),
+            );
+        }
+        validate_filters(&self.filters, None, "filters", &mut issues);
+        issues.0
+    }
+
+    /// テンプレートの構文・変換・日時の書式を検証する。
+    /// 組み込み変数以外のテンプレート変数がある場合はファイル名フィルタが必須で、
+    /// 変数は正規表現の名前付きグループか glob のワイルドカード（`{1}`・`{label:*}` の `label`）を指している必要がある。
+    fn validate_template(&self, field: &str, template: &str, issues: &mut Issues) {
+        let template = match Template::parse(template) {
+            Ok(template) => template,
+            Err(e) => {
+                issues.error(
+                    field,
+                    "invalid_template",
+                    format!("invalid {}: {}", field, e),
+                );
+                return;
+            }
+        };
+        let mut capture_vars = template.vars().filter(|var| !var.is_builtin()).peekable();
+        if capture_vars.peek().is_none() {
+            return;
+        }
+        let Some(filename) = &self.filters.filename else {
+            issues.error(
+                field,
+                "template_requires_filename_filter",
+                format!(
+                    "{} contains template variables but filename filter is not set",
+                    field
+                ),
+            );
+            return;
+        };
+        // パターン自体の誤りは `filters.filename.pattern` で報告する
+        let Ok(names) = capture_names(filename) else {
+            return;
+        };
+        for var in capture_vars.filter(|var| !names.contains(&var.name)) {
+            issues.error(
+                field,
+                "unknown_template_variable",
+                format!(
+                    "{} uses unknown template variable '{{{}}}' (not a built-in variable or a capture of the filename filter)",
+                    field, var.name
+                ),
+            );
         }
-        Ok(())
     }
 
     pub fn source_path(&self) -> PathBuf {
         PathBuf::from(&self.source_dir)
     }
 
+    /// 保存先のパス。変数を含まない場合は `{{`・`}}` を `{`・`}` に戻す
     pub fn destination_path(&self) -> PathBuf {
-        PathBuf::from(&self.destination_dir)
+        match Template::parse(&self.destination_dir).map(|t| t.as_literal()) {
+            Ok(Some(literal)) => PathBuf::from(literal),
+            _ => PathBuf::from(&self.destination_dir),
+        }
+    }
+
+    /// 同時に移動・コピーするファイル数
+    pub fn parallelism(&self) -> usize {
+        self.parallelism.unwrap_or(1).clamp(1, MAX_PARALLELISM) as usize
+    }
+
+    pub fn rename_pattern(&self) -> &str {
+        self.rename_pattern
+            .as_deref()
+            .unwrap_or(DEFAULT_RENAME_PATTERN)
     }
 }
 
+/// GUI と CLI が共通で使うルールファイルの保存先
+pub fn default_rulesets_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("filo")
+        .join("rulesets")
+        .join("filo-rules.yaml")
+}
+
 impl RulesetFile {
     pub fn from_yaml(yaml: &str) -> Result<Self, RulesetError> {
         let file: RulesetFile = serde_yaml::from_str(yaml)?;
@@ -193,7 +935,10 @@ mod tests {
             source_dir: "C:/Users/user/Downloads".to_string(),
             destination_dir: "C:/Users/user/Pictures/sorted".to_string(),
             action: Action::Move,
-            overwrite: false,
+            on_conflict: ConflictPolicy::Skip,
+            rename_pattern: None,
+            rename: None,
+            counter: CounterOptions::default(),
             filters: Filters {
                 extensions: Some(vec![".jpg".to_string(), ".png".to_string()]),
                 filename: Some(FilenameFilter {
@@ -202,7 +947,18 @@ mod tests {
                 }),
                 created_at: None,
                 modified_at: None,
+                accessed_at: None,
+                size: None,
+                exclude: None,
+                all: None,
+                any: None,
+                not: None,
+                captures: None,
             },
+            scan: ScanOptions::default(),
+            watch: false,
+            schedule: None,
+            parallelism: None,
         }
     }
 
@@ -250,7 +1006,7 @@ rulesets:
         let rs = &file.rulesets[0];
         assert_eq!(rs.name, "画像ファイルを整理");
         assert_eq!(rs.action, Action::Move);
-        assert!(!rs.overwrite);
+        assert_eq!(rs.on_conflict, ConflictPolicy::Skip);
         assert_eq!(
             rs.filters.extensions,
             Some(vec![
@@ -282,7 +1038,127 @@ rulesets:
 "#;
         let file = RulesetFile::from_yaml(yaml).unwrap();
         assert_eq!(file.rulesets[0].action, Action::Copy);
-        assert!(file.rulesets[0].overwrite);
+        assert_eq!(file.rulesets[0].on_conflict, ConflictPolicy::Overwrite);
+    }
+
+    #[test]
+    fn test_deserialize_conflict_policy() {
+        let yaml = r#"
+version: 1
+rulesets:
+  - id: "test-id"
+    name: "rename test"
+    enabled: true
+    source_dir: "/src"
+    destination_dir: "/dst"
+    action: move
+    on_conflict: auto_rename
+    rename_pattern: "{stem}_{n}{dotext}"
+    filters:
+      extensions:
+        - ".log"
+"#;
+        let file = RulesetFile::from_yaml(yaml).unwrap();
+        let rs = &file.rulesets[0];
+        assert_eq!(rs.on_conflict, ConflictPolicy::AutoRename);
+        assert_eq!(rs.rename_pattern(), "{stem}_{n}{dotext}");
+
+        // 保存時は新形式で書き出され、再読み込みしても同じ値になる
+        let yaml = file.to_yaml().unwrap();
+        assert!(yaml.contains("on_conflict: auto_rename"));
+        assert!(!yaml.contains("overwrite"));
+        assert_eq!(RulesetFile::from_yaml(&yaml).unwrap(), file);
+    }
+
+    #[test]
+    fn test_deserialize_without_conflict_policy_defaults_to_skip() {
+        let yaml = r#"
+version: 1
+rulesets:
+  - id: "test-id"
+    name: "no policy"
+    enabled: true
+    source_dir: "/src"
+    destination_dir: "/dst"
+    action: move
+    filters:
+      extensions:
+        - ".log"
+"#;
+        let file = RulesetFile::from_yaml(yaml).unwrap();
+        assert_eq!(file.rulesets[0].on_conflict, ConflictPolicy::Skip);
+        assert_eq!(file.rulesets[0].rename_pattern(), DEFAULT_RENAME_PATTERN);
+    }
+
+    #[test]
+    fn test_deserialize_unknown_conflict_policy_fails() {
+        let yaml = r#"
+version: 1
+rulesets:
+  - id: "test-id"
+    name: "bad policy"
+    enabled: true
+    source_dir: "/src"
+    destination_dir: "/dst"
+    action: move
+    on_conflict: replace_all
+    filters:
+      extensions:
+        - ".log"
+"#;
+        assert!(RulesetFile::from_yaml(yaml).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_without_scan_defaults_to_non_recursive() {
+        let yaml = r#"
+version: 1
+rulesets:
+  - id: "test-id"
+    name: "legacy"
+    enabled: true
+    source_dir: "/src"
+    destination_dir: "/dst"
+    action: move
+    overwrite: false
+    filters:
+      extensions:
+        - ".log"
+"#;
+        let file = RulesetFile::from_yaml(yaml).unwrap();
+        assert_eq!(file.rulesets[0].scan, ScanOptions::default());
+        assert!(!file.rulesets[0].scan.recursive);
+        assert!(!file.rulesets[0].watch);
+    }
+
+    #[test]
+    fn test_deserialize_scan_options() {
+        let yaml = r#"
+version: 1
+rulesets:
+  - id: "test-id"
+    name: "recursive"
+    enabled: true
+    source_dir: "/src"
+    destination_dir: "/dst"
+    action: move
+    overwrite: false
+    filters:
+      extensions:
+        - ".log"
+    scan:
+      recursive: true
+      max_depth: 2
+      layout: preserve
+    watch: true
+"#;
+        let file = RulesetFile::from_yaml(yaml).unwrap();
+        assert!(file.rulesets[0].watch);
+        let scan = &file.rulesets[0].scan;
+        assert!(scan.recursive);
+        assert_eq!(scan.max_depth, Some(2));
+        assert!(!scan.follow_symlinks);
+        assert_eq!(scan.layout, DestinationLayout::Preserve);
     }
 
     #[test]
@@ -320,6 +1196,13 @@ rulesets:
             filename: None,
             created_at: None,
             modified_at: None,
+            accessed_at: None,
+            size: None,
+            exclude: None,
+            all: None,
+            any: None,
+            not: None,
+            captures: None,
         };
         assert!(rs.validate().is_err());
     }
@@ -332,23 +1215,514 @@ rulesets:
             filename: None,
             created_at: None,
             modified_at: None,
+            accessed_at: None,
+            size: None,
+            exclude: None,
+            all: None,
+            any: None,
+            not: None,
+            captures: None,
         };
         assert!(rs.validate().is_err());
     }
 
     #[test]
-    fn test_file_save_and_load() {
-        let dir = tempfile::tempdir().unwrap();
-        let path = dir.path().join("test-rules.yaml");
+    fn test_validate_rename_pattern() {
+        let mut rs = sample_ruleset();
+        rs.on_conflict = ConflictPolicy::AutoRename;
+        rs.rename_pattern = Some("{stem}-{n}{dotext}".to_string());
+        assert!(rs.validate().is_ok());
 
-        let file = RulesetFile {
-            version: 1,
-            rulesets: vec![sample_ruleset()],
-        };
+        rs.rename_pattern = Some("{stem}-copy{dotext}".to_string());
+        assert!(rs.validate().is_err());
 
-        file.save(&path).unwrap();
-        let loaded = RulesetFile::load(&path).unwrap();
-        assert_eq!(file, loaded);
+        rs.rename_pattern = Some("dup/{stem}-{n}{dotext}".to_string());
+        assert!(rs.validate().is_err());
+
+        rs.rename_pattern = Some("{stem}-{n|pad:3}{dotext}".to_string());
+        assert!(rs.validate().is_ok());
+        rs.rename_pattern = Some("{{{stem}}}-{n}{dotext}".to_string());
+        assert!(rs.validate().is_ok());
+        rs.rename_pattern = Some("{stem}-{num}{dotext}".to_string());
+        assert!(rs.validate().is_err());
+        rs.rename_pattern = Some("{stem}-{n}{dotext".to_string());
+        assert!(rs.validate().is_err());
+        // `{ext}` は保存先と同じくドットなしを表すため、`rename_pattern` では使えない
+        rs.rename_pattern = Some("{stem}-{n}{ext}".to_string());
+        assert!(rs.validate().unwrap_err().to_string().contains("{dotext}"));
+    }
+
+    #[test]
+    fn test_validate_zero_max_depth() {
+        let mut rs = sample_ruleset();
+        rs.scan.recursive = true;
+        rs.scan.max_depth = Some(0);
+        assert!(rs.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_parallelism() {
+        let mut rs = sample_ruleset();
+        assert_eq!(rs.parallelism(), 1);
+        rs.parallelism = Some(4);
+        assert!(rs.validate().is_ok());
+        assert_eq!(rs.parallelism(), 4);
+
+        for invalid in [0, MAX_PARALLELISM + 1] {
+            rs.parallelism = Some(invalid);
+            let issues = rs.validation_issues();
+            assert_eq!(issues.len(), 1);
+            assert_eq!(issues[0].field, "parallelism");
+            assert_eq!(issues[0].code, "invalid_parallelism");
+        }
+    }
+
+    #[test]
+    fn test_deserialize_schedule() {
+        let yaml = r#"
+version: 1
+rulesets:
+  - id: "test-id"
+    name: "weekly"
+    enabled: true
+    source_dir: "/src"
+    destination_dir: "/dst"
+    action: move
+    filters:
+      extensions:
+        - ".log"
+    schedule:
+      cron: "0 3 * * 1"
+"#;
+        let file = RulesetFile::from_yaml(yaml).unwrap();
+        let schedule = file.rulesets[0].schedule.as_ref().unwrap();
+        assert_eq!(schedule.cron.as_deref(), Some("0 3 * * 1"));
+        assert_eq!(schedule.interval, None);
+    }
+
+    #[test]
+    fn test_schedule_next_after() {
+        use chrono::TimeZone;
+        // 2025-01-01 は水曜日
+        let after = Local.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+
+        let weekly = Schedule {
+            cron: Some("0 3 * * 1".to_string()),
+            interval: None,
+        };
+        assert_eq!(
+            weekly.next_after(after).unwrap(),
+            Local.with_ymd_and_hms(2025, 1, 6, 3, 0, 0).unwrap()
+        );
+
+        let interval = Schedule {
+            cron: None,
+            interval: Some("6h".to_string()),
+        };
+        assert_eq!(
+            interval.next_after(after).unwrap(),
+            Local.with_ymd_and_hms(2025, 1, 1, 18, 0, 0).unwrap()
+        );
+
+        // 日時の範囲を超える場合はパニックせずに `Err`
+        let near_max = Local.with_ymd_and_hms(262_142, 1, 1, 0, 0, 0).unwrap();
+        let yearly = Schedule {
+            cron: None,
+            interval: Some("53w".to_string()),
+        };
+        assert!(yearly.next_after(near_max).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_size_filter() {
+        let yaml = r#"
+version: 1
+rulesets:
+  - id: "test-id"
+    name: "videos"
+    enabled: true
+    source_dir: "/src"
+    destination_dir: "/dst"
+    action: move
+    filters:
+      size:
+        min: "1.5 GiB"
+        max: 10737418240
+"#;
+        let file = RulesetFile::from_yaml(yaml).unwrap();
+        let rs = &file.rulesets[0];
+        let size = rs.filters.size.as_ref().unwrap();
+        assert_eq!(size.min.as_deref(), Some("1.5 GiB"));
+        assert_eq!(size.max.as_deref(), Some("10737418240"));
+        // サイズ条件だけでもフィルタとして有効
+        assert!(rs.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_size_filter() {
+        let mut rs = sample_ruleset();
+        rs.filters.size = Some(SizeRange {
+            min: Some("10 XB".to_string()),
+            max: None,
+        });
+        assert!(rs.validate().is_err());
+
+        rs.filters.size = Some(SizeRange {
+            min: Some("2MB".to_string()),
+            max: Some("1MB".to_string()),
+        });
+        assert!(rs.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_relative_datetime() {
+        let mut rs = sample_ruleset();
+        rs.filters.accessed_at = Some(DateTimeRange {
+            older_than: Some("30d".to_string()),
+            ..Default::default()
+        });
+        assert!(rs.validate().is_ok());
+
+        rs.filters.modified_at = Some(DateTimeRange {
+            newer_than: Some("12 hours".to_string()),
+            ..Default::default()
+        });
+        assert!(rs.validate().is_err());
+
+        // 現在時刻から引くと日時の範囲を超える長さ
+        rs.filters.modified_at = Some(DateTimeRange {
+            older_than: Some("20000000w".to_string()),
+            ..Default::default()
+        });
+        let issues = rs.validation_issues();
+        assert_eq!(issues[0].field, "filters.modified_at.older_than");
+        assert_eq!(issues[0].code, "invalid_duration");
+    }
+
+    #[test]
+    fn test_validate_datetime_range_order() {
+        let mut rs = sample_ruleset();
+        rs.filters.modified_at = Some(DateTimeRange {
+            start: Some("2025-02-01T00:00:00+09:00".to_string()),
+            end: Some("2025-01-01T00:00:00+09:00".to_string()),
+            ..Default::default()
+        });
+        assert!(rs.validate().is_err());
+
+        rs.filters.modified_at = Some(DateTimeRange {
+            older_than: Some("7d".to_string()),
+            newer_than: Some("30d".to_string()),
+            ..Default::default()
+        });
+        assert!(rs.validate().is_ok());
+        rs.filters.modified_at = Some(DateTimeRange {
+            older_than: Some("30d".to_string()),
+            newer_than: Some("7d".to_string()),
+            ..Default::default()
+        });
+        let issues = rs.validation_issues();
+        assert_eq!(issues[0].field, "filters.modified_at");
+        assert_eq!(issues[0].code, "range_start_after_end");
+    }
+
+    #[test]
+    fn test_validate_extensions_format() {
+        let mut rs = sample_ruleset();
+        rs.filters.extensions = Some(vec!["pdf".into(), ".tar.gz".into(), ".*".into()]);
+        let fields: Vec<_> = rs
+            .validation_issues()
+            .into_iter()
+            .map(|issue| (issue.field, issue.code))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("filters.extensions[0]".into(), "invalid_extension".into()),
+                ("filters.extensions[1]".into(), "invalid_extension".into()),
+                ("filters.extensions[2]".into(), "invalid_extension".into()),
+            ]
+        );
+
+        // 重複は警告のみで、保存はできる
+        rs.filters.extensions = Some(vec![".pdf".into(), ".PDF".into()]);
+        let issues = rs.validation_issues();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, Severity::Warning);
+        assert_eq!(issues[0].code, "duplicate_extension");
+        assert!(rs.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validation_issues_reports_every_problem() {
+        let mut rs = sample_ruleset();
+        rs.name = " ".into();
+        rs.destination_dir = "D:/sorted/{label".into();
+        rs.filters.filename = Some(FilenameFilter {
+            pattern: "^(?P<label>[a-z+".to_string(),
+            match_type: MatchType::Regex,
+        });
+        rs.filters.all = Some(vec![Filters {
+            exclude: Some(vec![FilenameFilter {
+                pattern: "[abc".to_string(),
+                match_type: MatchType::Glob,
+            }]),
+            ..Default::default()
+        }]);
+        rs.scan.max_depth = Some(0);
+
+        let issues = rs.validation_issues();
+        let fields: Vec<_> = issues.iter().map(|issue| issue.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "name",
+                "destination_dir",
+                "scan.max_depth",
+                "filters.filename.pattern",
+                "filters.all[0]",
+                "filters.all[0].exclude[0].pattern",
+            ]
+        );
+        assert!(issues.iter().all(|issue| issue.severity == Severity::Error));
+        assert_eq!(issues[3].code, "invalid_pattern");
+
+        // すべてのエラーを 1 つのメッセージにまとめる
+        let message = rs.validate().unwrap_err().to_string();
+        assert!(message.contains("name is required"));
+        assert!(message.contains("invalid pattern '[abc'"));
+    }
+
+    #[test]
+    fn test_validate_invalid_filename_pattern() {
+        let mut rs = sample_ruleset();
+        rs.filters.filename = Some(FilenameFilter {
+            pattern: "(unclosed".to_string(),
+            match_type: MatchType::Regex,
+        });
+        assert!(rs.validate().is_err());
+
+        rs.filters.filename = Some(FilenameFilter {
+            pattern: "report_[0-9.pdf".to_string(),
+            match_type: MatchType::Glob,
+        });
+        assert!(rs.validate().is_err());
+    }
+
+    #[test]
+    fn test_deserialize_filter_groups() {
+        let yaml = r#"
+id: "group-test"
+name: "Group Test"
+enabled: true
+source_dir: "/src"
+destination_dir: "/dst"
+action: move
+filters:
+  any:
+    - extensions: [".jpg", ".png"]
+      not:
+        filename:
+          pattern: "screenshot_*"
+          match_type: glob
+    - size:
+        min: 1GB
+"#;
+        let rs: Ruleset = serde_yaml::from_str(yaml).unwrap();
+        let any = rs.filters.any.as_ref().unwrap();
+        assert_eq!(any.len(), 2);
+        assert_eq!(
+            any[0]
+                .not
+                .as_ref()
+                .unwrap()
+                .filename
+                .as_ref()
+                .unwrap()
+                .pattern,
+            "screenshot_*"
+        );
+        assert_eq!(any[1].size.as_ref().unwrap().min.as_deref(), Some("1GB"));
+        assert!(rs.filters.has_at_least_one());
+        assert!(rs.validate().is_ok());
+
+        // グループを使わないルールセットの書き出しには group のキーを含めない
+        let flat = serde_yaml::to_string(&sample_ruleset()).unwrap();
+        assert!(!flat.contains("any:"));
+    }
+
+    #[test]
+    fn test_deserialize_and_validate_exclude() {
+        let yaml = r#"
+id: "exclude-test"
+name: "Exclude Test"
+enabled: true
+source_dir: "/src"
+destination_dir: "/dst"
+action: move
+filters:
+  extensions: [".pdf"]
+  exclude:
+    - pattern: "README*"
+      match_type: glob
+    - pattern: '\.part$'
+      match_type: regex
+"#;
+        let mut rs: Ruleset = serde_yaml::from_str(yaml).unwrap();
+        let exclude = rs.filters.exclude.as_ref().unwrap();
+        assert_eq!(exclude.len(), 2);
+        assert_eq!(exclude[1].match_type, MatchType::Regex);
+        assert!(rs.validate().is_ok());
+
+        rs.filters.exclude = Some(vec![FilenameFilter {
+            pattern: "(unclosed".to_string(),
+            match_type: MatchType::Regex,
+        }]);
+        assert!(rs.validate().is_err());
+    }
+
+    #[test]
+    fn test_deserialize_and_validate_capture_conditions() {
+        let yaml = r#"
+version: 1
+rulesets:
+  - id: "test-id"
+    name: "archive"
+    enabled: true
+    source_dir: "/src"
+    destination_dir: "/dst/{year}"
+    action: move
+    filters:
+      filename:
+        pattern: '^(?P<year>\d{4})_(?P<client>[a-z]+)'
+        match_type: regex
+      captures:
+        - name: year
+          max: 2023
+        - name: client
+          in: [acme, globex]
+"#;
+        let file = RulesetFile::from_yaml(yaml).unwrap();
+        let rs = &file.rulesets[0];
+        let captures = rs.filters.captures.as_ref().unwrap();
+        assert_eq!(captures[0].max, Some(2023.0));
+        assert_eq!(
+            captures[1].one_of,
+            Some(vec!["acme".to_string(), "globex".to_string()])
+        );
+        assert!(rs.validate().is_ok());
+
+        let mut invalid = rs.clone();
+        invalid.filters.captures.as_mut().unwrap()[0].name = "month".into();
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = rs.clone();
+        invalid.filters.captures.as_mut().unwrap()[0].max = None;
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = rs.clone();
+        invalid.filters.captures.as_mut().unwrap()[0].min = Some(2030.0);
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = rs.clone();
+        invalid.filters.captures.as_mut().unwrap()[1].matches = Some("(".into());
+        assert!(invalid.validate().is_err());
+
+        // ファイル名フィルタのない階層では使えない
+        let mut invalid = rs.clone();
+        invalid.destination_dir = "/dst".into();
+        invalid.filters.filename = None;
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_filter_groups() {
+        let mut rs = sample_ruleset();
+        rs.filters = Filters {
+            any: Some(vec![]),
+            ..Default::default()
+        };
+        assert!(rs.validate().is_err());
+
+        // 空の条件を含むグループは不可
+        rs.filters = Filters {
+            any: Some(vec![
+                Filters {
+                    extensions: Some(vec![".jpg".to_string()]),
+                    ..Default::default()
+                },
+                Filters::default(),
+            ]),
+            ..Default::default()
+        };
+        assert!(rs.validate().is_err());
+
+        // ネストしたグループ内の値も検証する
+        rs.filters = Filters {
+            not: Some(Box::new(Filters {
+                all: Some(vec![Filters {
+                    size: Some(SizeRange {
+                        min: Some("10 XB".to_string()),
+                        max: None,
+                    }),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        assert!(rs.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_schedule() {
+        let mut rs = sample_ruleset();
+        rs.schedule = Some(Schedule {
+            cron: Some("*/15 * * * *".to_string()),
+            interval: None,
+        });
+        assert!(rs.validate().is_ok());
+
+        rs.schedule = Some(Schedule {
+            cron: Some("not a cron".to_string()),
+            interval: None,
+        });
+        assert!(rs.validate().is_err());
+
+        rs.schedule = Some(Schedule {
+            cron: None,
+            interval: Some("7x".to_string()),
+        });
+        assert!(rs.validate().is_err());
+
+        rs.schedule = Some(Schedule {
+            cron: None,
+            interval: Some("20000000w".to_string()),
+        });
+        assert!(rs.validate().is_err());
+
+        // どちらも指定・どちらも未指定はエラー
+        rs.schedule = Some(Schedule {
+            cron: Some("0 3 * * 1".to_string()),
+            interval: Some("7d".to_string()),
+        });
+        assert!(rs.validate().is_err());
+        rs.schedule = Some(Schedule::default());
+        assert!(rs.validate().is_err());
+    }
+
+    #[test]
+    fn test_file_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test-rules.yaml");
+
+        let file = RulesetFile {
+            version: 1,
+            rulesets: vec![sample_ruleset()],
+        };
+
+        file.save(&path).unwrap();
+        let loaded = RulesetFile::load(&path).unwrap();
+        assert_eq!(file, loaded);
     }
 
     #[test]
@@ -358,6 +1732,13 @@ rulesets:
             filename: None,
             created_at: None,
             modified_at: None,
+            accessed_at: None,
+            size: None,
+            exclude: None,
+            all: None,
+            any: None,
+            not: None,
+            captures: None,
         };
         assert!(!empty.has_at_least_one());
 
@@ -381,6 +1762,48 @@ rulesets:
         assert!(rs.validate().is_ok());
     }
 
+    #[test]
+    fn test_validate_template_unknown_regex_group_fails() {
+        let mut rs = sample_ruleset();
+        rs.destination_dir = "D:/sorted/{lable}".to_string();
+        rs.filters.filename = Some(FilenameFilter {
+            pattern: r"^(?P<label>\d+)_.+".to_string(),
+            match_type: MatchType::Regex,
+        });
+        let err = rs.validate().unwrap_err().to_string();
+        assert!(err.contains("{lable}"));
+
+        rs.destination_dir = "D:/sorted/{label}".to_string();
+        rs.rename = Some("{lable}.{ext}".to_string());
+        assert!(rs.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_template_escaped_braces() {
+        let mut rs = sample_ruleset();
+        rs.destination_dir = "D:/sorted/{{archive}}".to_string();
+        assert!(rs.validate().is_ok());
+        assert_eq!(rs.destination_path(), PathBuf::from("D:/sorted/{archive}"));
+
+        rs.destination_dir = "D:/sorted/{archive".to_string();
+        assert!(rs.validate().is_err());
+        rs.destination_dir = "D:/sorted/archive}".to_string();
+        assert!(rs.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_template_requires_leading_folder() {
+        let mut rs = sample_ruleset();
+        rs.destination_dir = "{ruleset}/sorted".to_string();
+        let issues = rs.validation_issues();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].field, "destination_dir");
+        assert_eq!(issues[0].code, "template_root_required");
+
+        rs.destination_dir = "D:/sorted/{ruleset}".to_string();
+        assert!(rs.validate().is_ok());
+    }
+
     #[test]
     fn test_validate_template_with_glob_fails() {
         let mut rs = sample_ruleset();
@@ -390,6 +1813,28 @@ rulesets:
             match_type: MatchType::Glob,
         });
         assert!(rs.validate().is_err());
+
+        // ワイルドカードの数を超える番号も不可
+        rs.destination_dir = "D:/sorted/{2}".to_string();
+        assert!(rs.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_template_with_glob_captures_ok() {
+        let mut rs = sample_ruleset();
+        rs.destination_dir = "D:/sorted/{label}/{2}".to_string();
+        rs.rename = Some("{1}.{ext}".to_string());
+        rs.filters.filename = Some(FilenameFilter {
+            pattern: "{label:*}_report_*.pdf".to_string(),
+            match_type: MatchType::Glob,
+        });
+        assert!(rs.validate().is_ok());
+
+        rs.filters.filename = Some(FilenameFilter {
+            pattern: "{label:[abc}_*.pdf".to_string(),
+            match_type: MatchType::Glob,
+        });
+        assert!(rs.validate().is_err());
     }
 
     #[test]
@@ -401,10 +1846,118 @@ rulesets:
             filename: None,
             created_at: None,
             modified_at: None,
+            accessed_at: None,
+            size: None,
+            exclude: None,
+            all: None,
+            any: None,
+            not: None,
+            captures: None,
         };
         assert!(rs.validate().is_err());
     }
 
+    #[test]
+    fn test_validate_date_template_without_regex_ok() {
+        let mut rs = sample_ruleset();
+        rs.destination_dir = "D:/photos/{mtime:%Y}/{mtime:%m}/{now}".to_string();
+        assert!(rs.validate().is_ok());
+
+        rs.filters.filename = None;
+        rs.destination_dir = "D:/photos/{ctime:%Y-%m-%d}".to_string();
+        assert!(rs.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_file_template_vars_without_regex_ok() {
+        let mut rs = sample_ruleset();
+        rs.destination_dir = "D:/sorted/{ext}/{ruleset}/{source_dir_name}/{parent}/{stem}".into();
+        assert!(rs.validate().is_ok());
+
+        // 組み込み変数に書式は指定できない
+        rs.destination_dir = "D:/sorted/{ext:%Y}".into();
+        assert!(rs.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_rename_template() {
+        let mut rs = sample_ruleset();
+        rs.rename = Some("{mtime:%Y-%m-%d}_{stem}.{ext}".into());
+        assert!(rs.validate().is_ok());
+
+        rs.rename = Some("".into());
+        assert!(rs.validate().is_err());
+
+        rs.rename = Some("{mtime:%Y}/{stem}.{ext}".into());
+        assert!(rs.validate().is_err());
+
+        // キャプチャを使う場合は正規表現フィルタが必要
+        rs.rename = Some("{label}.{ext}".into());
+        assert!(rs.validate().is_err());
+    }
+
+    #[test]
+    fn test_deserialize_and_validate_counter() {
+        let yaml = r#"
+version: 1
+rulesets:
+  - id: "test-id"
+    name: "scans"
+    enabled: true
+    source_dir: "/src"
+    destination_dir: "/dst"
+    action: move
+    rename: "scan_{counter:4}.{ext}"
+    counter:
+      sort: modified
+      descending: true
+      resume: true
+    filters:
+      extensions:
+        - ".pdf"
+"#;
+        let file = RulesetFile::from_yaml(yaml).unwrap();
+        let rs = &file.rulesets[0];
+        assert_eq!(rs.counter.sort, CounterSort::Modified);
+        assert!(rs.counter.descending);
+        assert!(rs.counter.resume);
+        assert_eq!(rs.counter.start, 1);
+        assert!(rs.validate().is_ok());
+        assert!(Template::parse(rs.rename.as_deref().unwrap())
+            .unwrap()
+            .uses_counter());
+
+        let mut rs = sample_ruleset();
+        rs.destination_dir = "/dst/batch_{counter}".into();
+        assert!(rs.validate().is_ok());
+        rs.destination_dir = "/dst/{counter:four}".into();
+        assert!(rs.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_template_filters() {
+        let mut rs = sample_ruleset();
+        rs.destination_dir = "D:/sorted/{ext|lower}/{mtime:%Y|default:unknown}".into();
+        assert!(rs.validate().is_ok());
+
+        rs.destination_dir = "D:/sorted/{ext|capitalize}".into();
+        assert!(rs.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_date_template_errors() {
+        let mut rs = sample_ruleset();
+        rs.destination_dir = "D:/photos/{mtime:%Q}".to_string();
+        assert!(rs.validate().is_err());
+
+        rs.destination_dir = "D:/photos/{size:%Y}".to_string();
+        assert!(rs.validate().is_err());
+
+        // 組み込み変数と正規表現のキャプチャを併用する場合は正規表現フィルタが必要
+        rs.destination_dir = "D:/photos/{mtime:%Y}/{label}".to_string();
+        assert!(rs.validate().is_err());
+    }
+
     #[test]
     fn test_validate_no_template_with_glob_ok() {
         // テンプレートなしの従来ルールセットは変更なし
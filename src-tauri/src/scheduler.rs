@@ -0,0 +1,383 @@
+//! `schedule` が設定されたルールセットを定期実行する。
+//! 前回の実行時刻を保存しておき、アプリを閉じていた間に予定を過ぎていた場合は起動時に 1 回だけ実行する。
+
+use crate::ruleset::Ruleset;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 実行予定を確認する間隔
+pub const DEFAULT_TICK: Duration = Duration::from_secs(30);
+
+/// ルールセットごとの前回の実行時刻
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ScheduleState {
+    #[serde(default)]
+    pub last_runs: HashMap<String, DateTime<Utc>>,
+}
+
+pub fn default_state_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("filo")
+        .join("schedule-state.json")
+}
+
+impl ScheduleState {
+    /// 保存された状態を読み込む。ファイルがない場合は空の状態を返し、壊れている場合はその理由を返す。
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("Ignoring broken schedule state {}: {}", path.display(), e)),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// 書き込み途中で中断されても壊れないよう、一時ファイルに書いてから置き換える。
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, path)
+    }
+}
+
+/// `now` の時点で実行すべきルールセットを一覧の順に返す。
+/// 前回の実行時刻が未記録のルールセットは、登録直後に実行されないよう `now` を基準として記録する。
+/// 削除されたルールセットやスケジュールを外したルールセットの記録は取り除く。
+/// スケジュールを解釈できないルールセットは実行せず、その理由を `errors` に追加する。
+pub fn due_rulesets<'a>(
+    rulesets: &'a [Ruleset],
+    state: &mut ScheduleState,
+    now: DateTime<Local>,
+    errors: &mut Vec<String>,
+) -> Vec<&'a Ruleset> {
+    state
+        .last_runs
+        .retain(|id, _| rulesets.iter().any(|r| &r.id == id && r.schedule.is_some()));
+
+    let mut due = Vec::new();
+    for ruleset in rulesets {
+        let Some(schedule) = ruleset.schedule.as_ref().filter(|_| ruleset.enabled) else {
+            continue;
+        };
+        let Some(last_run) = state.last_runs.get(&ruleset.id) else {
+            state
+                .last_runs
+                .insert(ruleset.id.clone(), now.with_timezone(&Utc));
+            continue;
+        };
+        // 予定を複数回過ぎていても、次回予定が過去であれば 1 回だけ実行する
+        match schedule.next_after(last_run.with_timezone(&Local)) {
+            Ok(next) if next <= now => due.push(ruleset),
+            Ok(_) => {}
+            Err(e) => errors.push(format!(
+                "Invalid schedule for ruleset '{}': {}",
+                ruleset.name, e
+            )),
+        }
+    }
+    due
+}
+
+/// バックグラウンドで実行予定を確認し続けるハンドル。drop すると停止する。
+pub struct Scheduler {
+    stop: Arc<AtomicBool>,
+}
+
+impl Scheduler {
+    /// `tick` ごとに `load_rulesets()` で最新のルールセットを読み、実行予定のものを
+    /// `run(ruleset)` で順に実行する。起動直後にも一度確認するため、閉じていた間の予定はすぐに実行される。
+    /// 実行のたびに前回の実行時刻を `state_path` に保存する。
+    /// ルールセットの読み込み・状態の保存の失敗や解釈できないスケジュールは `on_error` に渡す。
+    /// 同じエラーが続く間は確認のたびに繰り返さず、最初の 1 回だけ渡す。
+    pub fn start<L, R, E>(
+        state_path: PathBuf,
+        tick: Duration,
+        load_rulesets: L,
+        mut run: R,
+        mut on_error: E,
+    ) -> Self
+    where
+        L: Fn() -> Result<Vec<Ruleset>, String> + Send + 'static,
+        R: FnMut(&Ruleset) + Send + 'static,
+        E: FnMut(String) + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        std::thread::spawn(move || {
+            let mut state = ScheduleState::load(&state_path).unwrap_or_else(|e| {
+                on_error(e);
+                ScheduleState::default()
+            });
+            let mut reported: HashSet<String> = HashSet::new();
+            while !stopped.load(Ordering::Relaxed) {
+                let mut errors = Vec::new();
+                // 読み込めない間は記録を取り除かないよう、その回の確認を見送る
+                match load_rulesets() {
+                    Ok(rulesets) => {
+                        let before = state.clone();
+                        let due = due_rulesets(&rulesets, &mut state, Local::now(), &mut errors);
+                        for ruleset in due {
+                            if stopped.load(Ordering::Relaxed) {
+                                break;
+                            }
+                            // 実行中にアプリが終了しても同じ予定を繰り返さないよう、実行前に記録する
+                            state.last_runs.insert(ruleset.id.clone(), Utc::now());
+                            if let Err(e) = state.save(&state_path) {
+                                errors.push(format!("Failed to save schedule state: {}", e));
+                            }
+                            run(ruleset);
+                        }
+                        if state != before {
+                            if let Err(e) = state.save(&state_path) {
+                                errors.push(format!("Failed to save schedule state: {}", e));
+                            }
+                        }
+                    }
+                    Err(e) => errors.push(e),
+                }
+                for error in &errors {
+                    if !reported.contains(error) {
+                        on_error(error.clone());
+                    }
+                }
+                reported = errors.into_iter().collect();
+                std::thread::sleep(tick);
+            }
+        });
+        Self { stop }
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ruleset::{Action, ConflictPolicy, CounterOptions, Filters, ScanOptions, Schedule};
+    use chrono::TimeZone;
+    use std::sync::Mutex;
+
+    fn scheduled_ruleset(id: &str, schedule: Schedule) -> Ruleset {
+        Ruleset {
+            id: id.to_string(),
+            name: id.to_string(),
+            enabled: true,
+            source_dir: "/src".to_string(),
+            destination_dir: "/dst".to_string(),
+            action: Action::Move,
+            on_conflict: ConflictPolicy::Skip,
+            rename_pattern: None,
+            rename: None,
+            counter: CounterOptions::default(),
+            filters: Filters {
+                extensions: Some(vec![".log".to_string()]),
+                filename: None,
+                created_at: None,
+                modified_at: None,
+                accessed_at: None,
+                size: None,
+                exclude: None,
+                all: None,
+                any: None,
+                not: None,
+                captures: None,
+            },
+            scan: ScanOptions::default(),
+            watch: false,
+            schedule: Some(schedule),
+            parallelism: None,
+        }
+    }
+
+    fn weekly() -> Schedule {
+        Schedule {
+            cron: Some("0 3 * * 1".to_string()),
+            interval: None,
+        }
+    }
+
+    fn local(y: i32, m: u32, d: u32, h: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_first_sighting_is_recorded_not_run() {
+        let rulesets = vec![scheduled_ruleset("a", weekly())];
+        let mut state = ScheduleState::default();
+        let now = local(2025, 1, 1, 12);
+
+        assert!(due_rulesets(&rulesets, &mut state, now, &mut Vec::new()).is_empty());
+        assert_eq!(state.last_runs["a"], now.with_timezone(&Utc));
+    }
+
+    #[test]
+    fn test_due_after_next_occurrence() {
+        let rulesets = vec![scheduled_ruleset("a", weekly())];
+        let mut state = ScheduleState::default();
+        // 水曜に実行済み → 次の月曜 3:00 まで実行しない
+        state
+            .last_runs
+            .insert("a".to_string(), local(2025, 1, 1, 12).with_timezone(&Utc));
+
+        assert!(
+            due_rulesets(&rulesets, &mut state, local(2025, 1, 6, 2), &mut Vec::new()).is_empty()
+        );
+        assert_eq!(
+            due_rulesets(&rulesets, &mut state, local(2025, 1, 6, 3), &mut Vec::new()).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_missed_runs_are_caught_up_once() {
+        let rulesets = vec![scheduled_ruleset(
+            "a",
+            Schedule {
+                cron: None,
+                interval: Some("1d".to_string()),
+            },
+        )];
+        let mut state = ScheduleState::default();
+        state
+            .last_runs
+            .insert("a".to_string(), local(2025, 1, 1, 0).with_timezone(&Utc));
+
+        // 10 日間閉じていても実行は 1 回
+        let due = due_rulesets(
+            &rulesets,
+            &mut state,
+            local(2025, 1, 11, 0),
+            &mut Vec::new(),
+        );
+        assert_eq!(due.len(), 1);
+    }
+
+    #[test]
+    fn test_disabled_and_removed_rulesets() {
+        let mut disabled = scheduled_ruleset("a", weekly());
+        disabled.enabled = false;
+        let rulesets = vec![disabled];
+        let mut state = ScheduleState::default();
+        state
+            .last_runs
+            .insert("a".to_string(), local(2024, 1, 1, 0).with_timezone(&Utc));
+        state
+            .last_runs
+            .insert("gone".to_string(), local(2024, 1, 1, 0).with_timezone(&Utc));
+
+        assert!(
+            due_rulesets(&rulesets, &mut state, local(2025, 1, 1, 0), &mut Vec::new()).is_empty()
+        );
+        // 無効化したルールセットの記録は残し、削除されたものは取り除く
+        assert!(state.last_runs.contains_key("a"));
+        assert!(!state.last_runs.contains_key("gone"));
+    }
+
+    #[test]
+    fn test_state_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join("schedule-state.json");
+        assert_eq!(ScheduleState::load(&path), Ok(ScheduleState::default()));
+
+        let mut state = ScheduleState::default();
+        state
+            .last_runs
+            .insert("a".to_string(), local(2025, 1, 1, 0).with_timezone(&Utc));
+        state.save(&path).unwrap();
+        assert_eq!(ScheduleState::load(&path), Ok(state));
+
+        fs::write(&path, "{ broken").unwrap();
+        assert!(ScheduleState::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_scheduler_runs_overdue_ruleset_on_start() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("schedule-state.json");
+        let mut state = ScheduleState::default();
+        state
+            .last_runs
+            .insert("a".to_string(), local(2024, 1, 1, 0).with_timezone(&Utc));
+        state.save(&path).unwrap();
+
+        let ran: Arc<Mutex<Vec<String>>> = Default::default();
+        let sink = ran.clone();
+        let scheduler = Scheduler::start(
+            path.clone(),
+            Duration::from_millis(20),
+            || Ok(vec![scheduled_ruleset("a", weekly())]),
+            move |rs| sink.lock().unwrap().push(rs.id.clone()),
+            |e| panic!("unexpected scheduler error: {}", e),
+        );
+        std::thread::sleep(Duration::from_millis(200));
+        drop(scheduler);
+
+        assert_eq!(*ran.lock().unwrap(), vec!["a".to_string()]);
+        assert!(ScheduleState::load(&path).unwrap().last_runs["a"] > local(2024, 1, 1, 0));
+    }
+
+    #[test]
+    fn test_invalid_schedule_is_reported() {
+        let rulesets = vec![scheduled_ruleset(
+            "a",
+            Schedule {
+                cron: Some("not a cron".to_string()),
+                interval: None,
+            },
+        )];
+        let mut state = ScheduleState::default();
+        state
+            .last_runs
+            .insert("a".to_string(), local(2025, 1, 1, 0).with_timezone(&Utc));
+
+        let mut errors = Vec::new();
+        assert!(due_rulesets(&rulesets, &mut state, local(2025, 2, 1, 0), &mut errors).is_empty());
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("'a'"));
+    }
+
+    #[test]
+    fn test_scheduler_reports_repeated_errors_once_and_keeps_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("schedule-state.json");
+        let mut state = ScheduleState::default();
+        state
+            .last_runs
+            .insert("a".to_string(), local(2024, 1, 1, 0).with_timezone(&Utc));
+        state.save(&path).unwrap();
+
+        let errors: Arc<Mutex<Vec<String>>> = Default::default();
+        let sink = errors.clone();
+        let scheduler = Scheduler::start(
+            path.clone(),
+            Duration::from_millis(20),
+            || Err("Failed to load rulesets".to_string()),
+            |_| panic!("nothing should run"),
+            move |e| sink.lock().unwrap().push(e),
+        );
+        std::thread::sleep(Duration::from_millis(200));
+        drop(scheduler);
+
+        assert_eq!(
+            *errors.lock().unwrap(),
+            vec!["Failed to load rulesets".to_string()]
+        );
+        // 読み込めなかった間に前回の実行時刻が消えていない
+        assert_eq!(ScheduleState::load(&path), Ok(state));
+    }
+}
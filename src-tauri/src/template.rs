@@ -0,0 +1,500 @@
+//! 保存先・`rename`・`rename_pattern` のテンプレートの解釈。
+//! `{label|lower}` のような変数と変換を解析し、検証・プレビュー・実行で同じ結果を共有する。
+//! `{{`・`}}` は変数ではなく `{`・`}` そのものとして扱う。
+
+/// 日時から値を作る組み込みのテンプレート変数。`{mtime:%Y}` のように chrono の書式を指定でき、
+/// 書式を省略した場合は `DEFAULT_DATE_FORMAT` を使う。
+/// `mtime` は更新日時、`ctime` は作成日時、`now` は実行時刻。
+pub const DATE_TEMPLATE_VARS: &[&str] = &["mtime", "ctime", "now"];
+
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// ファイルやルールセットから値を作る組み込みのテンプレート変数。
+/// `stem` は拡張子を除いたファイル名、`ext` はドットを含まない拡張子、`parent` はファイルがあるフォルダ名、
+/// `ruleset` はルールセット名、`source_dir_name` はソースフォルダ名。
+pub const FILE_TEMPLATE_VARS: &[&str] = &["stem", "ext", "parent", "ruleset", "source_dir_name"];
+
+/// 連番のテンプレート変数。`{counter:4}` のように桁数を指定するとゼロ埋めする。
+pub const COUNTER_TEMPLATE_VAR: &str = "counter";
+
+/// `{counter:4}` の桁数・`pad` の桁数の上限
+const MAX_WIDTH: usize = 255;
+
+/// 解析済みのテンプレート
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    /// そのまま出力する文字列。`{{`・`}}` は `{`・`}` に戻してある
+    Literal(String),
+    Var(TemplateVar),
+}
+
+/// `{name:format|filter|...}` の形のテンプレート変数
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateVar {
+    /// `{` と `}` の間に書かれた元の文字列
+    pub expr: String,
+    pub name: String,
+    /// `{mtime:%Y}` の `%Y`・`{counter:4}` の `4`
+    pub format: Option<String>,
+    pub filters: Vec<TemplateFilter>,
+}
+
+impl Template {
+    /// テンプレート文字列を解析する。閉じていない `{`・対応のない `}`・空の変数・不正な変換や書式は `Err`。
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut rest = s;
+        while let Some(i) = rest.find(['{', '}']) {
+            literal.push_str(&rest[..i]);
+            let tail = &rest[i..];
+            if tail.starts_with("{{") || tail.starts_with("}}") {
+                literal.push_str(&tail[..1]);
+                rest = &tail[2..];
+                continue;
+            }
+            if tail.starts_with('}') {
+                return Err(format!(
+                    "unmatched '}}' in template '{}' (write '}}}}' for a literal brace)",
+                    s
+                ));
+            }
+            let end = tail
+                .find('}')
+                .ok_or_else(|| format!("unclosed '{{' in template '{}'", s))?;
+            let expr = &tail[1..end];
+            if expr.contains('{') {
+                return Err(format!(
+                    "unexpected '{{' in template '{}' (write '{{{{' for a literal brace)",
+                    s
+                ));
+            }
+            if !literal.is_empty() {
+                segments.push(Segment::Literal(std::mem::take(&mut literal)));
+            }
+            segments.push(Segment::Var(TemplateVar::parse(expr)?));
+            rest = &tail[end + 1..];
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self { segments })
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// テンプレート中の変数を順に返す
+    pub fn vars(&self) -> impl Iterator<Item = &TemplateVar> {
+        self.segments.iter().filter_map(|segment| match segment {
+            Segment::Var(var) => Some(var),
+            Segment::Literal(_) => None,
+        })
+    }
+
+    /// 変数を含まない場合、`{{`・`}}` を戻した文字列を返す
+    pub fn as_literal(&self) -> Option<String> {
+        match self.segments.as_slice() {
+            [] => Some(String::new()),
+            [Segment::Literal(text)] => Some(text.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn has_vars(&self) -> bool {
+        self.vars().next().is_some()
+    }
+
+    /// テンプレートに `{counter}` が含まれているか
+    pub fn uses_counter(&self) -> bool {
+        self.vars().any(|var| var.name == COUNTER_TEMPLATE_VAR)
+    }
+
+    /// 変数を `value(var)` の値に置き換えた文字列を返す。最初に `Err` になった変数のエラーを返す。
+    pub fn render(
+        &self,
+        mut value: impl FnMut(&TemplateVar) -> Result<String, String>,
+    ) -> Result<String, String> {
+        let mut result = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => result.push_str(text),
+                Segment::Var(var) => result.push_str(&value(var)?),
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl TemplateVar {
+    fn parse(expr: &str) -> Result<Self, String> {
+        let mut parts = expr.split('|');
+        let var = parts.next().unwrap_or_default();
+        if var.is_empty() {
+            return Err(format!("empty template variable '{{{}}}'", expr));
+        }
+        let (name, format) = match var.split_once(':') {
+            Some((name, format)) => (name, Some(format)),
+            None => (var, None),
+        };
+        match (name, format) {
+            (name, Some(format)) if DATE_TEMPLATE_VARS.contains(&name) => {
+                validate_date_format(format)?
+            }
+            (COUNTER_TEMPLATE_VAR, Some(width)) => {
+                width
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|width| *width <= MAX_WIDTH)
+                    .ok_or_else(|| format!("invalid counter width '{}'", width))?;
+            }
+            (_, Some(_)) => return Err(format!("unknown template variable '{}'", var)),
+            (_, None) => {}
+        }
+        // 連番はファイルごとの描画結果から数え直す範囲を決めるため、変換で値を変えられないようにする
+        if name == COUNTER_TEMPLATE_VAR && expr.contains('|') {
+            return Err(format!(
+                "template filters cannot be applied to '{{{}}}'",
+                var
+            ));
+        }
+        Ok(Self {
+            expr: expr.to_string(),
+            name: name.to_string(),
+            format: format.map(str::to_string),
+            filters: parts.map(TemplateFilter::parse).collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// 日時・ファイル・連番の組み込み変数か。ファイル名のキャプチャがなくても値が決まる。
+    pub fn is_builtin(&self) -> bool {
+        DATE_TEMPLATE_VARS.contains(&self.name.as_str())
+            || self.name == COUNTER_TEMPLATE_VAR
+            || (self.format.is_none() && FILE_TEMPLATE_VARS.contains(&self.name.as_str()))
+    }
+
+    /// `value` に変換を左から順に適用する
+    pub fn apply_filters(&self, value: Result<String, String>) -> Result<String, String> {
+        self.filters
+            .iter()
+            .fold(value, |value, filter| filter.apply(value))
+    }
+
+    /// `date` 変換の出力書式に `/` があり、値がフォルダの区切りを含みうるか
+    pub fn keeps_separators(&self) -> bool {
+        self.filters.iter().any(
+            |filter| matches!(filter, TemplateFilter::Date { output, .. } if output.contains('/')),
+        )
+    }
+}
+
+/// テンプレート変数の値に `{label|lower}` のように `|` で続けて適用する変換。左から順に適用する。
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateFilter {
+    /// 小文字にする
+    Lower,
+    /// 大文字にする
+    Upper,
+    /// 先頭を `0` で埋めて指定の文字数にする（`pad:6`）
+    Pad(usize),
+    /// 値がない・空の場合に代わりに使う値（`default:unknown`）
+    Default(String),
+    /// 文字単位で `start` から `end`（省略時は末尾）の手前までを取り出す（`slice:0:1`）
+    Slice(usize, Option<usize>),
+    /// `input` の書式で日時として解釈し、`output` の書式で整形し直す（`date:%Y%m%d->%Y/%m`）。
+    /// `output` 中の `/` はフォルダの区切りとして扱う。
+    Date { input: String, output: String },
+    /// 整数として解釈し、先頭の `0` を除いた 10 進数にする（`num`）
+    Num,
+    /// 整数を `size` ごとの範囲 `0-999` の形にまとめる（`range:1000`）
+    Range(u64),
+}
+
+impl TemplateFilter {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (name, arg) = match s.split_once(':') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (s, None),
+        };
+        let number = |value: &str| {
+            value
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| format!("invalid number '{}' in template filter '{}'", value, s))
+        };
+        match (name.trim(), arg) {
+            ("lower", None) => Ok(Self::Lower),
+            ("upper", None) => Ok(Self::Upper),
+            ("pad", Some(width)) => match number(width)? {
+                width if width <= MAX_WIDTH => Ok(Self::Pad(width)),
+                _ => Err(format!("pad width '{}' exceeds {}", width, MAX_WIDTH)),
+            },
+            ("default", Some(value)) => Ok(Self::Default(value.to_string())),
+            ("slice", Some(range)) => {
+                let (start, end) = match range.split_once(':') {
+                    Some((start, end)) => (number(start)?, Some(number(end)?)),
+                    None => (number(range)?, None),
+                };
+                if end.is_some_and(|end| end < start) {
+                    return Err(format!("invalid range in template filter '{}'", s));
+                }
+                Ok(Self::Slice(start, end))
+            }
+            ("date", Some(formats)) => {
+                let (input, output) = formats.split_once("->").ok_or_else(|| {
+                    format!("template filter '{}' must be in the form date:IN->OUT", s)
+                })?;
+                validate_date_format(input)?;
+                validate_naive_date_format(output)?;
+                Ok(Self::Date {
+                    input: input.to_string(),
+                    output: output.to_string(),
+                })
+            }
+            ("num", None) => Ok(Self::Num),
+            ("range", Some(size)) => match number(size)? {
+                0 => Err(format!("range size must be greater than zero in '{}'", s)),
+                size => Ok(Self::Range(size as u64)),
+            },
+            _ => Err(format!("unknown template filter '{}'", s)),
+        }
+    }
+
+    /// 変数の値に変換を適用する。`default` 以外は値が解決できなかった場合（`Err`）はそのまま返す。
+    pub fn apply(&self, value: Result<String, String>) -> Result<String, String> {
+        match self {
+            Self::Default(default) => match value {
+                Ok(v) if !v.is_empty() => Ok(v),
+                _ => Ok(default.clone()),
+            },
+            Self::Lower => value.map(|v| v.to_lowercase()),
+            Self::Upper => value.map(|v| v.to_uppercase()),
+            Self::Pad(width) => value.map(|v| format!("{:0>width$}", v, width = *width)),
+            Self::Slice(start, end) => value.map(|v| {
+                let take = end.map_or(usize::MAX, |end| end - start);
+                v.chars().skip(*start).take(take).collect()
+            }),
+            Self::Date { input, output } => value.and_then(|v| convert_date(&v, input, output)),
+            Self::Num => value.and_then(|v| parse_number(&v).map(|n| n.to_string())),
+            Self::Range(size) => value.and_then(|v| {
+                let low = parse_number(&v)? / size * size;
+                Ok(format!("{}-{}", low, low.saturating_add(size - 1)))
+            }),
+        }
+    }
+}
+
+/// テンプレートの最初の変数より前にある、変数を含まないフォルダ部分（`D:/sorted/{label}` の `D:/sorted/`）
+pub fn template_static_prefix(template: &str) -> &str {
+    let head = template.split('{').next().unwrap_or_default();
+    &head[..head.rfind(['/', '\\']).map_or(0, |i| i + 1)]
+}
+
+/// chrono の書式文字列として解釈できるか検証する。不正な書式は整形時に panic するため、事前に弾く。
+pub fn validate_date_format(format: &str) -> Result<(), String> {
+    use chrono::format::{Item, StrftimeItems};
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(format!("invalid date format '{}'", format));
+    }
+    Ok(())
+}
+
+/// タイムゾーンを持たない日時（`date` 変換の結果）に使える書式か検証する。
+/// `%z`・`%Z` のようにタイムゾーンが必要な書式は整形時に失敗するため、見本の日時で試して確かめる。
+fn validate_naive_date_format(format: &str) -> Result<(), String> {
+    validate_date_format(format)?;
+    format_naive_date(&chrono::NaiveDateTime::MIN, format)
+        .map(|_| ())
+        .map_err(|_| {
+            format!(
+                "date format '{}' cannot be used without a time zone",
+                format
+            )
+        })
+}
+
+/// 書式に使えない指定があると `to_string` は panic するため、`write!` で整形して失敗を返す
+fn format_naive_date(
+    date: &chrono::NaiveDateTime,
+    format: &str,
+) -> Result<String, std::fmt::Error> {
+    use std::fmt::Write;
+    let mut formatted = String::new();
+    write!(formatted, "{}", date.format(format))?;
+    Ok(formatted)
+}
+
+/// キャプチャした値を `input` の書式で日時として解釈し、`output` の書式で整形する。
+/// 日を含まない書式（`%Y%m` など）は 1 日、時刻を含まない書式は 0 時として扱う。
+fn convert_date(value: &str, input: &str, output: &str) -> Result<String, String> {
+    use chrono::format::{parse, Parsed, StrftimeItems};
+    let invalid = |e: chrono::format::ParseError| {
+        format!("Cannot parse '{}' as date '{}': {}", value, input, e)
+    };
+    let mut parsed = Parsed::new();
+    parse(&mut parsed, value, StrftimeItems::new(input)).map_err(invalid)?;
+    let has_day = parsed.day().is_some()
+        || parsed.ordinal().is_some()
+        || parsed.isoweek().is_some()
+        || parsed.week_from_sun().is_some()
+        || parsed.week_from_mon().is_some();
+    if !has_day {
+        if parsed.month().is_none() {
+            parsed.set_month(1).map_err(invalid)?;
+        }
+        parsed.set_day(1).map_err(invalid)?;
+    }
+    let date = parsed.to_naive_date().map_err(invalid)?;
+    let time = parsed.to_naive_time().unwrap_or(chrono::NaiveTime::MIN);
+    format_naive_date(&date.and_time(time), output)
+        .map_err(|_| format!("Cannot format date with '{}'", output))
+}
+
+/// `num`・`range` の対象となる値を 0 以上の整数として解釈する
+fn parse_number(value: &str) -> Result<u64, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("Template value '{}' is not a number", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var_names(template: &str) -> Vec<String> {
+        Template::parse(template)
+            .unwrap()
+            .vars()
+            .map(|var| var.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_template_segments() {
+        let template = Template::parse("D:/sorted/{label|lower}/{mtime:%Y}").unwrap();
+        assert_eq!(template.segments().len(), 4);
+        let vars: Vec<_> = template.vars().collect();
+        assert_eq!(vars[0].name, "label");
+        assert_eq!(vars[0].filters, vec![TemplateFilter::Lower]);
+        assert_eq!(vars[1].name, "mtime");
+        assert_eq!(vars[1].format.as_deref(), Some("%Y"));
+        assert!(!vars[0].is_builtin());
+        assert!(vars[1].is_builtin());
+
+        assert!(!Template::parse("D:/sorted/static").unwrap().has_vars());
+        assert!(!Template::parse("").unwrap().has_vars());
+        assert_eq!(
+            Template::parse("D:/{{raw}}").unwrap().as_literal(),
+            Some("D:/{raw}".to_string())
+        );
+        assert_eq!(Template::parse("D:/{label}").unwrap().as_literal(), None);
+        assert!(Template::parse("scan_{counter:4}").unwrap().uses_counter());
+        assert!(!Template::parse("{stem}").unwrap().uses_counter());
+    }
+
+    #[test]
+    fn test_parse_template_escapes() {
+        let template = Template::parse("D:/{{raw}}/{label}_}}").unwrap();
+        assert_eq!(var_names("D:/{{raw}}/{label}_}}"), vec!["label"]);
+        let rendered = template.render(|var| Ok(var.name.to_uppercase()));
+        assert_eq!(rendered, Ok("D:/{raw}/LABEL_}".to_string()));
+
+        // `{{` の直後の変数
+        assert_eq!(var_names("{{{id}}}"), vec!["id"]);
+        assert_eq!(
+            Template::parse("{{{id}}}")
+                .unwrap()
+                .render(|_| Ok("7".into())),
+            Ok("{7}".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_template_errors() {
+        assert!(Template::parse("D:/{label").is_err());
+        assert!(Template::parse("D:/label}").is_err());
+        assert!(Template::parse("D:/{}").is_err());
+        assert!(Template::parse("D:/{a{b}").is_err());
+        assert!(Template::parse("D:/{label|title}").is_err());
+        assert!(Template::parse("D:/{mtime:%Q}").is_err());
+        assert!(Template::parse("D:/{counter:four}").is_err());
+        assert!(Template::parse("D:/{counter:256}").is_err());
+        assert!(Template::parse("D:/{counter:255}").is_ok());
+        assert!(Template::parse("D:/{counter|lower}").is_err());
+        assert!(Template::parse("D:/{counter:3|pad:5}").is_err());
+        assert!(Template::parse("D:/{stem:x}").is_err());
+    }
+
+    #[test]
+    fn test_render_stops_at_first_error() {
+        let template = Template::parse("{a}/{b}/{c}").unwrap();
+        let mut seen = Vec::new();
+        let result = template.render(|var| {
+            seen.push(var.name.clone());
+            match var.name.as_str() {
+                "b" => Err("missing b".to_string()),
+                name => Ok(name.to_string()),
+            }
+        });
+        assert_eq!(result, Err("missing b".to_string()));
+        assert_eq!(seen, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_parse_template_filter() {
+        assert_eq!(TemplateFilter::parse("lower"), Ok(TemplateFilter::Lower));
+        assert_eq!(TemplateFilter::parse("upper"), Ok(TemplateFilter::Upper));
+        assert_eq!(TemplateFilter::parse("pad:6"), Ok(TemplateFilter::Pad(6)));
+        assert_eq!(
+            TemplateFilter::parse("default:no:client"),
+            Ok(TemplateFilter::Default("no:client".to_string()))
+        );
+        assert_eq!(
+            TemplateFilter::parse("slice:0:1"),
+            Ok(TemplateFilter::Slice(0, Some(1)))
+        );
+        assert_eq!(
+            TemplateFilter::parse("slice:2"),
+            Ok(TemplateFilter::Slice(2, None))
+        );
+        assert_eq!(
+            TemplateFilter::parse("date:%Y%m%d->%Y/%m"),
+            Ok(TemplateFilter::Date {
+                input: "%Y%m%d".to_string(),
+                output: "%Y/%m".to_string(),
+            })
+        );
+        assert_eq!(TemplateFilter::parse("num"), Ok(TemplateFilter::Num));
+        assert_eq!(
+            TemplateFilter::parse("range:1000"),
+            Ok(TemplateFilter::Range(1000))
+        );
+
+        assert!(TemplateFilter::parse("title").is_err());
+        assert!(TemplateFilter::parse("lower:1").is_err());
+        assert!(TemplateFilter::parse("pad").is_err());
+        assert!(TemplateFilter::parse("pad:x").is_err());
+        assert!(TemplateFilter::parse("pad:256").is_err());
+        assert!(TemplateFilter::parse("pad:255").is_ok());
+        assert!(TemplateFilter::parse("slice:3:1").is_err());
+        assert!(TemplateFilter::parse("date:%Y%m%d").is_err());
+        assert!(TemplateFilter::parse("date:%Q->%Y").is_err());
+        // タイムゾーンのない日時には `%z` を使えない
+        assert!(TemplateFilter::parse("date:%Y%m%d->%Y/%z").is_err());
+        assert!(TemplateFilter::parse("date:%Y%m%d%z->%Y/%m").is_ok());
+        let unchecked = TemplateFilter::Date {
+            input: "%Y%m%d".to_string(),
+            output: "%Y/%z".to_string(),
+        };
+        assert!(unchecked.apply(Ok("20250101".to_string())).is_err());
+        assert!(TemplateFilter::parse("range:0").is_err());
+    }
+}
@@ -0,0 +1,111 @@
+//! ルールファイルに書く単位付きの値の解析。
+
+use chrono::Duration;
+
+/// `"30m"`・`"6h"`・`"7d"`・`"2w"` のような期間を解析する。
+/// 単位は `s`（秒）・`m`（分）・`h`（時間）・`d`（日）・`w`（週）で、数値は正の整数のみ。
+/// 現在時刻に足し引きすると日時の範囲を超える長さは `Err`。
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| format!("missing unit in duration: '{}'", s))?;
+    let (number, unit) = s.split_at(split);
+    let value: i64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration: '{}'", s))?;
+    if value == 0 {
+        return Err(format!("duration must be greater than zero: '{}'", s));
+    }
+    let duration = match unit.trim() {
+        "s" => Duration::try_seconds(value),
+        "m" => Duration::try_minutes(value),
+        "h" => Duration::try_hours(value),
+        "d" => Duration::try_days(value),
+        "w" => Duration::try_weeks(value),
+        other => return Err(format!("unknown duration unit '{}' in '{}'", other, s)),
+    };
+    let now = chrono::Utc::now();
+    duration
+        .filter(|d| now.checked_add_signed(*d).is_some() && now.checked_sub_signed(*d).is_some())
+        .ok_or_else(|| format!("duration is too large: '{}'", s))
+}
+
+/// `"10MB"`・`"1.5 GiB"`・`"512"` のようなファイルサイズをバイト数に解析する。
+/// `KB`・`MB`・`GB`・`TB` は 1000 倍、`KiB`・`MiB`・`GiB`・`TiB` は 1024 倍の単位として扱い、
+/// 単位なし・`B` はバイト。単位の大文字小文字は区別しない。
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let value: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid size: '{}'", s))?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "tb" => 1_000_000_000_000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        "tib" => 1 << 40,
+        _ => return Err(format!("unknown size unit '{}' in '{}'", unit.trim(), s)),
+    };
+    let bytes = (value * multiplier as f64).round();
+    if !bytes.is_finite() || bytes > u64::MAX as f64 {
+        return Err(format!("size is too large: '{}'", s));
+    }
+    Ok(bytes as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration_units() {
+        assert_eq!(parse_duration("45s").unwrap(), Duration::seconds(45));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_duration("6h").unwrap(), Duration::hours(6));
+        assert_eq!(parse_duration("7d").unwrap(), Duration::days(7));
+        assert_eq!(parse_duration(" 2w ").unwrap(), Duration::weeks(2));
+    }
+
+    #[test]
+    fn test_parse_duration_rejects_invalid() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("10").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("0h").is_err());
+        assert!(parse_duration("1y").is_err());
+        assert!(parse_duration("-1d").is_err());
+        assert!(parse_duration("1.5h").is_err());
+        // 時間の長さとしては表せても、現在時刻に足すと日時の範囲を超える
+        assert!(parse_duration("20000000w").is_err());
+        assert!(parse_duration("99999999999999999999d").is_err());
+    }
+
+    #[test]
+    fn test_parse_size_units() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("512B").unwrap(), 512);
+        assert_eq!(parse_size("10MB").unwrap(), 10_000_000);
+        assert_eq!(parse_size("10 mb").unwrap(), 10_000_000);
+        assert_eq!(parse_size("1.5 GiB").unwrap(), 1_610_612_736);
+        assert_eq!(parse_size("4KiB").unwrap(), 4096);
+        assert_eq!(parse_size("2TB").unwrap(), 2_000_000_000_000);
+    }
+
+    #[test]
+    fn test_parse_size_rejects_invalid() {
+        assert!(parse_size("").is_err());
+        assert!(parse_size("MB").is_err());
+        assert!(parse_size("10 XB").is_err());
+        assert!(parse_size("-1MB").is_err());
+        assert!(parse_size("1.2.3MB").is_err());
+    }
+}
@@ -0,0 +1,352 @@
+//! `watch: true` のルールセットのソースディレクトリを監視し、新しく置かれたファイルに
+//! ルールセットを自動で適用する。監視開始時点で既にあるファイルは対象にしない。
+
+use crate::ruleset::Ruleset;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// 最後のイベントからこの時間だけ変化がなければ、書き込みが完了したとみなして処理する
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_secs(2);
+
+#[derive(Debug, thiserror::Error)]
+pub enum WatchError {
+    #[error("Failed to watch directory: {0}")]
+    Notify(#[from] notify::Error),
+}
+
+/// パスごとに最後のイベント時刻を記録し、`delay` の間イベントが途切れたものだけを取り出す。
+/// ダウンロード中のファイルのように書き込みが続く間は処理を遅らせる。
+struct Debouncer {
+    delay: Duration,
+    pending: HashMap<PathBuf, Instant>,
+}
+
+impl Debouncer {
+    fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            pending: HashMap::new(),
+        }
+    }
+
+    fn push(&mut self, path: PathBuf, now: Instant) {
+        self.pending.insert(path, now);
+    }
+
+    /// 次に `take_ready` が何かを返しうる時刻。保留中のパスがなければ `None`
+    fn next_deadline(&self) -> Option<Instant> {
+        self.pending.values().min().map(|t| *t + self.delay)
+    }
+
+    /// 待機時間を過ぎたパスを取り出す。処理順を安定させるためパス順に並べる。
+    fn take_ready(&mut self, now: Instant) -> Vec<PathBuf> {
+        let delay = self.delay;
+        let mut ready: Vec<PathBuf> = self
+            .pending
+            .iter()
+            .filter(|(_, t)| now.duration_since(**t) >= delay)
+            .map(|(p, _)| p.clone())
+            .collect();
+        for path in &ready {
+            self.pending.remove(path);
+        }
+        ready.sort();
+        ready
+    }
+}
+
+/// ファイルの到着・書き込みを表すイベントのパスを返す。削除や移動元のイベントは無視する。
+fn arrival_paths(event: &Event) -> &[PathBuf] {
+    match event.kind {
+        EventKind::Create(_) | EventKind::Modify(ModifyKind::Data(_)) => &event.paths,
+        EventKind::Modify(ModifyKind::Name(RenameMode::To | RenameMode::Any)) => &event.paths,
+        // 移動元と移動先の両方が含まれる場合は移動先のみ
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+            event.paths.get(1..).unwrap_or(&[])
+        }
+        EventKind::Modify(ModifyKind::Any) | EventKind::Any => &event.paths,
+        _ => &[],
+    }
+}
+
+/// ルールセットの監視範囲（`scan.recursive` ならサブディレクトリを含む）に `path` が含まれるか
+fn covers(ruleset: &Ruleset, path: &Path) -> bool {
+    let source_dir = ruleset.source_path();
+    if ruleset.scan.recursive {
+        path.starts_with(&source_dir) && path != source_dir
+    } else {
+        path.parent() == Some(source_dir.as_path())
+    }
+}
+
+/// 到着したパスをルールセットごとに振り分ける。ルールセットは一覧の順に並び、
+/// 「すべて実行」と同様に先のルールセットで処理されたファイルは後のルールセットでは見つからない。
+fn route<'a>(rulesets: &'a [Ruleset], paths: &[PathBuf]) -> Vec<(&'a Ruleset, Vec<PathBuf>)> {
+    rulesets
+        .iter()
+        .filter_map(|rs| {
+            let matched: Vec<PathBuf> = paths.iter().filter(|p| covers(rs, p)).cloned().collect();
+            (!matched.is_empty()).then_some((rs, matched))
+        })
+        .collect()
+}
+
+/// ルールセットのソースディレクトリを監視するハンドル。drop すると監視を停止する。
+pub struct RulesetWatcher {
+    // drop でイベント送信側が閉じ、ワーカースレッドが終了する
+    _watcher: RecommendedWatcher,
+    failures: Vec<String>,
+}
+
+impl RulesetWatcher {
+    /// `enabled` かつ `watch` のルールセットの監視を開始する。
+    /// 到着したファイルは `debounce` の間変化がなくなってから、ルールセットごとに
+    /// `on_arrival(ruleset, paths)` としてワーカースレッド上で順に渡される。
+    /// 一部のディレクトリを監視できなくても他の監視は続け、その理由を `failures` に残す。
+    /// 監視を始めた後に起きたエラーは `on_error` でワーカースレッドから渡される。
+    pub fn start<F, E>(
+        rulesets: Vec<Ruleset>,
+        debounce: Duration,
+        mut on_arrival: F,
+        mut on_error: E,
+    ) -> Result<Self, WatchError>
+    where
+        F: FnMut(&Ruleset, Vec<PathBuf>) + Send + 'static,
+        E: FnMut(String) + Send + 'static,
+    {
+        let rulesets: Vec<Ruleset> = rulesets
+            .into_iter()
+            .filter(|r| r.enabled && r.watch)
+            .collect();
+
+        let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        })?;
+
+        // 同じディレクトリを複数のルールセットが使う場合は一度だけ監視する
+        let mut dirs: Vec<(PathBuf, RecursiveMode)> = Vec::new();
+        for rs in &rulesets {
+            let mode = if rs.scan.recursive {
+                RecursiveMode::Recursive
+            } else {
+                RecursiveMode::NonRecursive
+            };
+            let dir = rs.source_path();
+            match dirs.iter_mut().find(|(d, _)| *d == dir) {
+                Some(entry) if mode == RecursiveMode::Recursive => entry.1 = mode,
+                Some(_) => {}
+                None => dirs.push((dir, mode)),
+            }
+        }
+        let mut failures = Vec::new();
+        for (dir, mode) in &dirs {
+            if let Err(e) = watcher.watch(dir, *mode) {
+                failures.push(format!("{}: {}", dir.display(), WatchError::from(e)));
+            }
+        }
+
+        std::thread::spawn(move || {
+            let mut debouncer = Debouncer::new(debounce);
+            loop {
+                let received = match debouncer.next_deadline() {
+                    Some(deadline) => {
+                        rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                    }
+                    None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                };
+                match received {
+                    Ok(Ok(event)) => {
+                        let now = Instant::now();
+                        for path in arrival_paths(&event) {
+                            debouncer.push(path.clone(), now);
+                        }
+                    }
+                    Ok(Err(e)) => on_error(format!("File watch error: {}", e)),
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+
+                let ready = debouncer.take_ready(Instant::now());
+                if ready.is_empty() {
+                    continue;
+                }
+                for (ruleset, paths) in route(&rulesets, &ready) {
+                    on_arrival(ruleset, paths);
+                }
+            }
+        });
+
+        Ok(Self {
+            _watcher: watcher,
+            failures,
+        })
+    }
+
+    /// 監視を開始できなかったディレクトリとその理由
+    pub fn failures(&self) -> &[String] {
+        &self.failures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ruleset::{Action, ConflictPolicy, CounterOptions, Filters, ScanOptions};
+    use std::fs;
+    use std::sync::Mutex;
+
+    fn watched_ruleset(id: &str, source: &Path, recursive: bool) -> Ruleset {
+        Ruleset {
+            id: id.to_string(),
+            name: id.to_string(),
+            enabled: true,
+            source_dir: source.to_str().unwrap().to_string(),
+            destination_dir: "/dst".to_string(),
+            action: Action::Move,
+            on_conflict: ConflictPolicy::Skip,
+            rename_pattern: None,
+            rename: None,
+            counter: CounterOptions::default(),
+            filters: Filters {
+                extensions: Some(vec![".txt".to_string()]),
+                filename: None,
+                created_at: None,
+                modified_at: None,
+                accessed_at: None,
+                size: None,
+                exclude: None,
+                all: None,
+                any: None,
+                not: None,
+                captures: None,
+            },
+            scan: ScanOptions {
+                recursive,
+                ..ScanOptions::default()
+            },
+            watch: true,
+            schedule: None,
+            parallelism: None,
+        }
+    }
+
+    #[test]
+    fn test_debouncer_waits_for_quiet_period() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(Duration::from_secs(2));
+        debouncer.push(PathBuf::from("/src/a.txt"), start);
+        debouncer.push(PathBuf::from("/src/b.txt"), start);
+        // 書き込みが続いているファイルは待機時間が延長される
+        debouncer.push(PathBuf::from("/src/a.txt"), start + Duration::from_secs(1));
+
+        assert!(debouncer
+            .take_ready(start + Duration::from_secs(1))
+            .is_empty());
+        assert_eq!(
+            debouncer.take_ready(start + Duration::from_secs(2)),
+            vec![PathBuf::from("/src/b.txt")]
+        );
+        assert_eq!(
+            debouncer.next_deadline(),
+            Some(start + Duration::from_secs(3))
+        );
+        assert_eq!(
+            debouncer.take_ready(start + Duration::from_secs(3)),
+            vec![PathBuf::from("/src/a.txt")]
+        );
+        assert_eq!(debouncer.next_deadline(), None);
+    }
+
+    #[test]
+    fn test_arrival_paths_ignores_removal() {
+        let path = PathBuf::from("/src/a.txt");
+        let created =
+            Event::new(EventKind::Create(notify::event::CreateKind::File)).add_path(path.clone());
+        assert_eq!(arrival_paths(&created), std::slice::from_ref(&path));
+
+        let removed =
+            Event::new(EventKind::Remove(notify::event::RemoveKind::File)).add_path(path.clone());
+        assert!(arrival_paths(&removed).is_empty());
+
+        let renamed = Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+            .add_path(PathBuf::from("/src/a.part"))
+            .add_path(path.clone());
+        assert_eq!(arrival_paths(&renamed), &[path]);
+    }
+
+    #[test]
+    fn test_route_respects_recursion_and_order() {
+        let flat = watched_ruleset("flat", Path::new("/src"), false);
+        let deep = watched_ruleset("deep", Path::new("/src"), true);
+        let other = watched_ruleset("other", Path::new("/other"), false);
+        let rulesets = vec![flat, deep, other];
+
+        let paths = vec![
+            PathBuf::from("/src/a.txt"),
+            PathBuf::from("/src/sub/b.txt"),
+            PathBuf::from("/elsewhere/c.txt"),
+        ];
+        let routed = route(&rulesets, &paths);
+        assert_eq!(routed.len(), 2);
+        assert_eq!(routed[0].0.id, "flat");
+        assert_eq!(routed[0].1, vec![PathBuf::from("/src/a.txt")]);
+        assert_eq!(routed[1].0.id, "deep");
+        assert_eq!(routed[1].1.len(), 2);
+    }
+
+    #[test]
+    fn test_watcher_reports_new_files() {
+        let src = tempfile::tempdir().unwrap();
+        fs::write(src.path().join("existing.txt"), "before").unwrap();
+        let arrived: std::sync::Arc<Mutex<Vec<PathBuf>>> = Default::default();
+
+        let sink = arrived.clone();
+        let watcher = RulesetWatcher::start(
+            vec![watched_ruleset("w", src.path(), false)],
+            Duration::from_millis(100),
+            move |_, paths| sink.lock().unwrap().extend(paths),
+            |_| {},
+        )
+        .unwrap();
+        assert!(watcher.failures().is_empty());
+
+        let new_file = src.path().join("new.txt");
+        fs::write(&new_file, "after").unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while arrived.lock().unwrap().is_empty() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        let arrived = arrived.lock().unwrap();
+        assert!(arrived
+            .iter()
+            .any(|p| p.file_name() == new_file.file_name()));
+        assert!(!arrived
+            .iter()
+            .any(|p| p.file_name().is_some_and(|n| n == "existing.txt")));
+    }
+
+    #[test]
+    fn test_watcher_skips_unwatched_rulesets() {
+        let src = tempfile::tempdir().unwrap();
+        let mut rs = watched_ruleset("w", src.path(), false);
+        rs.watch = false;
+        let missing = watched_ruleset("missing", &src.path().join("nope"), false);
+
+        let watcher = RulesetWatcher::start(
+            vec![rs, missing],
+            Duration::from_millis(100),
+            |_, _| {},
+            |_| {},
+        )
+        .unwrap();
+        // 存在しないディレクトリだけが失敗として報告される
+        assert_eq!(watcher.failures().len(), 1);
+        assert!(watcher.failures()[0].contains("nope"));
+    }
+}
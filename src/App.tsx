@@ -8,6 +8,9 @@ import { RulesetList } from "./components/RulesetList";
 import { RulesetEditDialog } from "./components/RulesetEditDialog";
 import { ExecutionResultDialog } from "./components/ExecutionResultDialog";
 import { LoadingOverlay } from "./components/LoadingOverlay";
+import { HistoryDialog } from "./components/HistoryDialog";
+import { PreviewDialog } from "./components/PreviewDialog";
+import { Toast } from "./components/Toast";
 import { useRulesetStore } from "./store/rulesetStore";
 import type { Ruleset, ExecutionResult } from "./lib/types";
 import * as commands from "./lib/commands";
@@ -32,6 +35,11 @@ function App() {
   const [executionResults, setExecutionResults] = useState<ExecutionResult[] | null>(
     null,
   );
+  const [showHistory, setShowHistory] = useState(false);
+  const [previewTarget, setPreviewTarget] = useState<string | null | undefined>(
+    undefined, // undefined = closed, null = all rulesets, string = ruleset id
+  );
+  const [backgroundErrors, setBackgroundErrors] = useState<string[]>([]);
   const [executing, setExecuting] = useState(false);
   const [executingFile, setExecutingFile] = useState<string | null>(null);
   const [executingRuleset, setExecutingRuleset] = useState<string | null>(null);
@@ -90,6 +98,37 @@ function App() {
     };
   }, []);
 
+  // 監視・スケジュールによる自動実行の結果は、結果ダイアログに追加して表示する
+  useEffect(() => {
+    const unlistens = ["watch-executed", "schedule-executed"].map((name) =>
+      listen<ExecutionResult>(name, (event) => {
+        setExecutionResults((prev) => [...(prev ?? []), event.payload]);
+      }),
+    );
+    return () => {
+      unlistens.forEach((unlisten) => unlisten.then((f) => f()));
+    };
+  }, []);
+
+  // 監視・スケジュール・履歴の記録など、操作の結果として返せないエラーを表示する
+  useEffect(() => {
+    const unlisten = listen<string>("background-error", (event) => {
+      setBackgroundErrors((prev) => [...prev, event.payload]);
+    });
+    // 起動直後など購読を始める前に起きたエラーはバックエンドが保持している
+    unlisten
+      .then(() => commands.takeBackgroundErrors())
+      .then((pending) => {
+        if (pending.length > 0) setBackgroundErrors((prev) => [...pending, ...prev]);
+      })
+      .catch(console.error);
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
+
+  const dismissBackgroundErrors = useCallback(() => setBackgroundErrors([]), []);
+
   useEffect(() => {
     if (!executing) return;
 
@@ -226,8 +265,10 @@ function App() {
       <Toolbar
         onCreateNew={() => setEditingRuleset(null)}
         onExecuteAll={handleExecuteAll}
+        onPreviewAll={() => setPreviewTarget(null)}
         onImport={handleImport}
         onExport={handleExport}
+        onShowHistory={() => setShowHistory(true)}
         executing={executing}
         darkMode={darkMode}
         onToggleDarkMode={toggleDarkMode}
@@ -247,6 +288,7 @@ function App() {
             onEdit={(rs) => setEditingRuleset(rs)}
             onDelete={handleDelete}
             onDuplicate={duplicateRuleset}
+            onPreview={setPreviewTarget}
             onReorder={reorderRulesets}
             executing={executing}
           />
@@ -279,6 +321,15 @@ function App() {
         />
       )}
 
+      {showHistory && <HistoryDialog onClose={() => setShowHistory(false)} />}
+
+      {previewTarget !== undefined && (
+        <PreviewDialog
+          rulesetId={previewTarget}
+          onClose={() => setPreviewTarget(undefined)}
+        />
+      )}
+
       {executing && (
         <LoadingOverlay
           currentFile={executingFile}
@@ -287,6 +338,8 @@ function App() {
           onCancel={handleCancelExecution}
         />
       )}
+
+      <Toast messages={backgroundErrors} onDismiss={dismissBackgroundErrors} />
     </main>
   );
 }
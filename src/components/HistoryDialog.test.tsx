@@ -0,0 +1,68 @@
+import { describe, it, expect, vi, beforeEach } from "vitest";
+import { screen, waitFor } from "@testing-library/react";
+import userEvent from "@testing-library/user-event";
+import { HistoryDialog } from "./HistoryDialog";
+import { renderWithProviders } from "../test/helpers/renderWithProviders";
+import type { RunRecord } from "../lib/types";
+import * as commands from "../lib/commands";
+
+vi.mock("../lib/commands", () => ({
+  listHistory: vi.fn(),
+  undoHistoryRun: vi.fn().mockResolvedValue([{ Ok: null }]),
+  undoHistoryFile: vi.fn().mockResolvedValue(undefined),
+}));
+
+const run: RunRecord = {
+  run_id: "2f1c3c9e-6f0a-4c55-9a57-0b9b3c1d2e4f",
+  timestamp: "2025-01-01T12:00:00+09:00",
+  ruleset_id: "test-uuid-1",
+  ruleset_name: "テストルールセット",
+  action: "move",
+  files: [
+    {
+      filename: "photo.jpg",
+      source_path: "C:\\Users\\test\\Downloads\\photo.jpg",
+      destination_path: "C:\\Users\\test\\Documents\\photo.jpg",
+      undone: false,
+    },
+  ],
+};
+
+describe("HistoryDialog", () => {
+  beforeEach(() => {
+    vi.clearAllMocks();
+  });
+
+  it("履歴がない場合はその旨を表示する", async () => {
+    vi.mocked(commands.listHistory).mockResolvedValue([]);
+    renderWithProviders(<HistoryDialog onClose={vi.fn()} />);
+    expect(await screen.findByTestId("history-empty")).toBeInTheDocument();
+  });
+
+  it("実行全体を元に戻すと履歴を読み直す", async () => {
+    vi.mocked(commands.listHistory)
+      .mockResolvedValueOnce([run])
+      .mockResolvedValueOnce([{ ...run, files: [{ ...run.files[0], undone: true }] }]);
+    renderWithProviders(<HistoryDialog onClose={vi.fn()} />);
+
+    await userEvent.click(await screen.findByTestId("btn-history-undo-run"));
+
+    expect(commands.undoHistoryRun).toHaveBeenCalledWith(run.run_id);
+    await waitFor(() => {
+      expect(screen.queryByTestId("btn-history-undo-run")).not.toBeInTheDocument();
+    });
+    expect(screen.getByText("元に戻しました")).toBeInTheDocument();
+  });
+
+  it("ファイルを個別に元に戻せる", async () => {
+    vi.mocked(commands.listHistory).mockResolvedValue([run]);
+    renderWithProviders(<HistoryDialog onClose={vi.fn()} />);
+
+    await userEvent.click(await screen.findByRole("button", { name: "↩ 戻す" }));
+
+    expect(commands.undoHistoryFile).toHaveBeenCalledWith(
+      run.run_id,
+      run.files[0].source_path,
+    );
+  });
+});
@@ -0,0 +1,179 @@
+import { useCallback, useEffect, useState } from "react";
+import { useTranslation } from "react-i18next";
+import type { RunRecord } from "../lib/types";
+import * as commands from "../lib/commands";
+
+interface HistoryDialogProps {
+  onClose: () => void;
+}
+
+export function HistoryDialog({ onClose }: HistoryDialogProps) {
+  const { t } = useTranslation();
+  const [runs, setRuns] = useState<RunRecord[] | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  const refresh = useCallback(async () => {
+    try {
+      setRuns(await commands.listHistory());
+    } catch (e) {
+      setError(String(e));
+    }
+  }, []);
+
+  useEffect(() => {
+    refresh();
+  }, [refresh]);
+
+  return (
+    <div
+      data-testid="history-dialog"
+      className="fixed inset-0 bg-black/40 dark:bg-black/60 flex items-center justify-center z-50 backdrop-blur-[6px]"
+    >
+      <div className="bg-white dark:bg-slate-900 rounded-2xl shadow-[0_20px_60px_rgba(0,0,0,0.25),0_8px_16px_rgba(0,0,0,0.10)] w-full max-w-2xl max-h-[80vh] flex flex-col overflow-hidden border border-slate-200 dark:border-slate-700/60">
+        <div className="px-5 py-4 border-b border-slate-200 dark:border-slate-800 flex items-center justify-between flex-none">
+          <h2 className="text-base font-semibold text-slate-900 dark:text-slate-100">
+            {t("history.title")}
+          </h2>
+          <button
+            onClick={onClose}
+            aria-label={t("result.close")}
+            className="w-7 h-7 flex items-center justify-center rounded-lg text-slate-400 hover:text-slate-600 dark:text-slate-500 dark:hover:text-slate-300 hover:bg-slate-100 dark:hover:bg-slate-800 transition-colors text-lg leading-none"
+          >
+            ×
+          </button>
+        </div>
+
+        <div className="px-5 py-4 space-y-6 flex-1 overflow-y-auto">
+          {error && (
+            <p className="text-sm text-red-600 dark:text-red-400">
+              {t("history.loadError", { error })}
+            </p>
+          )}
+          {runs && runs.length === 0 && (
+            <p
+              data-testid="history-empty"
+              className="text-sm text-slate-500 dark:text-slate-400"
+            >
+              {t("history.empty")}
+            </p>
+          )}
+          {runs?.map((run) => (
+            <HistoryRun key={run.run_id} run={run} onChange={refresh} />
+          ))}
+        </div>
+
+        <div className="flex justify-end px-5 py-4 border-t border-slate-200 dark:border-slate-800 flex-none">
+          <button
+            data-testid="btn-history-close"
+            onClick={onClose}
+            className="px-4 py-1.5 bg-slate-700 hover:bg-slate-600 dark:bg-slate-700 dark:hover:bg-slate-600 text-white rounded-lg text-sm font-medium transition-colors"
+          >
+            {t("result.close")}
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
+
+interface HistoryRunProps {
+  run: RunRecord;
+  onChange: () => Promise<void>;
+}
+
+function HistoryRun({ run, onChange }: HistoryRunProps) {
+  const { t } = useTranslation();
+  const [failed, setFailed] = useState(0);
+
+  const isMove = run.action === "move";
+  const remaining = run.files.filter((f) => !f.undone).length;
+  const actionLabel = isMove ? t("ruleset.move") : t("ruleset.copy");
+
+  async function handleUndoRun() {
+    try {
+      const results = await commands.undoHistoryRun(run.run_id);
+      setFailed(results.filter((r) => "Err" in r).length);
+    } catch {
+      setFailed(remaining);
+    }
+    await onChange();
+  }
+
+  async function handleUndoFile(source: string) {
+    try {
+      await commands.undoHistoryFile(run.run_id, source);
+      setFailed(0);
+    } catch {
+      setFailed(1);
+    }
+    await onChange();
+  }
+
+  return (
+    <div data-testid="history-run">
+      <div className="flex items-center justify-between mb-3">
+        <div>
+          <span className="font-medium text-slate-900 dark:text-slate-100">
+            {run.ruleset_name}
+          </span>
+          <span className="text-xs text-slate-500 dark:text-slate-400 ml-2">
+            {new Date(run.timestamp).toLocaleString()} | {actionLabel} |{" "}
+            {t("result.items", { count: run.files.length })}
+          </span>
+        </div>
+        {isMove && remaining > 0 && (
+          <button
+            data-testid="btn-history-undo-run"
+            onClick={handleUndoRun}
+            className="text-xs px-2.5 py-1 border border-amber-300 dark:border-amber-700 text-amber-600 dark:text-amber-400 rounded-lg hover:bg-amber-50 dark:hover:bg-amber-900/20 transition-colors"
+          >
+            {t("result.undoAll")}
+          </button>
+        )}
+      </div>
+
+      {failed > 0 && (
+        <p className="text-xs text-red-600 dark:text-red-400 mb-2">
+          {t("history.undoFailed", { count: failed })}
+        </p>
+      )}
+
+      <div className="border border-slate-200 dark:border-slate-700/60 rounded-xl divide-y divide-slate-100 dark:divide-slate-800 text-xs overflow-hidden">
+        {run.files.map((file) => (
+          <div
+            key={file.source_path}
+            className="flex items-center gap-2 px-3 py-2 bg-white dark:bg-slate-900"
+          >
+            {file.undone ? (
+              <span className="text-amber-500">↩</span>
+            ) : (
+              <span className="text-emerald-500">✓</span>
+            )}
+            <div className="flex-1 min-w-0">
+              <div className="font-medium text-slate-800 dark:text-slate-200 truncate">
+                {file.filename}
+              </div>
+              <div className="text-slate-400 dark:text-slate-600 truncate font-mono">
+                {file.source_path} → {file.destination_path}
+              </div>
+            </div>
+            {file.undone ? (
+              <span className="text-slate-400 dark:text-slate-500 whitespace-nowrap">
+                {t("result.undone")}
+              </span>
+            ) : (
+              isMove && (
+                <button
+                  onClick={() => handleUndoFile(file.source_path)}
+                  className="text-amber-500 hover:text-amber-600 dark:text-amber-400 dark:hover:text-amber-300 whitespace-nowrap transition-colors"
+                >
+                  ↩ {t("result.undoFile")}
+                </button>
+              )
+            )}
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
@@ -101,6 +101,13 @@ export function LoadingOverlay({
             </div>
           )}
 
+          {/* Processed count when the total was not counted */}
+          {progress && progress.total === 0 && progress.current > 0 && (
+            <p className="text-xs text-slate-500 dark:text-slate-400 tabular-nums">
+              {t("execution.progressCount", { current: progress.current })}
+            </p>
+          )}
+
           {/* Cancel button */}
           <button
             data-testid="btn-cancel-execution"
@@ -0,0 +1,70 @@
+import { describe, it, expect, vi, beforeEach } from "vitest";
+import { screen } from "@testing-library/react";
+import { PreviewDialog } from "./PreviewDialog";
+import { renderWithProviders } from "../test/helpers/renderWithProviders";
+import type { ExecutionPlan } from "../lib/types";
+import * as commands from "../lib/commands";
+
+vi.mock("../lib/commands", () => ({
+  previewRuleset: vi.fn(),
+  previewAll: vi.fn(),
+}));
+
+const plan: ExecutionPlan = {
+  ruleset_id: "test-uuid-1",
+  ruleset_name: "テストルールセット",
+  action: "move",
+  operations: [
+    {
+      filename: "photo.jpg",
+      source_path: "C:\\Users\\test\\Downloads\\photo.jpg",
+      destination_path: "C:\\Users\\test\\Documents\\photo.jpg",
+      action: "Move",
+      reason: null,
+      conflict: null,
+    },
+    {
+      filename: "notes.jpg",
+      source_path: "C:\\Users\\test\\Downloads\\notes.jpg",
+      destination_path: "C:\\Users\\test\\Documents\\notes.jpg",
+      action: "Skip",
+      reason: "File with same name exists at destination",
+      conflict: "Skipped",
+    },
+  ],
+};
+
+describe("PreviewDialog", () => {
+  beforeEach(() => {
+    vi.clearAllMocks();
+  });
+
+  it("ルールセットの移動元と移動先の一覧を表示する", async () => {
+    vi.mocked(commands.previewRuleset).mockResolvedValue(plan);
+    renderWithProviders(<PreviewDialog rulesetId="test-uuid-1" onClose={vi.fn()} />);
+
+    const operations = await screen.findAllByTestId("preview-operation");
+    expect(commands.previewRuleset).toHaveBeenCalledWith("test-uuid-1");
+    expect(operations).toHaveLength(2);
+    // toHaveTextContent はサブストリングマッチのためバックスラッシュも安全に検索できる
+    expect(operations[0]).toHaveTextContent("移動");
+    expect(operations[0]).toHaveTextContent(
+      "C:\\Users\\test\\Downloads\\photo.jpg → C:\\Users\\test\\Documents\\photo.jpg",
+    );
+    expect(operations[1]).toHaveTextContent("スキップ");
+    expect(operations[1]).toHaveTextContent("File with same name exists at destination");
+  });
+
+  it("rulesetId が null の場合はすべてのルールセットをプレビューする", async () => {
+    vi.mocked(commands.previewAll).mockResolvedValue([
+      plan,
+      { ...plan, ruleset_id: "test-uuid-2", operations: [] },
+    ]);
+    renderWithProviders(<PreviewDialog rulesetId={null} onClose={vi.fn()} />);
+
+    expect(await screen.findAllByTestId("preview-plan")).toHaveLength(2);
+    expect(commands.previewAll).toHaveBeenCalledOnce();
+    expect(commands.previewRuleset).not.toHaveBeenCalled();
+    expect(screen.getByTestId("preview-empty")).toBeInTheDocument();
+  });
+});
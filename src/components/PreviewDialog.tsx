@@ -0,0 +1,134 @@
+import { useEffect, useState } from "react";
+import { useTranslation } from "react-i18next";
+import type { ExecutionPlan, PlannedAction } from "../lib/types";
+import * as commands from "../lib/commands";
+
+interface PreviewDialogProps {
+  // null previews every enabled ruleset, like Execute All
+  rulesetId: string | null;
+  onClose: () => void;
+}
+
+export function PreviewDialog({ rulesetId, onClose }: PreviewDialogProps) {
+  const { t } = useTranslation();
+  const [plans, setPlans] = useState<ExecutionPlan[] | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    const load =
+      rulesetId === null
+        ? commands.previewAll()
+        : commands.previewRuleset(rulesetId).then((plan) => [plan]);
+    load.then(setPlans).catch((e) => setError(String(e)));
+  }, [rulesetId]);
+
+  return (
+    <div
+      data-testid="preview-dialog"
+      className="fixed inset-0 bg-black/40 dark:bg-black/60 flex items-center justify-center z-50 backdrop-blur-[6px]"
+    >
+      <div className="bg-white dark:bg-slate-900 rounded-2xl shadow-[0_20px_60px_rgba(0,0,0,0.25),0_8px_16px_rgba(0,0,0,0.10)] w-full max-w-2xl max-h-[80vh] flex flex-col overflow-hidden border border-slate-200 dark:border-slate-700/60">
+        <div className="px-5 py-4 border-b border-slate-200 dark:border-slate-800 flex items-center justify-between flex-none">
+          <h2 className="text-base font-semibold text-slate-900 dark:text-slate-100">
+            {t("preview.title")}
+          </h2>
+          <button
+            onClick={onClose}
+            aria-label={t("result.close")}
+            className="w-7 h-7 flex items-center justify-center rounded-lg text-slate-400 hover:text-slate-600 dark:text-slate-500 dark:hover:text-slate-300 hover:bg-slate-100 dark:hover:bg-slate-800 transition-colors text-lg leading-none"
+          >
+            ×
+          </button>
+        </div>
+
+        <div className="px-5 py-4 space-y-6 flex-1 overflow-y-auto">
+          {error && (
+            <p className="text-sm text-red-600 dark:text-red-400">
+              {t("preview.loadError", { error })}
+            </p>
+          )}
+          {plans?.map((plan) => <PreviewPlan key={plan.ruleset_id} plan={plan} />)}
+        </div>
+
+        <div className="flex justify-end px-5 py-4 border-t border-slate-200 dark:border-slate-800 flex-none">
+          <button
+            data-testid="btn-preview-close"
+            onClick={onClose}
+            className="px-4 py-1.5 bg-slate-700 hover:bg-slate-600 dark:bg-slate-700 dark:hover:bg-slate-600 text-white rounded-lg text-sm font-medium transition-colors"
+          >
+            {t("result.close")}
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
+
+function PreviewPlan({ plan }: { plan: ExecutionPlan }) {
+  const { t } = useTranslation();
+
+  const actionLabels: Record<PlannedAction, string> = {
+    Move: t("ruleset.move"),
+    Copy: t("ruleset.copy"),
+    Skip: t("result.skipped"),
+    Error: t("result.errors"),
+  };
+  const actionColors: Record<PlannedAction, string> = {
+    Move: "text-blue-600 dark:text-blue-400",
+    Copy: "text-violet-600 dark:text-violet-400",
+    Skip: "text-slate-400 dark:text-slate-500",
+    Error: "text-red-600 dark:text-red-400",
+  };
+
+  return (
+    <div data-testid="preview-plan">
+      <div className="mb-3">
+        <span className="font-medium text-slate-900 dark:text-slate-100">
+          {plan.ruleset_name}
+        </span>
+        <span className="text-xs text-slate-500 dark:text-slate-400 ml-2">
+          {t("result.items", { count: plan.operations.length })}
+        </span>
+      </div>
+
+      {plan.operations.length === 0 ? (
+        <p
+          data-testid="preview-empty"
+          className="text-sm text-slate-500 dark:text-slate-400"
+        >
+          {t("preview.empty")}
+        </p>
+      ) : (
+        <div className="border border-slate-200 dark:border-slate-700/60 rounded-xl divide-y divide-slate-100 dark:divide-slate-800 text-xs overflow-hidden">
+          {plan.operations.map((op, i) => (
+            <div
+              key={i}
+              data-testid="preview-operation"
+              className="flex items-center gap-2 px-3 py-2 bg-white dark:bg-slate-900"
+            >
+              <span
+                className={`whitespace-nowrap font-medium ${actionColors[op.action]}`}
+              >
+                {actionLabels[op.action]}
+              </span>
+              <div className="flex-1 min-w-0">
+                <div className="font-medium text-slate-800 dark:text-slate-200 truncate">
+                  {op.filename}
+                </div>
+                <div className="text-slate-400 dark:text-slate-600 truncate font-mono">
+                  {op.source_path}
+                  {op.destination_path && ` → ${op.destination_path}`}
+                </div>
+                {op.reason && (
+                  <div className="text-slate-500 dark:text-slate-400 truncate">
+                    {op.reason}
+                  </div>
+                )}
+              </div>
+            </div>
+          ))}
+        </div>
+      )}
+    </div>
+  );
+}
@@ -111,6 +111,48 @@ describe("RegexTesterPanel", () => {
     );
   });
 
+  it("テンプレート変数の | 変換が解決後のパスに反映される", async () => {
+    renderPanel({
+      pattern: "^(?P<label>[A-Za-z]+)_(?P<num>\\d+)(?P<client>x?)\\.pdf$",
+      destinationDir: "C:/sorted/{label|lower}/{num|pad:4}/{client|default:unknown}",
+    });
+    await userEvent.type(screen.getByTestId("regex-sample-input"), "Invoices_42.pdf");
+    await waitFor(() => {
+      expect(screen.getByTestId("regex-resolved-path")).toBeInTheDocument();
+    });
+    expect(screen.getByTestId("regex-resolved-path")).toHaveTextContent(
+      "C:/sorted/invoices/0042/unknown",
+    );
+  });
+
+  it("date・num・range 変換が解決後のパスに反映される", async () => {
+    renderPanel({
+      pattern: "^(?P<date>\\d{8})_(?P<id>\\d+)\\.pdf$",
+      destinationDir: "C:/sorted/{date|date:%Y%m%d->%Y/%m}/{id|num|range:1000}",
+    });
+    await userEvent.type(screen.getByTestId("regex-sample-input"), "20250107_001234.pdf");
+    await waitFor(() => {
+      expect(screen.getByTestId("regex-resolved-path")).toBeInTheDocument();
+    });
+    expect(screen.getByTestId("regex-resolved-path")).toHaveTextContent(
+      "C:/sorted/2025/01/1000-1999",
+    );
+  });
+
+  it("{{ と }} は括弧そのものとして解決後のパスに表示される", async () => {
+    renderPanel({
+      pattern: "^(?P<label>[a-z]+)_\\d+\\.pdf$",
+      destinationDir: "C:/sorted/{{label}}/{label}",
+    });
+    await userEvent.type(screen.getByTestId("regex-sample-input"), "invoice_1.pdf");
+    await waitFor(() => {
+      expect(screen.getByTestId("regex-resolved-path")).toBeInTheDocument();
+    });
+    expect(screen.getByTestId("regex-resolved-path")).toHaveTextContent(
+      "C:/sorted/{label}/invoice",
+    );
+  });
+
   it("sourceDir が空のときソースフォルダ読み込みボタンが表示されない", () => {
     renderPanel({ sourceDir: "" });
     expect(screen.queryByTestId("regex-load-files-btn")).not.toBeInTheDocument();
@@ -25,12 +25,74 @@ function buildJsRegex(pattern: string): RegexBuildResult {
   }
 }
 
+/**
+ * `date:IN->OUT` の変換。プレビュー用のため %Y・%m・%d・%H・%M・%S のみ解釈し、
+ * それ以外の書式を含む場合は `undefined` を返す
+ */
+function convertDate(value: string, formats: string): string | undefined {
+  const [input, output] = formats.split("->");
+  if (output === undefined || /%[^YmdHMS%]/.test(input + output)) return undefined;
+  const keys: string[] = [];
+  const pattern = input.replace(/%([YmdHMS%])|[.*+?^${}()|[\]\\]/g, (m, key?: string) => {
+    if (key === undefined) return `\\${m}`;
+    if (key === "%") return "%";
+    keys.push(key);
+    return key === "Y" ? "(\\d{4})" : "(\\d{2})";
+  });
+  const match = new RegExp(`^${pattern}$`).exec(value);
+  if (!match) return undefined;
+  const fields: Record<string, string> = { Y: "", m: "01", d: "01", H: "00", M: "00", S: "00" };
+  keys.forEach((key, i) => (fields[key] = match[i + 1]));
+  if (!fields.Y) return undefined;
+  return output.replace(/%([YmdHMS%])/g, (_, key: string) => (key === "%" ? "%" : fields[key]));
+}
+
+/** `{name|lower|pad:3}` の `|` 以降の変換を適用する（Rust 側の TemplateFilter と同じ仕様） */
+function applyTemplateFilter(value: string | undefined, filter: string): string | undefined {
+  const [name, ...args] = filter.split(":");
+  if (name === "default") return value ? value : args.join(":");
+  if (value === undefined) return undefined;
+  switch (name) {
+    case "lower":
+      return value.toLowerCase();
+    case "upper":
+      return value.toUpperCase();
+    case "pad":
+      return value.padStart(Number(args[0]) || 0, "0");
+    case "slice":
+      return [...value]
+        .slice(Number(args[0]) || 0, args[1] !== undefined ? Number(args[1]) : undefined)
+        .join("");
+    case "date":
+      return convertDate(value, args.join(":"));
+    case "num":
+      return /^\d+$/.test(value.trim()) ? BigInt(value.trim()).toString() : undefined;
+    case "range": {
+      const size = /^\d+$/.test(args[0] ?? "") ? BigInt(args[0]) : 0n;
+      if (size === 0n || !/^\d+$/.test(value.trim())) return undefined;
+      const low = (BigInt(value.trim()) / size) * size;
+      return `${low}-${low + size - 1n}`;
+    }
+    default:
+      return undefined;
+  }
+}
+
+/** `{{`・`}}` は `{`・`}` そのものとして出力する */
 function resolveTemplate(template: string, groups: Record<string, string>): string {
-  return template.replace(/\{([^}]+)\}/g, (_, name) => groups[name] ?? `{${name}}`);
+  return template.replace(
+    /\{\{|\}\}|\{([^}]+)\}/g,
+    (match, expr: string | undefined) => {
+      if (expr === undefined) return match[0];
+      const [name, ...filters] = expr.split("|");
+      const value = filters.reduce(applyTemplateFilter, groups[name] as string | undefined);
+      return value ?? match;
+    },
+  );
 }
 
 function hasTemplateVars(s: string): boolean {
-  return /\{[^}]+\}/.test(s);
+  return /\{[^}]+\}/.test(s.replace(/\{\{|\}\}/g, ""));
 }
 
 export function RegexTesterPanel({
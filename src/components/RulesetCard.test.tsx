@@ -21,6 +21,7 @@ function renderCard(overrides?: {
     onExecute: vi.fn(),
     onEdit: vi.fn(),
     onDelete: vi.fn(),
+    onPreview: vi.fn(),
     executing: false,
     ...overrides,
   };
@@ -79,6 +80,14 @@ describe("RulesetCard", () => {
     expect(screen.getByTestId("ruleset-menu-dropdown")).toBeTruthy();
   });
 
+  it("「プレビュー」をクリックすると onPreview が呼ばれメニューが閉じる", async () => {
+    const { onPreview } = renderCard();
+    await userEvent.click(screen.getByTestId("ruleset-menu"));
+    await userEvent.click(screen.getByTestId("ruleset-preview"));
+    expect(onPreview).toHaveBeenCalledWith(defaultRuleset.id);
+    expect(screen.queryByTestId("ruleset-menu-dropdown")).toBeNull();
+  });
+
   it("「対象フォルダを開く」をクリックすると openInExplorer が source_dir で呼ばれる", async () => {
     const { openInExplorer } = await import("../lib/commands");
     renderCard();
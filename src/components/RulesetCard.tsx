@@ -11,6 +11,7 @@ interface RulesetCardProps {
   onEdit: (ruleset: Ruleset) => void;
   onDelete: (id: string) => void;
   onDuplicate: (id: string) => void;
+  onPreview: (id: string) => void;
   executing: boolean;
   onMenuOpenChange?: (open: boolean) => void;
 }
@@ -23,6 +24,7 @@ export function RulesetCard({
   onEdit,
   onDelete,
   onDuplicate,
+  onPreview,
   executing,
   onMenuOpenChange,
 }: RulesetCardProps) {
@@ -60,6 +62,11 @@ export function RulesetCard({
     onDuplicate(ruleset.id);
   }
 
+  function handlePreview() {
+    changeMenuOpen(false);
+    onPreview(ruleset.id);
+  }
+
   async function openSourceDir() {
     changeMenuOpen(false);
     if (ruleset.source_dir) await openInExplorer(ruleset.source_dir);
@@ -186,6 +193,13 @@ export function RulesetCard({
               data-testid="ruleset-menu-dropdown"
               className={`absolute right-0 w-48 bg-white dark:bg-slate-900 border border-slate-200/80 dark:border-slate-700 rounded-xl shadow-[0_8px_24px_rgba(0,0,0,0.12),0_2px_8px_rgba(0,0,0,0.08)] z-10 py-1 ${menuDropsUp ? "bottom-full mb-1" : "top-full mt-1"}`}
             >
+              <button
+                data-testid="ruleset-preview"
+                onClick={handlePreview}
+                className="w-full text-left px-3 py-2 text-sm text-slate-700 dark:text-slate-300 hover:bg-slate-50 dark:hover:bg-slate-800 transition-colors"
+              >
+                {t("ruleset.preview")}
+              </button>
               <button
                 data-testid="ruleset-duplicate"
                 onClick={handleDuplicate}
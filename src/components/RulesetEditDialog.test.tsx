@@ -4,15 +4,17 @@ import userEvent from "@testing-library/user-event";
 import { RulesetEditDialog } from "./RulesetEditDialog";
 import { renderWithProviders } from "../test/helpers/renderWithProviders";
 import { defaultRuleset } from "../test/mocks/fixtures";
+import * as commands from "../lib/commands";
 
 // @tauri-apps/plugin-dialog の confirm をモック化
 vi.mock("@tauri-apps/plugin-dialog", () => ({
   confirm: vi.fn().mockResolvedValue(true),
 }));
 
-// RegexTesterPanel 内の listSourceFiles をモック化
+// RegexTesterPanel 内の listSourceFiles と、保存時の validateRuleset をモック化
 vi.mock("../lib/commands", () => ({
   listSourceFiles: vi.fn().mockResolvedValue([]),
+  validateRuleset: vi.fn().mockResolvedValue([]),
 }));
 
 function renderDialog(overrides?: { ruleset?: typeof defaultRuleset | null }) {
@@ -156,13 +158,13 @@ describe("RulesetEditDialog", () => {
     expect(onCancel).toHaveBeenCalledOnce();
   });
 
-  it("保存先にテンプレート変数を使い正規表現以外のフィルタで保存するとバリデーションエラーが表示される", async () => {
+  it("保存先にテンプレート変数を使いファイル名フィルタなしで保存するとバリデーションエラーが表示される", async () => {
     renderDialog();
     await userEvent.type(screen.getByTestId("field-name"), "テストルール");
     await userEvent.type(screen.getByTestId("field-source-dir"), "C:\\src");
     // テンプレート変数を含む destination_dir（{} は userEvent の特殊文字のため {{ }} でエスケープ）
     await userEvent.type(screen.getByTestId("field-dest-dir"), "C:\\dst\\{{label}}");
-    // 拡張子フィルタを追加（正規表現なし）
+    // 拡張子フィルタのみを追加（ファイル名フィルタなし）
     await userEvent.type(screen.getByTestId("extension-input"), ".zip");
     await userEvent.click(screen.getByTestId("btn-extension-add"));
 
@@ -173,4 +175,41 @@ describe("RulesetEditDialog", () => {
     });
     expect(screen.getByTestId("validation-errors")).toHaveTextContent("正規表現");
   });
+
+  it("ファイル名に {counter} を使うと連番の設定が表示される", () => {
+    renderDialog({ ruleset: { ...defaultRuleset, rename: "scan_{counter:4}.{ext}" } });
+    expect(screen.getByTestId("select-counter-sort")).toHaveValue("name");
+    expect(screen.getByTestId("checkbox-counter-resume")).not.toBeChecked();
+  });
+
+  it("バックエンドの検証でエラーになった項目が強調表示され、保存されない", async () => {
+    vi.mocked(commands.validateRuleset).mockResolvedValueOnce([
+      {
+        field: "filters.filename.pattern",
+        severity: "error",
+        code: "invalid_pattern",
+        message: "invalid pattern '(unclosed'",
+      },
+    ]);
+    const { onSave } = renderDialog({ ruleset: defaultRuleset });
+
+    await userEvent.click(screen.getByTestId("btn-save"));
+
+    await waitFor(() => {
+      expect(screen.getByTestId("field-filename-pattern")).toHaveAttribute(
+        "aria-invalid",
+        "true",
+      );
+    });
+    expect(screen.getByTestId("issues-filters.filename")).toHaveTextContent(
+      "invalid pattern '(unclosed'",
+    );
+    expect(screen.getByTestId("field-name")).toHaveAttribute("aria-invalid", "false");
+    expect(onSave).not.toHaveBeenCalled();
+  });
+
+  it("{counter} を使わない場合は連番の設定が表示されない", () => {
+    renderDialog({ ruleset: defaultRuleset });
+    expect(screen.queryByTestId("select-counter-sort")).not.toBeInTheDocument();
+  });
 });
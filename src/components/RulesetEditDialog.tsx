@@ -1,7 +1,21 @@
 import { useCallback, useEffect, useRef, useState } from "react";
 import { useTranslation } from "react-i18next";
 import { confirm } from "@tauri-apps/plugin-dialog";
-import type { Ruleset, Action, MatchType, Filters } from "../lib/types";
+import type {
+  Ruleset,
+  Action,
+  ConflictPolicy,
+  MatchType,
+  Filters,
+  Schedule,
+  SizeRange,
+  DateTimeRange,
+  FilenameFilter,
+  CounterOptions,
+  CounterSort,
+  ValidationIssue,
+} from "../lib/types";
+import { validateRuleset } from "../lib/commands";
 import { RegexTesterPanel } from "./RegexTesterPanel";
 import { Toast } from "./Toast";
 
@@ -32,6 +46,99 @@ function datetimeLocalToRfc3339(value: string | null): string | null {
   return `${value}:00${sign}${h}:${m}`;
 }
 
+/** 日時条件のいずれの項目も指定されていないか */
+function isRangeEmpty(range: DateTimeRange): boolean {
+  return !range.start && !range.end && !range.older_than && !range.newer_than;
+}
+
+/** 日時条件の一部を更新する。すべての項目が空になった場合は条件自体を外す（null） */
+function updateRange(
+  current: DateTimeRange | null | undefined,
+  updates: Partial<DateTimeRange>,
+): DateTimeRange | null {
+  const next: DateTimeRange = { start: null, end: null, ...current, ...updates };
+  return isRangeEmpty(next) ? null : next;
+}
+
+const DATE_FIELDS = ["created_at", "modified_at", "accessed_at"] as const;
+
+/** 保存先パスで使える組み込みの日時変数（`{mtime:%Y}` のように chrono の書式を指定する） */
+const DATE_TEMPLATE_VARS = ["mtime", "ctime", "now"];
+
+/** 保存先パスで使える、ファイルやルールセットから値を作る組み込み変数 */
+const FILE_TEMPLATE_VARS = ["stem", "ext", "parent", "ruleset", "source_dir_name"];
+const COUNTER_TEMPLATE_VAR = "counter";
+const COUNTER_SORTS: CounterSort[] = ["name", "modified", "created", "size"];
+const DEFAULT_COUNTER: CounterOptions = {
+  sort: "name",
+  descending: false,
+  start: 1,
+  resume: false,
+};
+
+/** テンプレート中の変数（`{mtime:%Y|lower}` の `mtime:%Y`）。`{{`・`}}` は文字としての括弧なので除く */
+function templateVarNames(s: string): string[] {
+  return [...s.replace(/\{\{|\}\}/g, "").matchAll(/\{([^}|]+)[^}]*\}/g)].map(
+    ([, name]) => name,
+  );
+}
+
+/** フォームの内容を保存する形に変換する */
+function toSavedRuleset(form: Ruleset): Ruleset {
+  const convertRange = (range: DateTimeRange | null | undefined) => {
+    if (!range) return null;
+    return {
+      ...range,
+      start: datetimeLocalToRfc3339(range.start),
+      end: datetimeLocalToRfc3339(range.end),
+    };
+  };
+  return {
+    ...form,
+    filters: {
+      ...form.filters,
+      created_at: convertRange(form.filters.created_at),
+      modified_at: convertRange(form.filters.modified_at),
+      accessed_at: convertRange(form.filters.accessed_at),
+      // パターンが未入力の除外行は保存しない
+      exclude: form.filters.exclude?.some((e) => e.pattern.trim())
+        ? form.filters.exclude.filter((e) => e.pattern.trim())
+        : null,
+    },
+  };
+}
+
+/** `field` 自身か、その配下の項目（`filters.exclude` に対する `filters.exclude[0].pattern` など）の問題か */
+function isIssueOf(issue: ValidationIssue, field: string): boolean {
+  return (
+    issue.field === field ||
+    issue.field.startsWith(`${field}.`) ||
+    issue.field.startsWith(`${field}[`)
+  );
+}
+
+const DATE_FIELD_LABELS: Record<(typeof DATE_FIELDS)[number], string> = {
+  created_at: "editor.createdAt",
+  modified_at: "editor.modifiedAt",
+  accessed_at: "editor.accessedAt",
+};
+
+const CONFLICT_POLICIES: ConflictPolicy[] = [
+  "skip",
+  "overwrite",
+  "auto_rename",
+  "keep_newer",
+  "keep_larger",
+  "skip_if_identical",
+];
+
+type ScheduleKind = "none" | "cron" | "interval";
+
+function scheduleKind(schedule: Schedule | null | undefined): ScheduleKind {
+  if (!schedule) return "none";
+  return schedule.cron != null ? "cron" : "interval";
+}
+
 interface RulesetEditDialogProps {
   ruleset: Ruleset | null; // null = create new
   onSave: (ruleset: Ruleset) => Promise<void>;
@@ -47,7 +154,7 @@ function emptyRuleset(): Ruleset {
     source_dir: "",
     destination_dir: "",
     action: "move",
-    overwrite: false,
+    on_conflict: "skip",
     filters: {
       extensions: null,
       filename: null,
@@ -59,9 +166,10 @@ function emptyRuleset(): Ruleset {
 
 /** 保存済みルールセットのRFC3339日時値をフォーム表示用のdatetime-local形式に変換する */
 function toFormRuleset(ruleset: Ruleset): Ruleset {
-  const convertRange = (range: Ruleset["filters"]["created_at"]) => {
+  const convertRange = (range: DateTimeRange | null | undefined) => {
     if (!range) return null;
     return {
+      ...range,
       start: rfc3339ToDatetimeLocal(range.start),
       end: rfc3339ToDatetimeLocal(range.end),
     };
@@ -72,6 +180,7 @@ function toFormRuleset(ruleset: Ruleset): Ruleset {
       ...ruleset.filters,
       created_at: convertRange(ruleset.filters.created_at),
       modified_at: convertRange(ruleset.filters.modified_at),
+      accessed_at: convertRange(ruleset.filters.accessed_at),
     },
   };
 }
@@ -90,6 +199,7 @@ export function RulesetEditDialog({
   );
   const [extensionInput, setExtensionInput] = useState("");
   const [errors, setErrors] = useState<string[]>([]);
+  const [issues, setIssues] = useState<ValidationIssue[]>([]);
   const dateTimeRefs = useRef<Partial<Record<string, HTMLInputElement | null>>>({});
   const handleDismissErrors = useCallback(() => setErrors([]), []);
 
@@ -121,8 +231,37 @@ export function RulesetEditDialog({
     updateFilters({ extensions: updated.length > 0 ? updated : null });
   }
 
-  function hasTemplateVars(s: string): boolean {
-    return /\{[^}]+\}/.test(s);
+  function updateExclude(index: number, patch: Partial<FilenameFilter>) {
+    const current = form.filters.exclude ?? [];
+    updateFilters({
+      exclude: current.map((e, i) => (i === index ? { ...e, ...patch } : e)),
+    });
+  }
+
+  function removeExclude(index: number) {
+    const updated = (form.filters.exclude ?? []).filter((_, i) => i !== index);
+    updateFilters({ exclude: updated.length > 0 ? updated : null });
+  }
+
+  /** 組み込み変数（`{mtime:%Y}`・`{ext}` など）以外の、ファイル名フィルタのキャプチャを使う変数が含まれているか */
+  function usesCaptureVars(s: string): boolean {
+    return templateVarNames(s).some(
+      (name) =>
+        !DATE_TEMPLATE_VARS.includes(name.split(":")[0]) &&
+        !FILE_TEMPLATE_VARS.includes(name) &&
+        name.split(":")[0] !== COUNTER_TEMPLATE_VAR,
+    );
+  }
+
+  /** `{counter}`・`{counter:4}` が含まれているか */
+  function usesCounter(s: string | null | undefined): boolean {
+    return templateVarNames(s ?? "").some(
+      (name) => name.split(":")[0] === COUNTER_TEMPLATE_VAR,
+    );
+  }
+
+  function updateCounter(patch: Partial<CounterOptions>) {
+    updateField("counter", { ...DEFAULT_COUNTER, ...form.counter, ...patch });
   }
 
   function validate(): boolean {
@@ -136,23 +275,27 @@ export function RulesetEditDialog({
       (f.extensions && f.extensions.length > 0) ||
       f.filename ||
       f.created_at ||
-      f.modified_at;
+      f.modified_at ||
+      f.accessed_at ||
+      f.size ||
+      (f.all && f.all.length > 0) ||
+      (f.any && f.any.length > 0) ||
+      f.not ||
+      (f.captures && f.captures.length > 0);
     if (!hasFilter) errs.push(t("editor.validation.filterRequired"));
 
-    if (
-      hasTemplateVars(form.destination_dir) &&
-      form.filters.filename?.match_type !== "regex"
-    ) {
+    if (usesCaptureVars(form.destination_dir) && !form.filters.filename) {
       errs.push(t("editor.validation.destinationTemplateRequiresRegex"));
     }
+    if (form.rename) {
+      if (/[/\\]/.test(form.rename)) errs.push(t("editor.validation.renameSeparator"));
+      if (usesCaptureVars(form.rename) && !form.filters.filename) {
+        errs.push(t("editor.validation.renameTemplateRequiresRegex"));
+      }
+    }
 
-    const dtKeys: Array<[string, string]> = [
-      ["created_at", "start"],
-      ["created_at", "end"],
-      ["modified_at", "start"],
-      ["modified_at", "end"],
-    ];
-    if (dtKeys.some(([f, p]) => dateTimeRefs.current[`${f}_${p}`]?.validity.badInput)) {
+    const dtKeys = DATE_FIELDS.flatMap((field) => [`${field}_start`, `${field}_end`]);
+    if (dtKeys.some((key) => dateTimeRefs.current[key]?.validity.badInput)) {
       errs.push(t("editor.validation.invalidDatetime"));
     }
 
@@ -163,27 +306,63 @@ export function RulesetEditDialog({
   async function handleSave() {
     if (!validate()) return;
     try {
-      const convertRange = (range: Ruleset["filters"]["created_at"]) => {
-        if (!range) return null;
-        return {
-          start: datetimeLocalToRfc3339(range.start),
-          end: datetimeLocalToRfc3339(range.end),
-        };
-      };
-      const rulesetToSave: Ruleset = {
-        ...form,
-        filters: {
-          ...form.filters,
-          created_at: convertRange(form.filters.created_at),
-          modified_at: convertRange(form.filters.modified_at),
-        },
-      };
+      const rulesetToSave = toSavedRuleset(form);
+      // パターンのコンパイルなどバックエンドでしかできない検証を行い、問題のある項目を示す
+      const found = await validateRuleset(rulesetToSave);
+      setIssues(found);
+      const blocking = found.filter((issue) => issue.severity === "error");
+      if (blocking.length > 0) {
+        setErrors(blocking.map((issue) => issue.message));
+        return;
+      }
       await onSave(rulesetToSave);
     } catch (e) {
       setErrors([String(e)]);
     }
   }
 
+  // 問題を表示した後は、入力に合わせて検証し直して表示を更新する
+  const showsIssues = issues.length > 0;
+  useEffect(() => {
+    if (!showsIssues) return;
+    const timer = setTimeout(() => {
+      validateRuleset(toSavedRuleset(form))
+        .then(setIssues)
+        .catch(() => {});
+    }, 300);
+    return () => clearTimeout(timer);
+  }, [form, showsIssues]);
+
+  function fieldIssues(...fields: string[]): ValidationIssue[] {
+    return issues.filter((issue) => fields.some((field) => isIssueOf(issue, field)));
+  }
+
+  function hasIssue(field: string): boolean {
+    return fieldIssues(field).length > 0;
+  }
+
+  /** `fields` とその配下の項目の問題を入力欄の下に表示する */
+  function renderIssues(...fields: string[]) {
+    const found = fieldIssues(...fields);
+    if (found.length === 0) return null;
+    return (
+      <ul data-testid={`issues-${fields[0]}`} className="mt-1 space-y-0.5 text-xs">
+        {found.map((issue, i) => (
+          <li
+            key={i}
+            className={
+              issue.severity === "error"
+                ? "text-red-600 dark:text-red-400"
+                : "text-amber-600 dark:text-amber-400"
+            }
+          >
+            {issue.message}
+          </li>
+        ))}
+      </ul>
+    );
+  }
+
   const handleClose = useCallback(async () => {
     if (JSON.stringify(form) !== JSON.stringify(initialForm.current)) {
       const ok = await confirm(t("editor.discardConfirm"));
@@ -215,6 +394,10 @@ export function RulesetEditDialog({
   const inputClass =
     "w-full px-3 py-1.5 border border-slate-200 dark:border-slate-700 rounded-lg text-sm bg-white dark:bg-slate-800 text-slate-900 dark:text-slate-100 placeholder:text-slate-400 dark:placeholder:text-slate-600 focus:outline-none focus:ring-2 focus:ring-blue-500/40 dark:focus:ring-blue-400/40 focus:border-blue-400 dark:focus:border-blue-500 transition-colors";
 
+  /** 検証で問題が見つかった入力欄の枠を赤くする（`aria-invalid` と組み合わせる） */
+  const invalidClass =
+    "aria-[invalid=true]:border-red-400 dark:aria-[invalid=true]:border-red-500";
+
   const labelClass = "block text-sm font-medium text-slate-700 dark:text-slate-300 mb-1";
   const labelXsClass =
     "block text-xs font-medium text-slate-600 dark:text-slate-400 mb-1";
@@ -249,8 +432,10 @@ export function RulesetEditDialog({
                 type="text"
                 value={form.name}
                 onChange={(e) => updateField("name", e.target.value)}
-                className={inputClass}
+                aria-invalid={hasIssue("name")}
+                className={`${inputClass} ${invalidClass}`}
               />
+              {renderIssues("name")}
             </div>
 
             {/* Source / Destination */}
@@ -262,7 +447,8 @@ export function RulesetEditDialog({
                   type="text"
                   value={form.source_dir}
                   onChange={(e) => updateField("source_dir", e.target.value)}
-                  className={`${inputClass} pr-9`}
+                  aria-invalid={hasIssue("source_dir")}
+                  className={`${inputClass} ${invalidClass} pr-9`}
                 />
                 <button
                   onClick={selectSource}
@@ -283,6 +469,7 @@ export function RulesetEditDialog({
                   </svg>
                 </button>
               </div>
+              {renderIssues("source_dir")}
             </div>
 
             <div>
@@ -293,7 +480,8 @@ export function RulesetEditDialog({
                   type="text"
                   value={form.destination_dir}
                   onChange={(e) => updateField("destination_dir", e.target.value)}
-                  className={`${inputClass} pr-9`}
+                  aria-invalid={hasIssue("destination_dir")}
+                  className={`${inputClass} ${invalidClass} pr-9`}
                 />
                 <button
                   onClick={selectDest}
@@ -314,14 +502,81 @@ export function RulesetEditDialog({
                   </svg>
                 </button>
               </div>
+              {renderIssues("destination_dir")}
               {form.filters.filename?.match_type === "regex" && (
                 <p className="mt-1 text-xs text-slate-400 dark:text-slate-500">
                   {t("editor.destinationTemplateHint")}
                 </p>
               )}
+              <p className="mt-1 text-xs text-slate-400 dark:text-slate-500">
+                {t("editor.destinationBuiltinHint")}
+              </p>
+              <p className="mt-1 text-xs text-slate-400 dark:text-slate-500">
+                {t("editor.destinationFilterHint")}
+              </p>
             </div>
 
-            {/* Action + Overwrite */}
+            <div>
+              <label className={labelClass}>{t("editor.rename")}</label>
+              <input
+                data-testid="field-rename"
+                type="text"
+                value={form.rename ?? ""}
+                onChange={(e) => updateField("rename", e.target.value || null)}
+                placeholder="{mtime:%Y-%m-%d}_{stem}.{ext}"
+                aria-invalid={hasIssue("rename")}
+                className={`${inputClass} ${invalidClass} font-mono`}
+              />
+              {renderIssues("rename")}
+              <p className="mt-1 text-xs text-slate-400 dark:text-slate-500">
+                {t("editor.renameHint")}
+              </p>
+            </div>
+
+            {(usesCounter(form.destination_dir) || usesCounter(form.rename)) && (
+              <div>
+                <label className={labelClass}>{t("editor.counter")}</label>
+                <div className="flex items-center gap-4">
+                  <select
+                    data-testid="select-counter-sort"
+                    value={form.counter?.sort ?? DEFAULT_COUNTER.sort}
+                    onChange={(e) => updateCounter({ sort: e.target.value as CounterSort })}
+                    className={`${inputClass} w-40 flex-none`}
+                  >
+                    {COUNTER_SORTS.map((sort) => (
+                      <option key={sort} value={sort}>
+                        {t(`editor.counterSort.${sort}`)}
+                      </option>
+                    ))}
+                  </select>
+                  <label className="flex items-center gap-1.5 text-sm text-slate-700 dark:text-slate-300 cursor-pointer">
+                    <input
+                      data-testid="checkbox-counter-descending"
+                      type="checkbox"
+                      checked={form.counter?.descending ?? false}
+                      onChange={(e) => updateCounter({ descending: e.target.checked })}
+                      className="accent-blue-600 dark:accent-blue-400"
+                    />
+                    {t("editor.counterDescending")}
+                  </label>
+                  <label className="flex items-center gap-1.5 text-sm text-slate-700 dark:text-slate-300 cursor-pointer">
+                    <input
+                      data-testid="checkbox-counter-resume"
+                      type="checkbox"
+                      checked={form.counter?.resume ?? false}
+                      onChange={(e) => updateCounter({ resume: e.target.checked })}
+                      className="accent-blue-600 dark:accent-blue-400"
+                    />
+                    {t("editor.counterResume")}
+                  </label>
+                </div>
+                <p className="mt-1 text-xs text-slate-400 dark:text-slate-500">
+                  {t("editor.counterHint")}
+                </p>
+              </div>
+            )}
+
+            {/* Action + Conflict policy */}
             <div className="flex items-center gap-6">
               <div>
                 <label className={labelClass}>{t("editor.action")}</label>
@@ -346,15 +601,78 @@ export function RulesetEditDialog({
                   </label>
                 </div>
               </div>
-              <label className="flex items-center gap-2 text-sm text-slate-700 dark:text-slate-300 mt-5 cursor-pointer">
-                <input
-                  type="checkbox"
-                  checked={form.overwrite}
-                  onChange={(e) => updateField("overwrite", e.target.checked)}
-                  className="accent-blue-600 dark:accent-blue-400"
-                />
-                {t("editor.overwrite")}
-              </label>
+              <div className="flex-1">
+                <label className={labelClass}>{t("editor.onConflict")}</label>
+                <select
+                  data-testid="select-on-conflict"
+                  value={form.on_conflict}
+                  onChange={(e) => updateField("on_conflict", e.target.value as ConflictPolicy)}
+                  className={inputClass}
+                >
+                  {CONFLICT_POLICIES.map((policy) => (
+                    <option key={policy} value={policy}>
+                      {t(`editor.conflictPolicy.${policy}`)}
+                    </option>
+                  ))}
+                </select>
+              </div>
+            </div>
+
+            {/* Watch */}
+            <label className="flex items-center gap-1.5 text-sm text-slate-700 dark:text-slate-300 cursor-pointer">
+              <input
+                data-testid="checkbox-watch"
+                type="checkbox"
+                checked={form.watch ?? false}
+                onChange={(e) => updateField("watch", e.target.checked)}
+                className="accent-blue-600 dark:accent-blue-400"
+              />
+              {t("editor.watch")}
+            </label>
+
+            {/* Schedule */}
+            <div>
+              <label className={labelClass}>{t("editor.schedule")}</label>
+              <div className="flex gap-2">
+                <select
+                  data-testid="select-schedule"
+                  value={scheduleKind(form.schedule)}
+                  onChange={(e) => {
+                    const kind = e.target.value as ScheduleKind;
+                    if (kind === "none") updateField("schedule", null);
+                    else if (kind === "cron") updateField("schedule", { cron: "0 3 * * 1" });
+                    else updateField("schedule", { interval: "7d" });
+                  }}
+                  className={`${inputClass} w-40 flex-none`}
+                >
+                  <option value="none">{t("editor.scheduleKind.none")}</option>
+                  <option value="cron">{t("editor.scheduleKind.cron")}</option>
+                  <option value="interval">{t("editor.scheduleKind.interval")}</option>
+                </select>
+                {form.schedule && (
+                  <input
+                    data-testid="field-schedule"
+                    type="text"
+                    value={form.schedule.cron ?? form.schedule.interval ?? ""}
+                    onChange={(e) =>
+                      updateField(
+                        "schedule",
+                        scheduleKind(form.schedule) === "cron"
+                          ? { cron: e.target.value }
+                          : { interval: e.target.value },
+                      )
+                    }
+                    aria-invalid={hasIssue("schedule")}
+                    className={`${inputClass} ${invalidClass} font-mono`}
+                  />
+                )}
+              </div>
+              {renderIssues("schedule")}
+              {form.schedule && (
+                <p className="mt-1 text-xs text-slate-400 dark:text-slate-500">
+                  {t(`editor.scheduleHint.${scheduleKind(form.schedule)}`)}
+                </p>
+              )}
             </div>
 
             {/* Filters */}
@@ -363,14 +681,29 @@ export function RulesetEditDialog({
                 {t("editor.filters")}
               </h3>
 
+              {/* all / any / not のグループと captures はエディタでは編集せず、保存時にそのまま保持する */}
+              {(form.filters.all ||
+                form.filters.any ||
+                form.filters.not ||
+                form.filters.captures) && (
+                <p
+                  data-testid="filter-groups-note"
+                  className="mb-4 text-xs text-slate-500 dark:text-slate-400"
+                >
+                  {t("editor.filterGroupsNote")}
+                </p>
+              )}
+              {renderIssues("filters.all", "filters.any", "filters.not", "filters.captures")}
+
               {/* Extensions */}
               <div className="mb-4">
                 <label className={labelXsClass}>{t("editor.extensions")}</label>
                 <div className="flex flex-wrap gap-1.5 mb-2">
-                  {(form.filters.extensions ?? []).map((ext) => (
+                  {(form.filters.extensions ?? []).map((ext, index) => (
                     <span
                       key={ext}
-                      className="inline-flex items-center gap-1 bg-slate-100 dark:bg-slate-800 text-slate-700 dark:text-slate-300 px-2 py-0.5 rounded-full text-xs font-mono"
+                      aria-invalid={hasIssue(`filters.extensions[${index}]`)}
+                      className="inline-flex items-center gap-1 bg-slate-100 dark:bg-slate-800 text-slate-700 dark:text-slate-300 px-2 py-0.5 rounded-full text-xs font-mono border border-transparent aria-[invalid=true]:border-red-400 dark:aria-[invalid=true]:border-red-500"
                     >
                       {ext}
                       <button
@@ -402,6 +735,7 @@ export function RulesetEditDialog({
                     {t("editor.extensionAdd")}
                   </button>
                 </div>
+                {renderIssues("filters.extensions")}
               </div>
 
               {/* Filename pattern */}
@@ -463,8 +797,11 @@ export function RulesetEditDialog({
                     }
                   }}
                   placeholder={t("editor.pattern")}
-                  className="w-full px-2 py-1 border border-slate-200 dark:border-slate-700 rounded-lg text-xs bg-white dark:bg-slate-800 text-slate-900 dark:text-slate-100 placeholder:text-slate-400 dark:placeholder:text-slate-600 focus:outline-none focus:ring-2 focus:ring-blue-500/40 dark:focus:ring-blue-400/40 focus:border-blue-400 dark:focus:border-blue-500 font-mono transition-colors"
+                  data-testid="field-filename-pattern"
+                  aria-invalid={hasIssue("filters.filename")}
+                  className={`w-full px-2 py-1 border border-slate-200 dark:border-slate-700 rounded-lg text-xs bg-white dark:bg-slate-800 text-slate-900 dark:text-slate-100 placeholder:text-slate-400 dark:placeholder:text-slate-600 focus:outline-none focus:ring-2 focus:ring-blue-500/40 dark:focus:ring-blue-400/40 focus:border-blue-400 dark:focus:border-blue-500 font-mono transition-colors ${invalidClass}`}
                 />
+                {renderIssues("filters.filename")}
                 {form.filters.filename?.match_type === "regex" && (
                   <RegexTesterPanel
                     pattern={form.filters.filename.pattern}
@@ -474,12 +811,98 @@ export function RulesetEditDialog({
                 )}
               </div>
 
+              {/* Exclude patterns */}
+              <div className="mb-4">
+                <label className={labelXsClass}>{t("editor.exclude")}</label>
+                {(form.filters.exclude ?? []).map((exclude, index) => (
+                  <div key={index} className="flex gap-1.5 mb-1.5">
+                    <select
+                      data-testid={`select-exclude-type-${index}`}
+                      value={exclude.match_type}
+                      onChange={(e) =>
+                        updateExclude(index, { match_type: e.target.value as MatchType })
+                      }
+                      className="px-2 py-1 border border-slate-200 dark:border-slate-700 rounded-lg text-xs bg-white dark:bg-slate-800 text-slate-700 dark:text-slate-300 focus:outline-none focus:ring-2 focus:ring-blue-500/40 dark:focus:ring-blue-400/40 transition-colors"
+                    >
+                      <option value="glob">{t("editor.matchTypeGlob")}</option>
+                      <option value="regex">{t("editor.matchTypeRegex")}</option>
+                    </select>
+                    <input
+                      data-testid={`field-exclude-${index}`}
+                      type="text"
+                      value={exclude.pattern}
+                      onChange={(e) => updateExclude(index, { pattern: e.target.value })}
+                      placeholder={t("editor.pattern")}
+                      aria-invalid={hasIssue(`filters.exclude[${index}]`)}
+                      className={`flex-1 px-2 py-1 border border-slate-200 dark:border-slate-700 rounded-lg text-xs bg-white dark:bg-slate-800 text-slate-900 dark:text-slate-100 placeholder:text-slate-400 dark:placeholder:text-slate-600 focus:outline-none focus:ring-2 focus:ring-blue-500/40 dark:focus:ring-blue-400/40 focus:border-blue-400 dark:focus:border-blue-500 font-mono transition-colors ${invalidClass}`}
+                    />
+                    <button
+                      onClick={() => removeExclude(index)}
+                      className="px-1.5 text-slate-400 hover:text-red-500 dark:text-slate-500 dark:hover:text-red-400 transition-colors"
+                    >
+                      ×
+                    </button>
+                  </div>
+                ))}
+                <button
+                  data-testid="btn-exclude-add"
+                  onClick={() =>
+                    updateFilters({
+                      exclude: [
+                        ...(form.filters.exclude ?? []),
+                        { pattern: "", match_type: "glob" },
+                      ],
+                    })
+                  }
+                  className="px-2.5 py-1 border border-slate-200 dark:border-slate-700 rounded-lg text-xs text-slate-600 dark:text-slate-400 hover:bg-slate-50 dark:hover:bg-slate-800 transition-colors"
+                >
+                  {t("editor.excludeAdd")}
+                </button>
+                {renderIssues("filters.exclude")}
+                <p className="mt-1 text-xs text-slate-400 dark:text-slate-500">
+                  {t("editor.excludeHint")}
+                </p>
+              </div>
+
+              {/* Size range */}
+              <div className="mb-4">
+                <label className={labelXsClass}>{t("editor.size")}</label>
+                <div className="flex gap-2">
+                  {(["min", "max"] as const).map((bound) => (
+                    <div key={bound} className="flex-1">
+                      <span className="text-xs text-slate-400 dark:text-slate-500">
+                        {t(bound === "min" ? "editor.sizeMin" : "editor.sizeMax")}
+                      </span>
+                      <input
+                        data-testid={`field-size-${bound}`}
+                        type="text"
+                        value={form.filters.size?.[bound] ?? ""}
+                        onChange={(e) => {
+                          const val = e.target.value.trim() || null;
+                          const other = bound === "min" ? "max" : "min";
+                          const otherVal = form.filters.size?.[other] ?? null;
+                          if (!val && !otherVal) {
+                            updateFilters({ size: null });
+                          } else {
+                            updateFilters({
+                              size: { [bound]: val, [other]: otherVal } as SizeRange,
+                            });
+                          }
+                        }}
+                        placeholder={t("editor.sizePlaceholder")}
+                        aria-invalid={hasIssue("filters.size")}
+                        className={`w-full px-2 py-1 border border-slate-200 dark:border-slate-700 rounded-lg text-xs bg-white dark:bg-slate-800 text-slate-900 dark:text-slate-100 placeholder:text-slate-400 dark:placeholder:text-slate-600 focus:outline-none focus:ring-2 focus:ring-blue-500/40 dark:focus:ring-blue-400/40 focus:border-blue-400 dark:focus:border-blue-500 font-mono transition-colors ${invalidClass}`}
+                      />
+                    </div>
+                  ))}
+                </div>
+                {renderIssues("filters.size")}
+              </div>
+
               {/* Date ranges */}
-              {(["created_at", "modified_at"] as const).map((field) => (
+              {DATE_FIELDS.map((field) => (
                 <div key={field} className="mb-4">
-                  <label className={labelXsClass}>
-                    {t(field === "created_at" ? "editor.createdAt" : "editor.modifiedAt")}
-                  </label>
+                  <label className={labelXsClass}>{t(DATE_FIELD_LABELS[field])}</label>
                   <div className="flex gap-2">
                     <div className="flex-1">
                       <span className="text-xs text-slate-400 dark:text-slate-500">
@@ -494,14 +917,7 @@ export function RulesetEditDialog({
                           value={form.filters[field]?.start ?? ""}
                           onChange={(e) => {
                             const val = e.target.value || null;
-                            const current = form.filters[field];
-                            if (!val && !current?.end) {
-                              updateFilters({ [field]: null });
-                            } else {
-                              updateFilters({
-                                [field]: { start: val, end: current?.end ?? null },
-                              });
-                            }
+                            updateFilters({ [field]: updateRange(form.filters[field], { start: val }) });
                           }}
                           className="w-full px-2 py-1 border border-slate-200 dark:border-slate-700 rounded-lg text-xs bg-white dark:bg-slate-800 text-slate-900 dark:text-slate-100 focus:outline-none focus:ring-2 focus:ring-blue-500/40 dark:focus:ring-blue-400/40 focus:border-blue-400 dark:focus:border-blue-500 transition-colors"
                         />
@@ -511,9 +927,8 @@ export function RulesetEditDialog({
                             onClick={() => {
                               const refEl = dateTimeRefs.current[`${field}_start`];
                               if (refEl) refEl.value = "";
-                              const end = form.filters[field]?.end ?? null;
                               updateFilters({
-                                [field]: end ? { start: null, end } : null,
+                                [field]: updateRange(form.filters[field], { start: null }),
                               });
                             }}
                             className="absolute right-6 top-1/2 -translate-y-1/2 w-4 h-4 flex items-center justify-center text-slate-400 hover:text-red-500 dark:text-slate-500 dark:hover:text-red-400 transition-colors text-xs leading-none"
@@ -536,14 +951,7 @@ export function RulesetEditDialog({
                           value={form.filters[field]?.end ?? ""}
                           onChange={(e) => {
                             const val = e.target.value || null;
-                            const current = form.filters[field];
-                            if (!val && !current?.start) {
-                              updateFilters({ [field]: null });
-                            } else {
-                              updateFilters({
-                                [field]: { start: current?.start ?? null, end: val },
-                              });
-                            }
+                            updateFilters({ [field]: updateRange(form.filters[field], { end: val }) });
                           }}
                           className="w-full px-2 py-1 border border-slate-200 dark:border-slate-700 rounded-lg text-xs bg-white dark:bg-slate-800 text-slate-900 dark:text-slate-100 focus:outline-none focus:ring-2 focus:ring-blue-500/40 dark:focus:ring-blue-400/40 focus:border-blue-400 dark:focus:border-blue-500 transition-colors"
                         />
@@ -553,9 +961,8 @@ export function RulesetEditDialog({
                             onClick={() => {
                               const refEl = dateTimeRefs.current[`${field}_end`];
                               if (refEl) refEl.value = "";
-                              const start = form.filters[field]?.start ?? null;
                               updateFilters({
-                                [field]: start ? { start, end: null } : null,
+                                [field]: updateRange(form.filters[field], { end: null }),
                               });
                             }}
                             className="absolute right-6 top-1/2 -translate-y-1/2 w-4 h-4 flex items-center justify-center text-slate-400 hover:text-red-500 dark:text-slate-500 dark:hover:text-red-400 transition-colors text-xs leading-none"
@@ -566,6 +973,30 @@ export function RulesetEditDialog({
                       </div>
                     </div>
                   </div>
+                  <div className="flex gap-2 mt-1">
+                    {(["older_than", "newer_than"] as const).map((relative) => (
+                      <div key={relative} className="flex-1">
+                        <span className="text-xs text-slate-400 dark:text-slate-500">
+                          {t(relative === "older_than" ? "editor.olderThan" : "editor.newerThan")}
+                        </span>
+                        <input
+                          data-testid={`field-${field}-${relative}`}
+                          type="text"
+                          value={form.filters[field]?.[relative] ?? ""}
+                          onChange={(e) => {
+                            const val = e.target.value.trim() || null;
+                            updateFilters({
+                              [field]: updateRange(form.filters[field], { [relative]: val }),
+                            });
+                          }}
+                          placeholder={t("editor.relativePlaceholder")}
+                          aria-invalid={hasIssue(`filters.${field}.${relative}`)}
+                          className={`w-full px-2 py-1 border border-slate-200 dark:border-slate-700 rounded-lg text-xs bg-white dark:bg-slate-800 text-slate-900 dark:text-slate-100 placeholder:text-slate-400 dark:placeholder:text-slate-600 focus:outline-none focus:ring-2 focus:ring-blue-500/40 dark:focus:ring-blue-400/40 focus:border-blue-400 dark:focus:border-blue-500 font-mono transition-colors ${invalidClass}`}
+                        />
+                      </div>
+                    ))}
+                  </div>
+                  {renderIssues(`filters.${field}`)}
                 </div>
               ))}
             </div>
@@ -26,6 +26,7 @@ interface RulesetListProps {
   onEdit: (ruleset: Ruleset) => void;
   onDelete: (id: string) => void;
   onDuplicate: (id: string) => void;
+  onPreview: (id: string) => void;
   onReorder: (ids: string[]) => void;
   executing: boolean;
 }
@@ -76,6 +77,7 @@ export function RulesetList({
   onEdit,
   onDelete,
   onDuplicate,
+  onPreview,
   onReorder,
   executing,
 }: RulesetListProps) {
@@ -121,6 +123,7 @@ export function RulesetList({
               onEdit={onEdit}
               onDelete={onDelete}
               onDuplicate={onDuplicate}
+              onPreview={onPreview}
               executing={executing}
             />
           ))}
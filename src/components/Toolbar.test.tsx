@@ -8,8 +8,10 @@ function renderToolbar(overrides?: { executing?: boolean }) {
   const props = {
     onCreateNew: vi.fn(),
     onExecuteAll: vi.fn(),
+    onPreviewAll: vi.fn(),
     onImport: vi.fn(),
     onExport: vi.fn(),
+    onShowHistory: vi.fn(),
     executing: false,
     ...overrides,
   };
@@ -35,6 +37,12 @@ describe("Toolbar", () => {
     expect(screen.getByTestId("toolbar-execute-all")).toBeDisabled();
   });
 
+  it("一括プレビューボタンをクリックすると onPreviewAll が呼ばれる", async () => {
+    const { onPreviewAll } = renderToolbar();
+    await userEvent.click(screen.getByTestId("toolbar-preview-all"));
+    expect(onPreviewAll).toHaveBeenCalledOnce();
+  });
+
   it("インポートボタンをクリックすると onImport が呼ばれる", async () => {
     const { onImport } = renderToolbar();
     await userEvent.click(screen.getByTestId("toolbar-import"));
@@ -46,4 +54,10 @@ describe("Toolbar", () => {
     await userEvent.click(screen.getByTestId("toolbar-export"));
     expect(onExport).toHaveBeenCalledOnce();
   });
+
+  it("履歴ボタンをクリックすると onShowHistory が呼ばれる", async () => {
+    const { onShowHistory } = renderToolbar();
+    await userEvent.click(screen.getByTestId("toolbar-history"));
+    expect(onShowHistory).toHaveBeenCalledOnce();
+  });
 });
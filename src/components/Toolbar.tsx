@@ -3,8 +3,10 @@ import { useTranslation } from "react-i18next";
 interface ToolbarProps {
   onCreateNew: () => void;
   onExecuteAll: () => void;
+  onPreviewAll: () => void;
   onImport: () => void;
   onExport: () => void;
+  onShowHistory: () => void;
   executing: boolean;
   darkMode?: boolean;
   onToggleDarkMode?: () => void;
@@ -13,8 +15,10 @@ interface ToolbarProps {
 export function Toolbar({
   onCreateNew,
   onExecuteAll,
+  onPreviewAll,
   onImport,
   onExport,
+  onShowHistory,
   executing,
   darkMode,
   onToggleDarkMode,
@@ -75,6 +79,33 @@ export function Toolbar({
         {t("toolbar.executeAll")}
       </button>
 
+      <button
+        data-testid="toolbar-preview-all"
+        onClick={onPreviewAll}
+        className="inline-flex items-center gap-1.5 px-3 py-1.5 border border-slate-200 dark:border-slate-700 hover:bg-slate-50 dark:hover:bg-slate-800 rounded-lg text-sm text-slate-600 dark:text-slate-300 transition-colors duration-150"
+      >
+        <svg
+          className="w-3.5 h-3.5"
+          fill="none"
+          stroke="currentColor"
+          viewBox="0 0 24 24"
+        >
+          <path
+            strokeLinecap="round"
+            strokeLinejoin="round"
+            strokeWidth={2}
+            d="M15 12a3 3 0 11-6 0 3 3 0 016 0z"
+          />
+          <path
+            strokeLinecap="round"
+            strokeLinejoin="round"
+            strokeWidth={2}
+            d="M2.458 12C3.732 7.943 7.523 5 12 5c4.478 0 8.268 2.943 9.542 7-1.274 4.057-5.064 7-9.542 7-4.477 0-8.268-2.943-9.542-7z"
+          />
+        </svg>
+        {t("toolbar.previewAll")}
+      </button>
+
       <div className="flex-1" />
 
       <button
@@ -119,6 +150,27 @@ export function Toolbar({
         {t("toolbar.export")}
       </button>
 
+      <button
+        data-testid="toolbar-history"
+        onClick={onShowHistory}
+        className="inline-flex items-center gap-1.5 px-3 py-1.5 border border-slate-200 dark:border-slate-700 hover:bg-slate-50 dark:hover:bg-slate-800 rounded-lg text-sm text-slate-600 dark:text-slate-300 transition-colors duration-150"
+      >
+        <svg
+          className="w-3.5 h-3.5"
+          fill="none"
+          stroke="currentColor"
+          viewBox="0 0 24 24"
+        >
+          <path
+            strokeLinecap="round"
+            strokeLinejoin="round"
+            strokeWidth={2}
+            d="M12 8v4l3 3m6-3a9 9 0 11-18 0 9 9 0 0118 0z"
+          />
+        </svg>
+        {t("toolbar.history")}
+      </button>
+
       {onToggleDarkMode && (
         <button
           onClick={onToggleDarkMode}
@@ -1,5 +1,12 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { Ruleset, ExecutionResult, UndoRequest } from "./types";
+import type {
+  Ruleset,
+  ExecutionResult,
+  ExecutionPlan,
+  RunRecord,
+  UndoRequest,
+  ValidationIssue,
+} from "./types";
 
 export async function getRulesets(): Promise<Ruleset[]> {
   return invoke<Ruleset[]>("get_rulesets");
@@ -9,6 +16,10 @@ export async function saveRuleset(ruleset: Ruleset): Promise<string> {
   return invoke<string>("save_ruleset", { ruleset });
 }
 
+export async function validateRuleset(ruleset: Ruleset): Promise<ValidationIssue[]> {
+  return invoke<ValidationIssue[]>("validate_ruleset", { ruleset });
+}
+
 export async function deleteRuleset(id: string): Promise<void> {
   return invoke("delete_ruleset", { id });
 }
@@ -25,6 +36,14 @@ export async function executeAll(): Promise<ExecutionResult[]> {
   return invoke<ExecutionResult[]>("execute_all");
 }
 
+export async function previewRuleset(id: string): Promise<ExecutionPlan> {
+  return invoke<ExecutionPlan>("preview_ruleset", { id });
+}
+
+export async function previewAll(): Promise<ExecutionPlan[]> {
+  return invoke<ExecutionPlan[]>("preview_all");
+}
+
 export async function cancelExecution(): Promise<void> {
   return invoke("cancel_execution");
 }
@@ -39,6 +58,24 @@ export async function undoAll(
   return invoke("undo_all", { files });
 }
 
+export async function listHistory(): Promise<RunRecord[]> {
+  return invoke<RunRecord[]>("list_history");
+}
+
+export async function undoHistoryRun(
+  runId: string,
+): Promise<Array<{ Ok: null } | { Err: string }>> {
+  return invoke("undo_history_run", { runId });
+}
+
+export async function undoHistoryFile(runId: string, source: string): Promise<void> {
+  return invoke("undo_history_file", { runId, source });
+}
+
+export async function takeBackgroundErrors(): Promise<string[]> {
+  return invoke<string[]>("take_background_errors");
+}
+
 export async function importRulesets(path: string): Promise<Ruleset[]> {
   return invoke<Ruleset[]>("import_rulesets", { path });
 }
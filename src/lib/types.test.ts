@@ -17,7 +17,7 @@ describe("Type definitions", () => {
       source_dir: "/src",
       destination_dir: "/dst",
       action: "move",
-      overwrite: false,
+      on_conflict: "skip",
       filters,
     };
 
@@ -35,7 +35,7 @@ describe("Type definitions", () => {
       source_dir: "/src",
       destination_dir: "/dst",
       action: "copy",
-      overwrite: true,
+      on_conflict: "overwrite",
       filters: {
         extensions: [".log"],
         filename: null,
@@ -45,6 +45,6 @@ describe("Type definitions", () => {
     };
 
     expect(ruleset.action).toBe("copy");
-    expect(ruleset.overwrite).toBe(true);
+    expect(ruleset.on_conflict).toBe("overwrite");
   });
 });
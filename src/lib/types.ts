@@ -2,6 +2,14 @@ export type Action = "move" | "copy";
 
 export type MatchType = "glob" | "regex";
 
+export type ConflictPolicy =
+  | "skip"
+  | "overwrite"
+  | "auto_rename"
+  | "keep_newer"
+  | "keep_larger"
+  | "skip_if_identical";
+
 export interface FilenameFilter {
   pattern: string;
   match_type: MatchType;
@@ -10,6 +18,16 @@ export interface FilenameFilter {
 export interface DateTimeRange {
   start: string | null;
   end: string | null;
+  /** 実行時刻からこの期間以上前（"30d"・"12h" など） */
+  older_than?: string | null;
+  /** 実行時刻からこの期間以内 */
+  newer_than?: string | null;
+}
+
+/** "10MB"・"1.5 GiB" のような単位付きサイズ（両端を含む） */
+export interface SizeRange {
+  min: string | null;
+  max: string | null;
 }
 
 export interface Filters {
@@ -17,6 +35,54 @@ export interface Filters {
   filename: FilenameFilter | null;
   created_at: DateTimeRange | null;
   modified_at: DateTimeRange | null;
+  accessed_at?: DateTimeRange | null;
+  size?: SizeRange | null;
+  /** ファイル名がいずれかに一致するファイルは対象外とする */
+  exclude?: FilenameFilter[] | null;
+  /** すべての条件を満たす */
+  all?: Filters[] | null;
+  /** いずれかの条件を満たす */
+  any?: Filters[] | null;
+  /** 条件を満たさない */
+  not?: Filters | null;
+  /** ファイル名フィルタのキャプチャの値に対する条件 */
+  captures?: CaptureCondition[] | null;
+}
+
+/** キャプチャ（正規表現の名前付きグループ・glob のワイルドカード）の値に対する条件 */
+export interface CaptureCondition {
+  name: string;
+  equals?: string | null;
+  in?: string[] | null;
+  min?: number | null;
+  max?: number | null;
+  matches?: string | null;
+}
+
+export type DestinationLayout = "flatten" | "preserve";
+
+export interface ScanOptions {
+  recursive: boolean;
+  max_depth: number | null;
+  follow_symlinks: boolean;
+  layout: DestinationLayout;
+  count_total?: boolean;
+}
+
+export interface Schedule {
+  cron?: string | null;
+  interval?: string | null;
+}
+
+export type CounterSort = "name" | "modified" | "created" | "size";
+
+/** テンプレート変数 `{counter}` の採番方法 */
+export interface CounterOptions {
+  sort: CounterSort;
+  descending: boolean;
+  start: number;
+  /** 保存先に既にある番号の続きから採番する */
+  resume: boolean;
 }
 
 export interface Ruleset {
@@ -26,17 +92,40 @@ export interface Ruleset {
   source_dir: string;
   destination_dir: string;
   action: Action;
-  overwrite: boolean;
+  on_conflict: ConflictPolicy;
+  /** 別名保存時の名前。{stem}・{n}・{dotext}（ドット付きの拡張子）を使用できる。未指定の場合は "{stem} ({n}){dotext}" */
+  rename_pattern?: string | null;
+  /** 移動・コピー先でのファイル名のテンプレート。未指定の場合は元のファイル名のまま */
+  rename?: string | null;
+  counter?: CounterOptions;
   filters: Filters;
+  scan?: ScanOptions;
+  watch?: boolean;
+  schedule?: Schedule | null;
+  /** 同時に移動・コピーするファイル数。未指定の場合は 1 件ずつ処理する */
+  parallelism?: number | null;
+}
+
+export type Severity = "error" | "warning";
+
+/** ルールセットの検証で見つかった問題。`field` は `filters.all[0].filename.pattern` のような項目のパス */
+export interface ValidationIssue {
+  field: string;
+  severity: Severity;
+  code: string;
+  message: string;
 }
 
 export type ExecutionStatus = "Completed" | "PartialFailure" | "Failed";
 
+export type ConflictResolution = "Skipped" | "Overwritten" | "Renamed";
+
 export interface FileResult {
   filename: string;
   source_path: string;
   destination_path: string | null;
   reason: string | null;
+  conflict: ConflictResolution | null;
 }
 
 export interface ExecutionResult {
@@ -49,6 +138,40 @@ export interface ExecutionResult {
   errors: FileResult[];
 }
 
+export type PlannedAction = "Move" | "Copy" | "Skip" | "Error";
+
+export interface PlannedOperation {
+  filename: string;
+  source_path: string;
+  destination_path: string | null;
+  action: PlannedAction;
+  reason: string | null;
+  conflict: ConflictResolution | null;
+}
+
+export interface ExecutionPlan {
+  ruleset_id: string;
+  ruleset_name: string;
+  action: Action;
+  operations: PlannedOperation[];
+}
+
+export interface JournalFile {
+  filename: string;
+  source_path: string;
+  destination_path: string;
+  undone: boolean;
+}
+
+export interface RunRecord {
+  run_id: string;
+  timestamp: string;
+  ruleset_id: string;
+  ruleset_name: string;
+  action: Action;
+  files: JournalFile[];
+}
+
 export interface UndoRequest {
   source_path: string;
   destination_path: string;
@@ -7,7 +7,7 @@ export const defaultRuleset: Ruleset = {
   source_dir: "C:\\Users\\test\\Downloads",
   destination_dir: "C:\\Users\\test\\Documents",
   action: "move",
-  overwrite: false,
+  on_conflict: "skip",
   filters: {
     extensions: [".jpg", ".png"],
     filename: null,
@@ -41,6 +41,7 @@ export const defaultExecutionResult: ExecutionResult = {
       source_path: "C:\\Users\\test\\Downloads\\photo.jpg",
       destination_path: "C:\\Users\\test\\Documents\\photo.jpg",
       reason: null,
+      conflict: null,
     },
   ],
   skipped: [],